@@ -0,0 +1,911 @@
+//! Zero-copy parsing. The nodes in this module borrow their names and types
+//! from the input instead of allocating a `String` for every identifier, which
+//! keeps parsing large definitions cheap: a field of a plain type such as
+//! `u32` costs no allocation, while nested types still allocate the boxes and
+//! lists holding their parts. The nodes cover trait, struct and enum
+//! definitions; use [`AstNodeRef::to_owned`] to convert into an [`AstNode`].
+//! Bounds, attributes and doc comments are kept as written. Bounds and
+//! attributes are checked while parsing, so that the conversion cannot fail,
+//! but only built into owned nodes by it.
+
+use crate::{
+    after_keyword, bound_colon, find_closing, is_param_pattern, is_receiver, join_docs,
+    receiver_name, split_default, split_items, split_raw_pointer, split_reference, split_top_level,
+    split_variant, split_where_clause, take_docs, AstNode, Attribute, Bound, EnumNode, FieldNode,
+    GenericParam, MethodNode, ParamNode, ParseError, ParseOptions, Parser, StructNode, TraitNode,
+    TypeNode, VariantNode, Visibility, WherePredicate,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum AstNodeRef<'a> {
+    Trait(TraitNodeRef<'a>),
+    Struct(StructNodeRef<'a>),
+    Enum(EnumNodeRef<'a>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TraitNodeRef<'a> {
+    pub visibility: Visibility,
+    pub name: &'a str,
+    pub generics: Vec<GenericParamRef<'a>>,
+    /// The supertrait bounds as written, such as `Clone` or `'a`.
+    pub supertraits: Vec<&'a str>,
+    pub where_clause: Vec<WherePredicateRef<'a>>,
+    pub methods: Vec<MethodNodeRef<'a>>,
+    /// The contents of the outer attributes, without `#[` and `]`.
+    pub attributes: Vec<&'a str>,
+    /// The doc comments as written, including their `///` or `/**` markers.
+    pub docs: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StructNodeRef<'a> {
+    pub visibility: Visibility,
+    pub name: &'a str,
+    pub generics: Vec<GenericParamRef<'a>>,
+    pub where_clause: Vec<WherePredicateRef<'a>>,
+    pub fields: Vec<FieldNodeRef<'a>>,
+    pub attributes: Vec<&'a str>,
+    pub docs: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct EnumNodeRef<'a> {
+    pub visibility: Visibility,
+    pub name: &'a str,
+    pub generics: Vec<GenericParamRef<'a>>,
+    pub where_clause: Vec<WherePredicateRef<'a>>,
+    pub variants: Vec<VariantNodeRef<'a>>,
+    pub attributes: Vec<&'a str>,
+    pub docs: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MethodNodeRef<'a> {
+    pub visibility: Visibility,
+    pub name: &'a str,
+    pub generics: Vec<GenericParamRef<'a>>,
+    pub params: Vec<ParamNodeRef<'a>>,
+    pub return_type: Option<TypeNodeRef<'a>>,
+    pub where_clause: Vec<WherePredicateRef<'a>>,
+    pub is_async: bool,
+    pub has_body: bool,
+}
+
+/// A parameter. Shorthand receivers keep their name as written, such as
+/// `&'a mut self`.
+#[derive(Debug, PartialEq)]
+pub struct ParamNodeRef<'a> {
+    pub name: &'a str,
+    pub param_type: TypeNodeRef<'a>,
+}
+
+/// A field of a struct or of an enum variant. Tuple fields are unnamed.
+#[derive(Debug, PartialEq)]
+pub struct FieldNodeRef<'a> {
+    pub visibility: Visibility,
+    pub name: Option<&'a str>,
+    pub field_type: TypeNodeRef<'a>,
+    pub attributes: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct VariantNodeRef<'a> {
+    pub name: &'a str,
+    pub fields: Option<Vec<FieldNodeRef<'a>>>,
+    pub discriminant: Option<&'a str>,
+    pub attributes: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum GenericParamRef<'a> {
+    Lifetime {
+        name: &'a str,
+        bounds: Vec<&'a str>,
+    },
+    Type {
+        name: &'a str,
+        bounds: Vec<&'a str>,
+        default: Option<TypeNodeRef<'a>>,
+    },
+    Const {
+        name: &'a str,
+        ty: TypeNodeRef<'a>,
+    },
+}
+
+/// A `where` predicate with its bounds as written.
+#[derive(Debug, PartialEq)]
+pub struct WherePredicateRef<'a> {
+    pub subject: TypeNodeRef<'a>,
+    pub bounds: Vec<&'a str>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TypeNodeRef<'a> {
+    Simple(&'a str),
+    /// `&T`, `&mut T` or either with a lifetime, such as `&'a T`.
+    Reference {
+        lifetime: Option<&'a str>,
+        mutable: bool,
+        inner: Box<TypeNodeRef<'a>>,
+    },
+    /// `*const T` or `*mut T`.
+    RawPointer {
+        mutable: bool,
+        inner: Box<TypeNodeRef<'a>>,
+    },
+    Generic {
+        name: &'a str,
+        args: Vec<TypeNodeRef<'a>>,
+    },
+    Tuple(Vec<TypeNodeRef<'a>>),
+    /// `impl` with its bounds as written.
+    ImplTrait(Vec<&'a str>),
+    /// `dyn` with its bounds as written.
+    DynTrait(Vec<&'a str>),
+}
+
+impl Parser {
+    /// Parses a trait, struct or enum definition into nodes that borrow from
+    /// `input`.
+    pub fn parse_borrowed(input: &str) -> Result<AstNodeRef<'_>, ParseError> {
+        Self::parse_borrowed_with_options(input, &ParseOptions::default())
+    }
+
+    /// Like [`Parser::parse_borrowed`], with the limits given by `options`.
+    pub fn parse_borrowed_with_options<'a>(
+        input: &'a str,
+        options: &ParseOptions,
+    ) -> Result<AstNodeRef<'a>, ParseError> {
+        let depth = options.max_depth;
+        let mut docs = Vec::new();
+        let mut attributes = Vec::new();
+        let mut input = take_docs(input, false, &mut docs).trim_end();
+        while let Some(attr) = input.strip_prefix('#') {
+            let attr = attr.trim_start();
+            if !attr.starts_with('[') {
+                return Err("Invalid attribute".into());
+            }
+            let close = find_closing(attr).ok_or("Unclosed attribute")?;
+            Parser::parse_attribute(&attr[1..close])?;
+            attributes.push(attr[1..close].trim());
+            input = take_docs(&attr[close + 1..], false, &mut docs);
+        }
+
+        let (_, rest) = Parser::parse_visibility(input)?;
+        let keyword_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let mut node = match &rest[..keyword_end] {
+            "trait" => parse_trait(input, depth)?,
+            "struct" => parse_struct(input, depth)?,
+            "enum" => parse_enum(input, depth)?,
+            _ => return Err("Unsupported construct for borrowed parsing".into()),
+        };
+        match &mut node {
+            AstNodeRef::Trait(node) => (node.attributes, node.docs) = (attributes, docs),
+            AstNodeRef::Struct(node) => (node.attributes, node.docs) = (attributes, docs),
+            AstNodeRef::Enum(node) => (node.attributes, node.docs) = (attributes, docs),
+        }
+        Ok(node)
+    }
+}
+
+fn parse_trait(input: &str, depth: usize) -> Result<AstNodeRef<'_>, ParseError> {
+    let (visibility, _) = Parser::parse_visibility(input)?;
+    let (name, generics, rest) = parse_item_name(input, "trait", depth)?;
+    let (header, where_clause) = split_where_clause(rest.split('{').next().unwrap_or(rest));
+    let supertraits = match header.strip_prefix(':') {
+        Some(bounds) => split_bounds(bounds, depth)?,
+        None => Vec::new(),
+    };
+    let where_clause = parse_where_clause(where_clause, depth)?;
+    let methods = split_items(item_body(input, "trait")?)
+        .into_iter()
+        .map(|method| parse_method(method, depth))
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    Ok(AstNodeRef::Trait(TraitNodeRef {
+        visibility,
+        name,
+        generics,
+        supertraits,
+        where_clause,
+        methods,
+        attributes: Vec::new(),
+        docs: Vec::new(),
+    }))
+}
+
+fn parse_struct(input: &str, depth: usize) -> Result<AstNodeRef<'_>, ParseError> {
+    let (visibility, _) = Parser::parse_visibility(input)?;
+    let (name, generics, rest) = parse_item_name(input, "struct", depth)?;
+    let (_, where_clause) = split_where_clause(rest.split('{').next().unwrap_or(rest));
+    let where_clause = parse_where_clause(where_clause, depth)?;
+    let fields = parse_fields(item_body(input, "struct")?, depth)?;
+
+    Ok(AstNodeRef::Struct(StructNodeRef {
+        visibility,
+        name,
+        generics,
+        where_clause,
+        fields,
+        attributes: Vec::new(),
+        docs: Vec::new(),
+    }))
+}
+
+fn parse_enum(input: &str, depth: usize) -> Result<AstNodeRef<'_>, ParseError> {
+    let (visibility, _) = Parser::parse_visibility(input)?;
+    let (name, generics, rest) = parse_item_name(input, "enum", depth)?;
+    let (_, where_clause) = split_where_clause(rest.split('{').next().unwrap_or(rest));
+    let where_clause = parse_where_clause(where_clause, depth)?;
+    let variants = split_top_level(item_body(input, "enum")?, ',')
+        .into_iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|variant| {
+            let (attributes, variant) = parse_member_attributes(variant)?;
+            let (name, data, discriminant) = split_variant(variant)?;
+            let fields = match data.map(|d| (d, &d[1..d.len() - 1])) {
+                Some((d, data_str)) if d.starts_with('(') => Some(
+                    split_top_level(data_str, ',')
+                        .into_iter()
+                        .map(|s| s.trim())
+                        .filter(|s| !s.is_empty())
+                        .map(|s| {
+                            let (attributes, s) = parse_member_attributes(s)?;
+                            Ok(FieldNodeRef {
+                                visibility: Visibility::Private,
+                                name: None,
+                                field_type: parse_type_within(s, depth)?,
+                                attributes,
+                            })
+                        })
+                        .collect::<Result<Vec<_>, ParseError>>()?,
+                ),
+                Some((_, data_str)) => Some(parse_fields(data_str, depth)?),
+                None => None,
+            };
+            Ok(VariantNodeRef {
+                name,
+                fields,
+                discriminant,
+                attributes,
+            })
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    Ok(AstNodeRef::Enum(EnumNodeRef {
+        visibility,
+        name,
+        generics,
+        where_clause,
+        variants,
+        attributes: Vec::new(),
+        docs: Vec::new(),
+    }))
+}
+
+fn item_body<'a>(input: &'a str, keyword: &str) -> Result<&'a str, ParseError> {
+    let body_start = input
+        .find('{')
+        .ok_or_else(|| format!("Missing {} body", keyword))?;
+    let body_end = input.rfind('}').ok_or("Missing closing brace")?;
+    if body_end <= body_start {
+        return Err(format!("Invalid {} body", keyword).into());
+    }
+    Ok(input[body_start + 1..body_end].trim())
+}
+
+fn parse_item_name<'a>(
+    input: &'a str,
+    keyword: &str,
+    depth: usize,
+) -> Result<(&'a str, Vec<GenericParamRef<'a>>, &'a str), ParseError> {
+    let rest = after_keyword(input, keyword)?;
+    let name_end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    let name = &rest[..name_end];
+    if name.is_empty() {
+        return Err(format!("Invalid {} definition", keyword).into());
+    }
+
+    let rest = rest[name_end..].trim_start();
+    let (generics, rest) = if rest.starts_with('<') {
+        let close = find_closing(rest).ok_or("Unclosed generic parameter list")?;
+        (
+            parse_generic_params(&rest[1..close], depth)?,
+            rest[close + 1..].trim_start(),
+        )
+    } else {
+        (Vec::new(), rest)
+    };
+    Ok((name, generics, rest))
+}
+
+fn parse_generic_params(input: &str, depth: usize) -> Result<Vec<GenericParamRef<'_>>, ParseError> {
+    split_top_level(input, ',')
+        .into_iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|param| {
+            if let Some(rest) = param.strip_prefix("const ") {
+                let (name, ty) = rest
+                    .split_once(':')
+                    .ok_or("Const generic parameter is missing its type")?;
+                Ok(GenericParamRef::Const {
+                    name: name.trim(),
+                    ty: parse_type_within(ty.trim(), depth)?,
+                })
+            } else {
+                let (param, default) = split_default(param)?;
+                let (name, bounds) = param.split_once(':').unwrap_or((param, ""));
+                let name = name.trim();
+                let bounds = split_bounds(bounds, depth)?;
+                if !name.starts_with('\'') {
+                    Ok(GenericParamRef::Type {
+                        name,
+                        bounds,
+                        default: default.map(|d| parse_type_within(d, depth)).transpose()?,
+                    })
+                } else if default.is_none() {
+                    Ok(GenericParamRef::Lifetime { name, bounds })
+                } else {
+                    Err(format!("Lifetime parameter `{}` cannot have a default", name).into())
+                }
+            }
+        })
+        .collect()
+}
+
+/// Splits a `+`-separated list of bounds, checking that each parses within
+/// `depth` as [`Parser::parse`] requires, but keeping them as written.
+fn split_bounds(input: &str, depth: usize) -> Result<Vec<&str>, ParseError> {
+    let bounds: Vec<&str> = split_top_level(input, '+')
+        .into_iter()
+        .map(|b| b.trim())
+        .filter(|b| !b.is_empty())
+        .collect();
+    for bound in &bounds {
+        Parser::parse_bound(bound, depth)?;
+    }
+    Ok(bounds)
+}
+
+fn parse_where_clause(input: &str, depth: usize) -> Result<Vec<WherePredicateRef<'_>>, ParseError> {
+    split_top_level(input, ',')
+        .into_iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|predicate| {
+            let colon = bound_colon(predicate).ok_or("Where predicate is missing its bounds")?;
+            Ok(WherePredicateRef {
+                subject: parse_type_within(predicate[..colon].trim(), depth)?,
+                bounds: split_bounds(&predicate[colon + 1..], depth)?,
+            })
+        })
+        .collect()
+}
+
+/// Splits the leading `#[...]` attributes off a field or variant.
+fn parse_member_attributes(input: &str) -> Result<(Vec<&str>, &str), ParseError> {
+    let mut attributes = Vec::new();
+    let mut rest = input.trim_start();
+    while let Some(attr) = rest.strip_prefix('#') {
+        let attr = attr.trim_start();
+        if !attr.starts_with('[') {
+            return Err("Invalid attribute".into());
+        }
+        let close = find_closing(attr).ok_or("Unclosed attribute")?;
+        Parser::parse_attribute(&attr[1..close])?;
+        attributes.push(attr[1..close].trim());
+        rest = attr[close + 1..].trim_start();
+    }
+    Ok((attributes, rest))
+}
+
+fn parse_fields(input: &str, depth: usize) -> Result<Vec<FieldNodeRef<'_>>, ParseError> {
+    split_top_level(input, ',')
+        .into_iter()
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|field| {
+            let (attributes, field) = parse_member_attributes(field)?;
+            let (visibility, field) = Parser::parse_visibility(field)?;
+            let (name, field_type) = field.split_once(':').ok_or("Invalid field format")?;
+            Ok(FieldNodeRef {
+                visibility,
+                name: Some(name.trim()),
+                field_type: parse_type_within(field_type.trim(), depth)?,
+                attributes,
+            })
+        })
+        .collect()
+}
+
+fn parse_method(input: &str, depth: usize) -> Result<MethodNodeRef<'_>, ParseError> {
+    let input = input.trim();
+    let (visibility, qualifiers) = Parser::parse_visibility(input)?;
+    let is_async = qualifiers
+        .split_whitespace()
+        .take_while(|word| *word != "fn")
+        .any(|word| word == "async");
+    let (name, generics, rest) = parse_item_name(input, "fn", depth)?;
+    if !rest.starts_with('(') {
+        return Err("Invalid method format".into());
+    }
+    let close = find_closing(rest).ok_or("Invalid method format")?;
+
+    let params = split_top_level(&rest[1..close], ',')
+        .into_iter()
+        .map(|param| param.trim())
+        .filter(|param| !param.is_empty())
+        .map(|param| {
+            if is_receiver(param) {
+                let placeholder = TypeNodeRef::Simple("self");
+                let param_type = match split_reference(param) {
+                    Some((lifetime, mutable, _)) => TypeNodeRef::Reference {
+                        lifetime,
+                        mutable,
+                        inner: Box::new(placeholder),
+                    },
+                    None => placeholder,
+                };
+                Ok(ParamNodeRef {
+                    name: param,
+                    param_type,
+                })
+            } else {
+                let (name, param_type) = param.split_once(':').ok_or("Invalid parameter format")?;
+                if !is_param_pattern(name) {
+                    return Err("Invalid parameter format".into());
+                }
+                Ok(ParamNodeRef {
+                    name: name.trim(),
+                    param_type: parse_type_within(param_type.trim(), depth)?,
+                })
+            }
+        })
+        .collect::<Result<Vec<_>, ParseError>>()?;
+
+    let after_params = &rest[close + 1..];
+    let (after_params, has_body) = match after_params.find('{') {
+        Some(body) => (&after_params[..body], true),
+        None => (after_params, false),
+    };
+    let after_params = after_params.trim().trim_end_matches(';').trim();
+    let (after_params, where_clause) = split_where_clause(after_params);
+    let return_type = match after_params.strip_prefix("->") {
+        Some(return_str) => Some(parse_type_within(return_str.trim(), depth)?),
+        None => None,
+    };
+    let where_clause = parse_where_clause(where_clause, depth)?;
+
+    Ok(MethodNodeRef {
+        visibility,
+        name,
+        generics,
+        params,
+        return_type,
+        where_clause,
+        is_async,
+        has_body,
+    })
+}
+
+fn parse_type_within(input: &str, depth: usize) -> Result<TypeNodeRef<'_>, ParseError> {
+    if depth == 0 {
+        return Err(ParseError::RecursionLimit);
+    }
+    if input.starts_with('&') {
+        let Some((lifetime, mutable, inner)) = split_reference(input) else {
+            return Err(format!("Invalid reference type `{}`", input).into());
+        };
+        Ok(TypeNodeRef::Reference {
+            lifetime,
+            mutable,
+            inner: Box::new(parse_type_within(inner, depth - 1)?),
+        })
+    } else if input.starts_with('*') {
+        let Some((mutable, inner)) = split_raw_pointer(input) else {
+            return Err(format!("Invalid raw pointer type `{}`", input).into());
+        };
+        Ok(TypeNodeRef::RawPointer {
+            mutable,
+            inner: Box::new(parse_type_within(inner, depth - 1)?),
+        })
+    } else if let Some(bounds) = input.strip_prefix("impl ") {
+        Ok(TypeNodeRef::ImplTrait(split_bounds(bounds, depth - 1)?))
+    } else if let Some(bounds) = input.strip_prefix("dyn ") {
+        Ok(TypeNodeRef::DynTrait(split_bounds(bounds, depth - 1)?))
+    } else if input.starts_with('(') && find_closing(input) == Some(input.len() - 1) {
+        let elements: Vec<&str> = split_top_level(&input[1..input.len() - 1], ',')
+            .into_iter()
+            .map(|s| s.trim())
+            .collect();
+        match elements.as_slice() {
+            [""] => Ok(TypeNodeRef::Tuple(Vec::new())),
+            [single] => parse_type_within(single, depth - 1),
+            _ => Ok(TypeNodeRef::Tuple(
+                elements
+                    .iter()
+                    .filter(|s| !s.is_empty())
+                    .map(|s| parse_type_within(s, depth - 1))
+                    .collect::<Result<Vec<_>, ParseError>>()?,
+            )),
+        }
+    } else if input.starts_with('[') && input.ends_with(']') {
+        let (element, len) = match split_top_level(&input[1..input.len() - 1], ';')[..] {
+            [element] => (element, None),
+            [element, len] => (element, Some(len.trim())),
+            _ => return Err(format!("Invalid array type `{}`", input).into()),
+        };
+        let mut args = vec![parse_type_within(element.trim(), depth - 1)?];
+        args.extend(len.map(TypeNodeRef::Simple));
+        Ok(TypeNodeRef::Generic { name: "[]", args })
+    } else if let Some(open) = input
+        .find('<')
+        .filter(|&open| find_closing(&input[open..]) == Some(input.len() - 1 - open))
+    {
+        let args = split_top_level(&input[open + 1..input.len() - 1], ',')
+            .into_iter()
+            .map(|arg| arg.trim())
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| parse_type_within(arg, depth - 1))
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        Ok(TypeNodeRef::Generic {
+            name: input[..open].trim(),
+            args,
+        })
+    } else {
+        Ok(TypeNodeRef::Simple(input))
+    }
+}
+
+impl AstNodeRef<'_> {
+    /// Copies the borrowed names into an owned [`AstNode`], parsing the
+    /// bounds and attributes kept as written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a bound or attribute does not parse, which cannot happen for
+    /// nodes returned by [`Parser::parse_borrowed`].
+    pub fn to_owned(&self) -> AstNode {
+        match self {
+            AstNodeRef::Trait(node) => AstNode::Trait(TraitNode {
+                visibility: node.visibility.clone(),
+                name: node.name.to_string(),
+                generics: node.generics.iter().map(|g| g.to_owned()).collect(),
+                supertraits: bounds_to_owned(&node.supertraits),
+                where_clause: node.where_clause.iter().map(|p| p.to_owned()).collect(),
+                methods: node.methods.iter().map(|m| m.to_owned()).collect(),
+                attributes: attributes_to_owned(&node.attributes),
+                doc: join_docs(&node.docs),
+            }),
+            AstNodeRef::Struct(node) => AstNode::Struct(StructNode {
+                visibility: node.visibility.clone(),
+                name: node.name.to_string(),
+                generics: node.generics.iter().map(|g| g.to_owned()).collect(),
+                where_clause: node.where_clause.iter().map(|p| p.to_owned()).collect(),
+                fields: fields_to_owned(&node.fields),
+                inherent_methods: Vec::new(),
+                attributes: attributes_to_owned(&node.attributes),
+                doc: join_docs(&node.docs),
+            }),
+            AstNodeRef::Enum(node) => AstNode::Enum(EnumNode {
+                visibility: node.visibility.clone(),
+                name: node.name.to_string(),
+                generics: node.generics.iter().map(|g| g.to_owned()).collect(),
+                where_clause: node.where_clause.iter().map(|p| p.to_owned()).collect(),
+                variants: node.variants.iter().map(|v| v.to_owned()).collect(),
+                inherent_methods: Vec::new(),
+                attributes: attributes_to_owned(&node.attributes),
+                doc: join_docs(&node.docs),
+            }),
+        }
+    }
+}
+
+impl MethodNodeRef<'_> {
+    pub fn to_owned(&self) -> MethodNode {
+        MethodNode {
+            visibility: self.visibility.clone(),
+            name: self.name.to_string(),
+            generics: self.generics.iter().map(|g| g.to_owned()).collect(),
+            params: self
+                .params
+                .iter()
+                .map(|p| ParamNode {
+                    name: if is_receiver(p.name) {
+                        receiver_name(p.name)
+                    } else {
+                        p.name.to_string()
+                    },
+                    param_type: Box::new(p.param_type.to_owned()),
+                })
+                .collect(),
+            return_type: self.return_type.as_ref().map(|ty| Box::new(ty.to_owned())),
+            where_clause: self.where_clause.iter().map(|p| p.to_owned()).collect(),
+            is_async: self.is_async,
+            has_body: self.has_body,
+        }
+    }
+}
+
+impl VariantNodeRef<'_> {
+    /// Variant data becomes an anonymous [`StructNode`], as in
+    /// [`Parser::parse`].
+    pub fn to_owned(&self) -> VariantNode {
+        VariantNode {
+            name: self.name.to_string(),
+            associated_data: self.fields.as_ref().map(|fields| {
+                Box::new(AstNode::Struct(StructNode {
+                    visibility: Visibility::Private,
+                    name: "".to_string(),
+                    generics: Vec::new(),
+                    where_clause: Vec::new(),
+                    fields: fields_to_owned(fields),
+                    inherent_methods: Vec::new(),
+                    attributes: Vec::new(),
+                    doc: None,
+                }))
+            }),
+            discriminant: self.discriminant.map(str::to_string),
+            attributes: attributes_to_owned(&self.attributes),
+        }
+    }
+}
+
+/// Unnamed tuple fields are numbered by position.
+fn fields_to_owned(fields: &[FieldNodeRef<'_>]) -> Vec<FieldNode> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| FieldNode {
+            visibility: field.visibility.clone(),
+            name: field
+                .name
+                .map_or_else(|| i.to_string(), |name| name.to_string()),
+            field_type: Box::new(field.field_type.to_owned()),
+            attributes: attributes_to_owned(&field.attributes),
+        })
+        .collect()
+}
+
+fn attributes_to_owned(attributes: &[&str]) -> Vec<Attribute> {
+    attributes
+        .iter()
+        .map(|a| Parser::parse_attribute(a).expect("attributes are checked while parsing"))
+        .collect()
+}
+
+/// Borrowed bounds are kept as written, so they are parsed here. They were
+/// checked against the depth limit while parsing, so none applies again.
+fn bounds_to_owned(bounds: &[&str]) -> Vec<Bound> {
+    bounds
+        .iter()
+        .map(|b| Parser::parse_bound(b, usize::MAX).expect("bounds are checked while parsing"))
+        .collect()
+}
+
+impl GenericParamRef<'_> {
+    pub fn to_owned(&self) -> GenericParam {
+        match self {
+            GenericParamRef::Lifetime { name, bounds } => GenericParam::Lifetime {
+                name: name.to_string(),
+                bounds: bounds_to_owned(bounds),
+            },
+            GenericParamRef::Type {
+                name,
+                bounds,
+                default,
+            } => GenericParam::Type {
+                name: name.to_string(),
+                bounds: bounds_to_owned(bounds),
+                default: default.as_ref().map(TypeNodeRef::to_owned),
+            },
+            GenericParamRef::Const { name, ty } => GenericParam::Const {
+                name: name.to_string(),
+                ty: ty.to_owned(),
+            },
+        }
+    }
+}
+
+impl WherePredicateRef<'_> {
+    pub fn to_owned(&self) -> WherePredicate {
+        WherePredicate {
+            subject: self.subject.to_owned(),
+            bounds: bounds_to_owned(&self.bounds),
+        }
+    }
+}
+
+impl TypeNodeRef<'_> {
+    pub fn to_owned(&self) -> TypeNode {
+        match self {
+            TypeNodeRef::Simple(name) => TypeNode::Simple(name.to_string()),
+            TypeNodeRef::Reference {
+                lifetime,
+                mutable,
+                inner,
+            } => TypeNode::Reference {
+                lifetime: lifetime.map(str::to_string),
+                mutable: *mutable,
+                inner: Box::new((**inner).to_owned()),
+            },
+            TypeNodeRef::RawPointer { mutable, inner } => TypeNode::RawPointer {
+                mutable: *mutable,
+                inner: Box::new((**inner).to_owned()),
+            },
+            TypeNodeRef::Generic { name, args } => TypeNode::Generic {
+                name: name.to_string(),
+                args: args.iter().map(|arg| arg.to_owned()).collect(),
+            },
+            TypeNodeRef::Tuple(elements) => {
+                TypeNode::Tuple(elements.iter().map(|e| e.to_owned()).collect())
+            }
+            TypeNodeRef::ImplTrait(bounds) => TypeNode::ImplTrait(bounds_to_owned(bounds)),
+            TypeNodeRef::DynTrait(bounds) => TypeNode::DynTrait(bounds_to_owned(bounds)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    /// Counts the allocations made on each thread, so that a test can
+    /// measure its own while others run.
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// Returns the number of allocations `f` makes on the current thread.
+    fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATIONS.with(Cell::get);
+        let result = f();
+        (result, ALLOCATIONS.with(Cell::get) - before)
+    }
+
+    /// Returns `true` if `s` points into `input` rather than a separate
+    /// allocation.
+    fn borrows_from(s: &str, input: &str) -> bool {
+        let range = input.as_bytes().as_ptr_range();
+        range.contains(&s.as_ptr())
+    }
+
+    #[test]
+    fn test_parse_borrowed_large_struct() {
+        let struct_with = |count: usize| {
+            let fields: Vec<String> = (0..count)
+                .map(|i| format!("pub field_{}: u{}", i, 8 << (i % 4)))
+                .collect();
+            format!("pub struct Large {{ {}, }}", fields.join(", "))
+        };
+        let input = struct_with(500);
+
+        let (borrowed, allocations) = count_allocations(|| Parser::parse_borrowed(&input));
+        let borrowed = borrowed.unwrap();
+        let struct_node = match &borrowed {
+            AstNodeRef::Struct(struct_node) => struct_node,
+            other => panic!("expected a struct, got {:?}", other),
+        };
+        assert_eq!(struct_node.fields.len(), 500);
+        assert!(borrows_from(struct_node.name, &input));
+        for field in &struct_node.fields {
+            assert!(borrows_from(field.name.unwrap(), &input));
+            match &field.field_type {
+                TypeNodeRef::Simple(name) => assert!(borrows_from(name, &input)),
+                other => panic!("expected a simple type, got {:?}", other),
+            }
+        }
+
+        // Only the growing lists of fields allocate, so twice the fields
+        // take no more than a few extra allocations
+        let larger = struct_with(1000);
+        let (_, larger_allocations) = count_allocations(|| Parser::parse_borrowed(&larger));
+        assert!(allocations < 50, "{} allocations", allocations);
+        assert!(
+            larger_allocations <= allocations + 4,
+            "{} allocations for twice the fields, {} for 500",
+            larger_allocations,
+            allocations
+        );
+        let (_, owned_allocations) = count_allocations(|| Parser::parse(&input));
+        assert!(owned_allocations >= 1000);
+
+        assert_eq!(borrowed.to_owned(), Parser::parse(&input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_borrowed_matches_owned() {
+        let inputs = [
+            "pub trait Visualizer { fn visualize(&self, data: &[u8]); fn process(&self, input: &str) -> String; }",
+            "pub enum Message { Quit, Move { x: i32, y: i32 }, Write(String), ChangeColor(i32, i32, i32) }",
+        ];
+        for input in inputs {
+            assert_eq!(
+                Parser::parse_borrowed(input).unwrap().to_owned(),
+                Parser::parse(input).unwrap()
+            );
+        }
+        assert!(Parser::parse_borrowed("pub type Bytes = Vec<u8>;").is_err());
+    }
+
+    #[test]
+    fn test_parse_borrowed_to_owned_parity() {
+        let inputs = [
+            "/// A 2D point.\n#[derive(Debug, Clone)]\npub struct Point<T: Copy = f64> where T: Default { #[serde(rename = \"px\")] pub x: T, pub(crate) y: [T; 2], label: &'static str }",
+            "/**\n * Draws frames.\n */\n#[async_trait]\npub trait Renderer<'a>: Send + Sync where Self: 'a {\n    async fn render(&mut self, frame: &[u8]) -> Result<(), Error>;\n    fn names(&self) -> impl Iterator<Item = char> + 'a { todo!() }\n    fn boxed(self: Box<Self>) -> Box<dyn Fn(u8) -> u8 + Send>;\n}",
+            "#[repr(u8)]\npub(crate) enum Event<T> where T: Clone { #[default] Quit, Move { x: i32, y: i32 }, Write(#[doc = \"text\"] String), Data(Vec<T>, (u8, u16)) }",
+            "pub struct Index<K: Hash + Eq, V> { entries: HashMap<K, Vec<(K, V)>>, lookup: <K as Key>::Id, raw: *mut *const V, cursor: &mut &&K }",
+        ];
+        for input in inputs {
+            assert_eq!(
+                Parser::parse_borrowed(input).unwrap().to_owned(),
+                Parser::parse(input).unwrap(),
+                "{}",
+                input
+            );
+        }
+
+        let deep_bound = format!("{}u8{}", "Box<".repeat(200), ">".repeat(200));
+        let rejected = [
+            "#[] pub struct S { a: u8 }".to_string(),
+            "pub struct S { #[= 1] a: u8 }".to_string(),
+            format!("pub struct S<T: {}> {{ a: T }}", deep_bound),
+            format!("pub trait S where Self: {} {{}}", deep_bound),
+            format!("pub struct S {{ a: Box<dyn {}> }}", deep_bound),
+        ];
+        for input in &rejected {
+            assert!(Parser::parse(input).is_err(), "{}", input);
+            assert_eq!(
+                Parser::parse_borrowed(input).map(|node| node.to_owned()),
+                Parser::parse(input),
+                "{}",
+                input
+            );
+        }
+
+        let inputs = [
+            "pub struct Deep { value: Vec<Vec<Vec<u8>>> }",
+            "pub struct Deep<T: Into<Vec<Vec<u8>>>> { value: T }",
+            "pub struct Deep { value: Box<dyn Into<Vec<u8>>> }",
+        ];
+        for (input, max_depth) in inputs.iter().flat_map(|input| [(input, 3), (input, 4)]) {
+            let options = ParseOptions {
+                max_depth,
+                ..ParseOptions::default()
+            };
+            assert_eq!(
+                Parser::parse_borrowed_with_options(input, &options).map(|node| node.to_owned()),
+                Parser::parse_with_options(input, &options),
+                "{} at depth {}",
+                input,
+                max_depth
+            );
+        }
+    }
+}
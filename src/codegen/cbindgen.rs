@@ -0,0 +1,186 @@
+use crate::{AstNode, MethodNode, StructNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as C header declarations, mapping types with
+    /// [`TypeNode::to_c_type`]. Structs for which
+    /// [`StructNode::is_repr_c_compatible`] holds become `typedef struct`s,
+    /// and the functions of `extern` blocks and free `extern` functions
+    /// become prototypes. Structs without fields, which C does not allow,
+    /// functions with the `Rust` ABI or with a parameter or return type that
+    /// has no C counterpart, and other items are left out.
+    pub fn to_cbindgen_header(&self) -> String {
+        let mut declarations = Vec::new();
+        add_declarations(self, &mut declarations);
+
+        let source = declarations.join("\n\n");
+        let mut includes = vec!["#include <stdint.h>"];
+        if source.contains("bool") {
+            includes.push("#include <stdbool.h>");
+        }
+        if source.contains("size_t") {
+            includes.push("#include <stddef.h>");
+        }
+        let mut header = includes.join("\n");
+        if !source.is_empty() {
+            header.push_str("\n\n");
+            header.push_str(&source);
+        }
+        header.push('\n');
+        header
+    }
+}
+
+fn add_declarations(node: &AstNode, declarations: &mut Vec<String>) {
+    match node {
+        AstNode::Struct(node) => declarations.extend(typedef(node)),
+        AstNode::Extern(node) if node.abi != "Rust" => {
+            let prototypes: Vec<String> = node
+                .functions
+                .iter()
+                .filter_map(|f| prototype(&f.signature))
+                .collect();
+            if !prototypes.is_empty() {
+                declarations.push(prototypes.join("\n"));
+            }
+        }
+        AstNode::Function(node) if node.abi != "Rust" => {
+            declarations.extend(prototype(&node.signature));
+        }
+        AstNode::Module(node) => node
+            .items
+            .iter()
+            .for_each(|item| add_declarations(item, declarations)),
+        AstNode::Trait(_)
+        | AstNode::Extern(_)
+        | AstNode::Function(_)
+        | AstNode::Enum(_)
+        | AstNode::TypeAlias(_)
+        | AstNode::Const(_)
+        | AstNode::Static(_)
+        | AstNode::Use(_)
+        | AstNode::Impl(_)
+        | AstNode::MacroDef(_)
+        | AstNode::Opaque(_) => {}
+    }
+}
+
+fn typedef(node: &StructNode) -> Option<String> {
+    if node.fields.is_empty() || !node.is_repr_c_compatible() {
+        return None;
+    }
+    let fields = node
+        .fields
+        .iter()
+        .map(|f| Some(format!("    {};", declaration(&f.field_type, &f.name)?)))
+        .collect::<Option<Vec<String>>>()?;
+    Some(format!(
+        "typedef struct {{\n{}\n}} {};",
+        fields.join("\n"),
+        node.name
+    ))
+}
+
+fn prototype(function: &MethodNode) -> Option<String> {
+    let return_type = match function.return_type.as_deref() {
+        Some(ty) => ty.to_c_type()?,
+        None => "void".to_string(),
+    };
+    let params = function
+        .params
+        .iter()
+        .map(|p| declaration(&p.param_type, p.name.trim_start_matches("mut ")))
+        .collect::<Option<Vec<String>>>()?;
+    let params = if params.is_empty() {
+        "void".to_string()
+    } else {
+        params.join(", ")
+    };
+    Some(format!("{} {}({});", return_type, function.name, params))
+}
+
+/// Declares `name` with the C type of `ty`. Array lengths follow the name,
+/// as in `uint8_t tag[4]`.
+fn declaration(ty: &TypeNode, name: &str) -> Option<String> {
+    let c_type = ty.to_c_type()?;
+    let (base, lengths) = c_type.split_at(c_type.find('[').unwrap_or(c_type.len()));
+    Some(format!("{} {}{}", base, name, lengths))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_cbindgen_header() {
+        let input = "#[repr(C)] pub struct Header { len: u32, data: *const u8, tag: [c_char; 4] }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+#include <stdint.h>
+
+typedef struct {
+    uint32_t len;
+    const uint8_t* data;
+    char tag[4];
+} Header;
+";
+        assert_eq!(ast.to_cbindgen_header(), expected);
+
+        let owned = "#[repr(C)] pub struct Name { text: String }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(owned.to_cbindgen_header(), "#include <stdint.h>\n");
+    }
+
+    #[test]
+    fn test_extern_to_cbindgen_header() {
+        let input = r#"
+            extern "C" {
+                fn buffer_len(buffer: *const u8) -> usize;
+                fn buffer_clear(buffer: *mut u8, zero: bool);
+                fn buffer_new() -> *mut u8;
+                fn buffer_name(buffer: *const u8) -> String;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+#include <stdint.h>
+#include <stdbool.h>
+#include <stddef.h>
+
+size_t buffer_len(const uint8_t* buffer);
+void buffer_clear(uint8_t* buffer, bool zero);
+uint8_t* buffer_new(void);
+";
+        assert_eq!(ast.to_cbindgen_header(), expected);
+    }
+
+    #[test]
+    fn test_extern_functions_to_cbindgen_header() {
+        let input = r#"
+            pub mod ffi {
+                #[no_mangle]
+                pub extern "C" fn counter_new(start: u64) -> *mut u8 { todo!() }
+                #[no_mangle]
+                pub unsafe extern "C" fn counter_free(counter: *mut u8) {}
+                pub extern "C" fn counter_peek(counter: *const *const u8, reset: bool) -> i32 { 0 }
+                pub extern "C" fn counter_name(counter: *const u8) -> String { todo!() }
+                pub extern "Rust" fn counter_reset(counter: *mut u8) {}
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+#include <stdint.h>
+#include <stdbool.h>
+
+uint8_t* counter_new(uint64_t start);
+
+void counter_free(uint8_t* counter);
+
+int32_t counter_peek(const uint8_t* const* counter, bool reset);
+";
+        assert_eq!(ast.to_cbindgen_header(), expected);
+    }
+}
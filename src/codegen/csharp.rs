@@ -0,0 +1,302 @@
+use super::{
+    angle_type_params, field_name, principal_trait, to_camel_case, to_pascal_case, type_args,
+};
+use crate::{AstNode, MethodNode, StructNode, TraitNode, TypeNode};
+
+/// C# keywords that are valid Rust identifiers and so need an `@` prefix
+/// when used as parameter names.
+const KEYWORDS: &[&str] = &[
+    "base", "bool", "byte", "case", "catch", "char", "checked", "class", "decimal", "default",
+    "delegate", "double", "event", "explicit", "fixed", "float", "foreach", "goto", "implicit",
+    "int", "internal", "is", "lock", "long", "new", "null", "object", "operator", "out", "params",
+    "private", "public", "readonly", "sbyte", "sealed", "short", "string", "switch", "this",
+    "throw", "uint", "ulong", "ushort", "using", "void", "volatile",
+];
+
+impl AstNode {
+    /// Emits the item as C# source. Structs become positional `record`s with
+    /// PascalCased properties and traits become `I`-prefixed `interface`s.
+    /// Trait methods without a receiver are left out, as are other items.
+    pub fn to_csharp(&self) -> String {
+        let mut declarations = Vec::new();
+        add_declarations(self, &mut declarations);
+
+        let source = declarations.join("\n\n");
+        let mut source = if source.contains("IList<")
+            || source.contains("IDictionary<")
+            || source.contains("ISet<")
+        {
+            format!("using System.Collections.Generic;\n\n{}", source)
+        } else {
+            source
+        };
+        source.push('\n');
+        source
+    }
+}
+
+fn add_declarations(node: &AstNode, declarations: &mut Vec<String>) {
+    match node {
+        AstNode::Trait(node) => declarations.push(interface(node)),
+        AstNode::Struct(node) => declarations.push(record(node)),
+        AstNode::Module(node) => node
+            .items
+            .iter()
+            .for_each(|item| add_declarations(item, declarations)),
+        AstNode::Enum(_)
+        | AstNode::TypeAlias(_)
+        | AstNode::Const(_)
+        | AstNode::Static(_)
+        | AstNode::Use(_)
+        | AstNode::Impl(_)
+        | AstNode::Extern(_)
+        | AstNode::Function(_)
+        | AstNode::MacroDef(_)
+        | AstNode::Opaque(_) => {}
+    }
+}
+
+fn record(node: &StructNode) -> String {
+    let name = format!("{}{}", node.name, angle_type_params(&node.generics));
+    let properties: Vec<String> = node
+        .fields
+        .iter()
+        .map(|f| {
+            format!(
+                "    {} {}",
+                csharp_type(&f.field_type, &name),
+                to_pascal_case(&field_name(&f.name))
+            )
+        })
+        .collect();
+    if properties.is_empty() {
+        return format!("public record {}();", name);
+    }
+    format!("public record {}(\n{}\n);", name, properties.join(",\n"))
+}
+
+fn interface(node: &TraitNode) -> String {
+    let name = format!("I{}{}", node.name, angle_type_params(&node.generics));
+    let methods: Vec<String> = node
+        .methods
+        .iter()
+        .filter(|m| m.receiver().is_some())
+        .map(|m| format!("    {};\n", method(m, &name)))
+        .collect();
+    format!("public interface {}\n{{\n{}}}", name, methods.concat())
+}
+
+fn method(method: &MethodNode, self_name: &str) -> String {
+    let params: Vec<String> = method
+        .params_without_self()
+        .iter()
+        .map(|p| {
+            let name = to_camel_case(&p.name);
+            let name = if KEYWORDS.contains(&name.as_str()) {
+                format!("@{}", name)
+            } else {
+                name
+            };
+            format!("{} {}", csharp_type(&p.param_type, self_name), name)
+        })
+        .collect();
+    let return_type = match method.return_type.as_deref() {
+        None => "void".to_string(),
+        Some(TypeNode::Tuple(elements)) if elements.is_empty() => "void".to_string(),
+        Some(ty) => csharp_type(ty, self_name),
+    };
+    format!(
+        "{} {}({})",
+        return_type,
+        to_pascal_case(&method.name),
+        params.join(", ")
+    )
+}
+
+/// Maps a Rust type to C#. `Self` resolves to `self_name`, the type being
+/// generated.
+fn csharp_type(ty: &TypeNode, self_name: &str) -> String {
+    let map = |ty: &TypeNode| csharp_type(ty, self_name);
+    match ty {
+        TypeNode::Simple(name) => match name.as_str() {
+            "i8" => "sbyte".to_string(),
+            "i16" => "short".to_string(),
+            "i32" => "int".to_string(),
+            "i64" => "long".to_string(),
+            "isize" => "nint".to_string(),
+            "u8" => "byte".to_string(),
+            "u16" => "ushort".to_string(),
+            "u32" => "uint".to_string(),
+            "u64" => "ulong".to_string(),
+            "usize" => "nuint".to_string(),
+            "f32" => "float".to_string(),
+            "f64" => "double".to_string(),
+            "String" | "str" => "string".to_string(),
+            "char" => "char".to_string(),
+            "bool" => "bool".to_string(),
+            "Self" => self_name.to_string(),
+            _ => name.rsplit("::").next().unwrap_or(name).to_string(),
+        },
+        TypeNode::Reference { inner, .. } => map(inner),
+        TypeNode::RawPointer { inner, .. } => format!("{}*", map(inner)),
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => map(inner),
+            ("Option", [inner]) => {
+                let inner = map(inner);
+                if inner.ends_with('?') {
+                    inner
+                } else {
+                    format!("{}?", inner)
+                }
+            }
+            ("Vec" | "VecDeque" | "[]", [inner]) => format!("IList<{}>", map(inner)),
+            ("HashSet" | "BTreeSet", [inner]) => format!("ISet<{}>", map(inner)),
+            ("HashMap" | "BTreeMap", [key, value]) => {
+                format!("IDictionary<{}, {}>", map(key), map(value))
+            }
+            _ => {
+                let args: Vec<String> = args.iter().map(map).collect();
+                format!(
+                    "{}<{}>",
+                    name.rsplit("::").next().unwrap_or(name),
+                    args.join(", ")
+                )
+            }
+        },
+        TypeNode::Tuple(elements) => match elements.as_slice() {
+            [] => "void".to_string(),
+            // C# has no single-element tuple syntax
+            [single] => format!("ValueTuple<{}>", map(single)),
+            _ => {
+                let elements: Vec<String> = elements.iter().map(map).collect();
+                format!("({})", elements.join(", "))
+            }
+        },
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            principal_trait(bounds).map_or("object".to_string(), map)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_csharp() {
+        let input = r#"
+            pub struct Frame {
+                delta: i8,
+                width: usize,
+                offset: isize,
+                port: u16,
+                extent: (f32, f32),
+                id: (u64,),
+                headers: HashMap<String, Vec<u8>>,
+                flags: BTreeSet<char>,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // C# has native-sized integers and unsigned types, and no one-element
+        // tuple syntax
+        let expected = "\
+using System.Collections.Generic;
+
+public record Frame(
+    sbyte Delta,
+    nuint Width,
+    nint Offset,
+    ushort Port,
+    (float, float) Extent,
+    ValueTuple<ulong> Id,
+    IDictionary<string, IList<byte>> Headers,
+    ISet<char> Flags
+);
+";
+        assert_eq!(ast.to_csharp(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert_eq!(marker.to_csharp(), "public record Marker();\n");
+    }
+
+    #[test]
+    fn test_trait_to_csharp() {
+        let input = r#"
+            pub trait Decoder {
+                fn decode_frame(&mut self, frame: &Frame) -> Option<Frame>;
+                fn reset(&mut self) -> ();
+                fn split(&self) -> (Self, Self);
+                fn create() -> Self;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+public interface IDecoder
+{
+    Frame? DecodeFrame(Frame frame);
+    void Reset();
+    (IDecoder, IDecoder) Split();
+}
+";
+        assert_eq!(ast.to_csharp(), expected);
+
+        let factory = "pub trait Factory { fn create() -> Self; }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(factory.to_csharp(), "public interface IFactory\n{\n}\n");
+    }
+
+    #[test]
+    fn test_generics_to_csharp() {
+        let input = r#"
+            pub mod store {
+                pub struct Wrapper<'a, T, const N: usize> { value: T, items: [&'a T; N] }
+                pub trait Repo<K, V> {
+                    fn get(&self, key: K) -> Option<V>;
+                    fn fork(&self) -> Self;
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+using System.Collections.Generic;
+
+public record Wrapper<T>(
+    T Value,
+    IList<T> Items
+);
+
+public interface IRepo<K, V>
+{
+    V? Get(K key);
+    IRepo<K, V> Fork();
+}
+";
+        assert_eq!(ast.to_csharp(), expected);
+    }
+
+    #[test]
+    fn test_escaped_keywords_to_csharp() {
+        let input = r#"
+            pub trait Dispatcher {
+                fn dispatch(&self, event: &str, params: Vec<String>, lock: bool) -> Option<u32>;
+                fn rebase(&mut self, base: u64, string: String);
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+using System.Collections.Generic;
+
+public interface IDispatcher
+{
+    uint? Dispatch(string @event, IList<string> @params, bool @lock);
+    void Rebase(ulong @base, string @string);
+}
+";
+        assert_eq!(ast.to_csharp(), expected);
+    }
+}
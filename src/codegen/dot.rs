@@ -0,0 +1,245 @@
+use crate::{
+    display_generics, display_impl_trait, display_supertraits, display_where_clause, inline_fields,
+    opaque_label,
+};
+use crate::{AstNode, ImplItem, MethodNode};
+
+impl AstNode {
+    /// Renders the item as a Graphviz DOT digraph with one node per line of
+    /// [`AstNode::tree_string`]. Node IDs are assigned by a pre-order
+    /// traversal and edges emitted in traversal order, so the output for a
+    /// given AST is always the same.
+    pub fn to_dot(&self) -> String {
+        let mut graph = DotGraph::default();
+        graph.add(self, None);
+        let mut source = String::from("digraph ast {\n    node [shape=box];\n");
+        for line in graph.lines {
+            source.push_str("    ");
+            source.push_str(&line);
+            source.push('\n');
+        }
+        source.push_str("}\n");
+        source
+    }
+}
+
+#[derive(Default)]
+struct DotGraph {
+    lines: Vec<String>,
+    next_id: usize,
+}
+
+impl DotGraph {
+    /// Declares a node, and the edge from its parent, returning its ID.
+    fn node(&mut self, label: &str, parent: Option<usize>) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        let label = label.replace('\\', "\\\\").replace('"', "\\\"");
+        self.lines.push(format!("n{} [label=\"{}\"];", id, label));
+        if let Some(parent) = parent {
+            self.lines.push(format!("n{} -> n{};", parent, id));
+        }
+        id
+    }
+
+    fn add(&mut self, node: &AstNode, parent: Option<usize>) {
+        match node {
+            AstNode::Trait(node) => {
+                let label = format!(
+                    "Trait: {}{}{}{}",
+                    node.name,
+                    display_generics(&node.generics),
+                    display_supertraits(&node.supertraits),
+                    display_where_clause(&node.where_clause)
+                );
+                let id = self.node(&label, parent);
+                for method in &node.methods {
+                    self.add_method(method, id);
+                }
+            }
+            AstNode::Struct(node) => {
+                let label = format!(
+                    "Struct: {}{}{}",
+                    node.name,
+                    display_generics(&node.generics),
+                    display_where_clause(&node.where_clause)
+                );
+                let id = self.node(&label, parent);
+                for field in &node.fields {
+                    self.node(&format!("Field: {}", field.to_source_string()), Some(id));
+                }
+                for method in &node.inherent_methods {
+                    self.add_method(method, id);
+                }
+            }
+            AstNode::Enum(node) => {
+                let label = format!(
+                    "Enum: {}{}{}",
+                    node.name,
+                    display_generics(&node.generics),
+                    display_where_clause(&node.where_clause)
+                );
+                let id = self.node(&label, parent);
+                for variant in &node.variants {
+                    match variant.associated_data.as_deref() {
+                        Some(AstNode::Struct(data)) if data.name.is_empty() => {
+                            let label =
+                                format!("Variant: {}{}", variant.name, inline_fields(&data.fields));
+                            self.node(&label, Some(id));
+                        }
+                        Some(data) => {
+                            let variant_id =
+                                self.node(&format!("Variant: {}", variant.name), Some(id));
+                            self.add(data, Some(variant_id));
+                        }
+                        None => {
+                            self.node(&format!("Variant: {}", variant.name), Some(id));
+                        }
+                    }
+                }
+                for method in &node.inherent_methods {
+                    self.add_method(method, id);
+                }
+            }
+            AstNode::TypeAlias(node) => {
+                let label = format!(
+                    "Type Alias: {}{} = {}",
+                    node.name,
+                    display_generics(&node.generics),
+                    node.aliased_type.display()
+                );
+                self.node(&label, parent);
+            }
+            AstNode::Const(node) => {
+                let label = format!(
+                    "Const: {}: {} = {}",
+                    node.name,
+                    node.const_type.display(),
+                    node.value
+                );
+                self.node(&label, parent);
+            }
+            AstNode::Static(node) => {
+                let label = format!(
+                    "Static: {}{}: {} = {}",
+                    if node.is_mut { "mut " } else { "" },
+                    node.name,
+                    node.static_type.display(),
+                    node.value
+                );
+                self.node(&label, parent);
+            }
+            AstNode::Use(node) => {
+                self.node(&format!("Use: {}", node.path), parent);
+            }
+            AstNode::Impl(node) => {
+                let label = format!(
+                    "Impl{}: {}{}{}",
+                    display_generics(&node.generics),
+                    display_impl_trait(node),
+                    node.self_type.display(),
+                    display_where_clause(&node.where_clause)
+                );
+                let id = self.node(&label, parent);
+                for item in &node.items {
+                    match item {
+                        ImplItem::Method(method) => self.add_method(method, id),
+                        ImplItem::Const(c) => {
+                            let label = format!(
+                                "Const: {}: {} = {}",
+                                c.name,
+                                c.const_type.display(),
+                                c.value
+                            );
+                            self.node(&label, Some(id));
+                        }
+                        ImplItem::Type(t) => {
+                            let label = format!("Type: {} = {}", t.name, t.aliased_type.display());
+                            self.node(&label, Some(id));
+                        }
+                    }
+                }
+            }
+            AstNode::Module(node) => {
+                let id = self.node(&format!("Module: {}", node.name), parent);
+                for item in &node.items {
+                    self.add(item, Some(id));
+                }
+            }
+            AstNode::Extern(node) => {
+                let id = self.node(&format!("Extern: \"{}\"", node.abi), parent);
+                for function in &node.functions {
+                    self.add_method(&function.signature, id);
+                }
+            }
+            AstNode::Function(node) => {
+                let id = self.node(&format!("Function: extern \"{}\"", node.abi), parent);
+                self.add_method(&node.signature, id);
+            }
+            AstNode::MacroDef(node) => {
+                self.node(&format!("Macro: {}", node.name), parent);
+            }
+            AstNode::Opaque(source) => {
+                self.node(&format!("Opaque: {}", opaque_label(source)), parent);
+            }
+        }
+    }
+
+    fn add_method(&mut self, method: &MethodNode, parent: usize) {
+        let label = format!(
+            "Method: {}{}",
+            method.name,
+            display_generics(&method.generics)
+        );
+        let id = self.node(&label, Some(parent));
+        for param in &method.params {
+            let label = format!("Param: {}: {}", param.name, param.param_type.display());
+            self.node(&label, Some(id));
+        }
+        if let Some(return_type) = &method.return_type {
+            self.node(&format!("Return Type: {}", return_type.display()), Some(id));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_to_dot_is_deterministic() {
+        let input = r#"
+            pub mod geometry {
+                pub struct Point { x: f64, y: f64 }
+                pub trait Shape { fn area(&self) -> f64; }
+                pub enum Kind { Circle(f64), Label { text: String } }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let first = ast.to_dot();
+        assert_eq!(first, ast.to_dot());
+        assert_eq!(first, input.parse::<AstNode>().unwrap().to_dot());
+    }
+
+    #[test]
+    fn test_to_dot_numbers_nodes_in_pre_order() {
+        let ast = "pub trait Shape { fn area(&self) -> f64; }"
+            .parse::<AstNode>()
+            .unwrap();
+
+        let expected = "\
+digraph ast {
+    node [shape=box];
+    n0 [label=\"Trait: Shape\"];
+    n1 [label=\"Method: area\"];
+    n0 -> n1;
+    n2 [label=\"Param: &self: &self\"];
+    n1 -> n2;
+    n3 [label=\"Return Type: f64\"];
+    n1 -> n3;
+}
+";
+        assert_eq!(ast.to_dot(), expected);
+    }
+}
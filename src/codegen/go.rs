@@ -0,0 +1,340 @@
+use super::{field_name, principal_trait, to_pascal_case, type_args, type_param_names};
+use crate::{AstNode, GenericParam, MethodNode, StructNode, TraitNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as Go source. Structs become `type Foo struct` with
+    /// exported, PascalCased fields and traits become `type FooInterface
+    /// interface`. Trait methods without a receiver have no Go counterpart
+    /// and are left out, as are other items.
+    pub fn to_go(&self) -> String {
+        let mut declarations = Vec::new();
+        add_declarations(self, &mut declarations);
+        let mut source = declarations.join("\n\n");
+        source.push('\n');
+        source
+    }
+}
+
+fn add_declarations(node: &AstNode, declarations: &mut Vec<String>) {
+    match node {
+        AstNode::Trait(node) => declarations.push(interface(node)),
+        AstNode::Struct(node) => declarations.push(structure(node)),
+        AstNode::Module(node) => node
+            .items
+            .iter()
+            .for_each(|item| add_declarations(item, declarations)),
+        AstNode::Enum(_)
+        | AstNode::TypeAlias(_)
+        | AstNode::Const(_)
+        | AstNode::Static(_)
+        | AstNode::Use(_)
+        | AstNode::Impl(_)
+        | AstNode::Extern(_)
+        | AstNode::Function(_)
+        | AstNode::MacroDef(_)
+        | AstNode::Opaque(_) => {}
+    }
+}
+
+/// Renders Rust type parameters as Go type parameters constrained by `any`,
+/// or by `comparable` when `types` use them as map keys. Lifetimes and
+/// const parameters have no Go counterpart.
+fn type_params<'a>(
+    generics: &[GenericParam],
+    types: impl IntoIterator<Item = &'a TypeNode>,
+) -> String {
+    let names = type_param_names(generics);
+    if names.is_empty() {
+        return String::new();
+    }
+    let mut keys = Vec::new();
+    types
+        .into_iter()
+        .for_each(|ty| collect_map_keys(ty, &mut keys));
+    let constraint = |name: &str| {
+        if keys.contains(&name) {
+            "comparable"
+        } else {
+            "any"
+        }
+    };
+
+    // Runs of parameters with the same constraint share it, as in `[K, V any]`
+    let mut groups: Vec<(Vec<&str>, &str)> = Vec::new();
+    for name in names {
+        match groups.last_mut() {
+            Some((group, last)) if *last == constraint(name) => group.push(name),
+            _ => groups.push((vec![name], constraint(name))),
+        }
+    }
+    let groups: Vec<String> = groups
+        .iter()
+        .map(|(group, constraint)| format!("{} {}", group.join(", "), constraint))
+        .collect();
+    format!("[{}]", groups.join(", "))
+}
+
+/// Collects the simple type names used as keys of maps and sets in `ty`.
+fn collect_map_keys<'a>(ty: &'a TypeNode, keys: &mut Vec<&'a str>) {
+    match ty {
+        TypeNode::Simple(_) => {}
+        TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+            collect_map_keys(inner, keys)
+        }
+        TypeNode::Generic { name, args } => {
+            let is_map = matches!(
+                name.as_str(),
+                "HashMap" | "BTreeMap" | "HashSet" | "BTreeSet"
+            );
+            if let (true, Some(TypeNode::Simple(key))) = (is_map, args.first()) {
+                keys.push(key);
+            }
+            args.iter().for_each(|arg| collect_map_keys(arg, keys));
+        }
+        TypeNode::Tuple(elements) => elements.iter().for_each(|e| collect_map_keys(e, keys)),
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            if let Some(ty) = principal_trait(bounds) {
+                collect_map_keys(ty, keys);
+            }
+        }
+    }
+}
+
+fn structure(node: &StructNode) -> String {
+    let fields: Vec<(String, String)> = node
+        .fields
+        .iter()
+        .map(|f| {
+            (
+                to_pascal_case(&field_name(&f.name)),
+                go_type(&f.field_type, &node.name),
+            )
+        })
+        .collect();
+    let type_params = type_params(&node.generics, node.fields.iter().map(|f| &*f.field_type));
+    let header = format!("type {}{} struct", node.name, type_params);
+    if fields.is_empty() {
+        return format!("{}{{}}", header);
+    }
+
+    // Align field types the way gofmt does
+    let width = fields.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let lines: Vec<String> = fields
+        .iter()
+        .map(|(name, ty)| format!("\t{:width$} {}", name, ty, width = width))
+        .collect();
+    format!("{} {{\n{}\n}}", header, lines.join("\n"))
+}
+
+fn interface(node: &TraitNode) -> String {
+    let name = format!("{}Interface", node.name);
+    let methods: Vec<String> = node
+        .methods
+        .iter()
+        .filter(|m| m.receiver().is_some())
+        .map(|m| format!("\t{}", method(m, &name)))
+        .collect();
+    let types = node.methods.iter().flat_map(|m| {
+        m.params_without_self()
+            .iter()
+            .map(|p| &*p.param_type)
+            .chain(m.return_type.as_deref())
+    });
+    let header = format!(
+        "type {}{} interface",
+        name,
+        type_params(&node.generics, types)
+    );
+    if methods.is_empty() {
+        return format!("{}{{}}", header);
+    }
+    format!("{} {{\n{}\n}}", header, methods.join("\n"))
+}
+
+fn method(method: &MethodNode, self_name: &str) -> String {
+    let params: Vec<String> = method
+        .params_without_self()
+        .iter()
+        .map(|p| format!("{} {}", p.name, go_type(&p.param_type, self_name)))
+        .collect();
+    format!(
+        "{}({}){}",
+        to_pascal_case(&method.name),
+        params.join(", "),
+        results(method.return_type.as_deref(), self_name)
+    )
+}
+
+/// Renders a return type as Go results. Tuples become multiple results and
+/// `Result<T, E>` becomes `(T, error)`.
+fn results(return_type: Option<&TypeNode>, self_name: &str) -> String {
+    match return_type {
+        None => String::new(),
+        Some(TypeNode::Tuple(elements)) if elements.is_empty() => String::new(),
+        Some(TypeNode::Tuple(elements)) => {
+            let elements: Vec<String> = elements.iter().map(|e| go_type(e, self_name)).collect();
+            format!(" ({})", elements.join(", "))
+        }
+        Some(TypeNode::Generic { name, args }) if name == "Result" && !args.is_empty() => {
+            match &args[0] {
+                TypeNode::Tuple(elements) if elements.is_empty() => " error".to_string(),
+                ok => format!(" ({}, error)", go_type(ok, self_name)),
+            }
+        }
+        Some(ty) => format!(" {}", go_type(ty, self_name)),
+    }
+}
+
+/// Maps a Rust type to Go. `Self` resolves to `self_name`, the type being
+/// generated.
+fn go_type(ty: &TypeNode, self_name: &str) -> String {
+    let map = |ty: &TypeNode| go_type(ty, self_name);
+    match ty {
+        TypeNode::Simple(name) => match name.as_str() {
+            "i8" | "i16" | "i32" | "i64" => format!("int{}", &name[1..]),
+            "u8" | "u16" | "u32" | "u64" => format!("uint{}", &name[1..]),
+            "isize" => "int".to_string(),
+            "usize" => "uint".to_string(),
+            "f32" => "float32".to_string(),
+            "f64" => "float64".to_string(),
+            "String" | "str" => "string".to_string(),
+            "char" => "rune".to_string(),
+            "bool" => "bool".to_string(),
+            "Self" => self_name.to_string(),
+            _ => name.rsplit("::").next().unwrap_or(name).to_string(),
+        },
+        TypeNode::Reference { inner, .. } => map(inner),
+        TypeNode::RawPointer { inner, .. } => format!("*{}", map(inner)),
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Cow", [inner]) => map(inner),
+            // Nested wrappers such as `Option<Box<T>>` share a single pointer
+            ("Option" | "Box" | "Rc" | "Arc", [inner]) => {
+                let inner = map(inner);
+                if inner.starts_with('*') {
+                    inner
+                } else {
+                    format!("*{}", inner)
+                }
+            }
+            ("Vec" | "VecDeque" | "[]", [inner]) => format!("[]{}", map(inner)),
+            ("HashSet" | "BTreeSet", [inner]) => format!("map[{}]struct{{}}", map(inner)),
+            ("HashMap" | "BTreeMap", [key, value]) => format!("map[{}]{}", map(key), map(value)),
+            _ => {
+                let args: Vec<String> = args.iter().map(map).collect();
+                format!(
+                    "{}[{}]",
+                    name.rsplit("::").next().unwrap_or(name),
+                    args.join(", ")
+                )
+            }
+        },
+        TypeNode::Tuple(elements) if elements.is_empty() => "struct{}".to_string(),
+        TypeNode::Tuple(elements) => {
+            let fields: Vec<String> = elements
+                .iter()
+                .enumerate()
+                .map(|(i, e)| format!("Field{} {}", i, map(e)))
+                .collect();
+            format!("struct{{ {} }}", fields.join("; "))
+        }
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            principal_trait(bounds).map_or("any".to_string(), map)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_go() {
+        let input = r#"
+            pub struct Cache<K, V> {
+                initial: char,
+                capacity: usize,
+                hits: isize,
+                keys: HashSet<K>,
+                entries: BTreeMap<K, V>,
+                bounds: (u16, u16),
+                marker: (),
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // Map keys must be `comparable`, and sets are maps to empty structs
+        let expected = "\
+type Cache[K comparable, V any] struct {
+\tInitial  rune
+\tCapacity uint
+\tHits     int
+\tKeys     map[K]struct{}
+\tEntries  map[K]V
+\tBounds   struct{ Field0 uint16; Field1 uint16 }
+\tMarker   struct{}
+}
+";
+        assert_eq!(ast.to_go(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert_eq!(marker.to_go(), "type Marker struct{}\n");
+    }
+
+    #[test]
+    fn test_trait_to_go() {
+        let input = r#"
+            pub trait Resolver {
+                fn lookup(&self, host: &str) -> Result<Vec<u8>, Error>;
+                fn flush(&mut self) -> Result<(), Error>;
+                fn split(&self, addr: String) -> (String, u16);
+                fn child(&self) -> Self;
+                fn system() -> Self;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+type ResolverInterface interface {
+\tLookup(host string) ([]uint8, error)
+\tFlush() error
+\tSplit(addr string) (string, uint16)
+\tChild() ResolverInterface
+}
+";
+        assert_eq!(ast.to_go(), expected);
+
+        let index = "pub trait Index<T, K, V> { fn get(&self, key: K) -> Option<V>; fn all(&self) -> Vec<HashMap<K, V>>; fn tag(&self) -> T; }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert!(index
+            .to_go()
+            .starts_with("type IndexInterface[T any, K comparable, V any] interface {\n"));
+    }
+
+    #[test]
+    fn test_pointer_optionals_to_go() {
+        let input = r#"
+            pub struct Node {
+                value: i32,
+                next: Option<Box<Node>>,
+                parent: Rc<Node>,
+                label: Option<String>,
+                children: Vec<Node>,
+                raw: *const Node,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+type Node struct {
+\tValue    int32
+\tNext     *Node
+\tParent   *Node
+\tLabel    *string
+\tChildren []Node
+\tRaw      *Node
+}
+";
+        assert_eq!(ast.to_go(), expected);
+    }
+}
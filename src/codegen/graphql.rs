@@ -0,0 +1,410 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{
+    field_name, principal_trait, to_camel_case, to_pascal_case, to_screaming_snake_case, type_args,
+};
+use crate::{AstNode, EnumNode, FieldNode, StructNode, TraitNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as GraphQL SDL. Structs become object types, enums
+    /// become GraphQL enums (or unions of object types when variants carry
+    /// data) and traits become interfaces. Rust types without a built-in
+    /// GraphQL counterpart are declared as custom scalars.
+    ///
+    /// GraphQL does not accept object types as arguments, so a method
+    /// argument of a struct type gets an `input` type with the struct's
+    /// fields, named like `UserInput`. Arguments of unions and interfaces
+    /// become custom scalars named the same way.
+    pub fn to_graphql_schema(&self) -> String {
+        let mut schema = Schema::default();
+        schema.collect_names(self);
+        schema.add(self);
+        schema.finish()
+    }
+}
+
+#[derive(Default)]
+struct Schema {
+    /// Names of the object, enum and interface types the schema defines.
+    defined: BTreeSet<String>,
+    /// Fields of the structs the schema defines, for their input types.
+    objects: BTreeMap<String, Vec<FieldNode>>,
+    /// Names of the field-less enums, which are valid input types as is.
+    enums: BTreeSet<String>,
+    /// Names of the input types already emitted.
+    inputs: BTreeSet<String>,
+    scalars: BTreeSet<String>,
+    definitions: Vec<String>,
+}
+
+impl Schema {
+    fn collect_names(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Trait(node) => {
+                self.defined.insert(node.name.clone());
+            }
+            AstNode::Struct(node) => {
+                self.defined.insert(node.name.clone());
+                self.objects.insert(node.name.clone(), node.fields.clone());
+            }
+            AstNode::Enum(node) => {
+                self.defined.insert(node.name.clone());
+                if node.variants.iter().all(|v| v.associated_data.is_none()) {
+                    self.enums.insert(node.name.clone());
+                }
+            }
+            AstNode::Module(node) => node.items.iter().for_each(|item| self.collect_names(item)),
+            _ => {}
+        }
+    }
+
+    fn add(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Trait(node) => self.add_interface(node),
+            AstNode::Struct(node) => {
+                let definition = self.object("type", &node.name, &node.fields);
+                self.definitions.push(definition);
+            }
+            AstNode::Enum(node) => self.add_enum(node),
+            AstNode::TypeAlias(node) => {
+                self.scalars.insert(node.name.clone());
+            }
+            AstNode::Module(node) => node.items.iter().for_each(|item| self.add(item)),
+            AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Use(_)
+            | AstNode::Impl(_)
+            | AstNode::Extern(_)
+            | AstNode::Function(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => {}
+        }
+    }
+
+    fn add_interface(&mut self, node: &TraitNode) {
+        // `Self` is the interface itself
+        let resolve_self = |ty: &TypeNode| {
+            let mut ty = ty.clone();
+            ty.rename("Self", &node.name);
+            ty
+        };
+        let mut fields = Vec::new();
+        for method in &node.methods {
+            let args: Vec<String> = method
+                .params_without_self()
+                .iter()
+                .map(|p| {
+                    format!(
+                        "{}: {}",
+                        to_camel_case(&p.name),
+                        self.field_type(&resolve_self(&p.param_type), true)
+                    )
+                })
+                .collect();
+            let return_type = match &method.return_type {
+                Some(ty) => self.field_type(&resolve_self(ty), false),
+                None => self.scalar("Void"),
+            };
+            let args = if args.is_empty() {
+                String::new()
+            } else {
+                format!("({})", args.join(", "))
+            };
+            fields.push(format!(
+                "{}{}: {}",
+                to_camel_case(&method.name),
+                args,
+                return_type
+            ));
+        }
+        self.definitions
+            .push(block(&format!("interface {}", node.name), fields));
+    }
+
+    fn add_enum(&mut self, node: &EnumNode) {
+        if node.variants.iter().all(|v| v.associated_data.is_none()) {
+            let values = node
+                .variants
+                .iter()
+                .map(|v| to_screaming_snake_case(&v.name))
+                .collect();
+            self.definitions
+                .push(block(&format!("enum {}", node.name), values));
+            return;
+        }
+
+        // GraphQL enums cannot carry data, so each variant becomes an object
+        // type and the enum a union of them.
+        let mut members = Vec::new();
+        for variant in &node.variants {
+            let member = format!("{}{}", node.name, variant.name);
+            let fields = match variant.associated_data.as_deref() {
+                Some(AstNode::Struct(StructNode { fields, .. })) => fields.as_slice(),
+                _ => &[],
+            };
+            let definition = self.object("type", &member, fields);
+            self.definitions.push(definition);
+            members.push(member);
+        }
+        self.definitions
+            .push(format!("union {} = {}", node.name, members.join(" | ")));
+    }
+
+    fn object(&mut self, keyword: &str, name: &str, fields: &[FieldNode]) -> String {
+        let input = keyword == "input";
+        let fields = fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "{}: {}",
+                    to_camel_case(&field_name(&f.name)),
+                    self.field_type(&f.field_type, input)
+                )
+            })
+            .collect();
+        block(&format!("{} {}", keyword, name), fields)
+    }
+
+    /// Renders a type as non-null unless it is an `Option`. With `input`,
+    /// types are rendered for an argument or an input type field.
+    fn field_type(&mut self, ty: &TypeNode, input: bool) -> String {
+        match ty {
+            TypeNode::Reference { inner, .. } => self.field_type(inner, input),
+            // Raw pointers may be null
+            TypeNode::RawPointer { inner, .. } => self.nullable_type(inner, input),
+            TypeNode::Generic { name, args } if name == "Option" && args.len() == 1 => {
+                self.nullable_type(&args[0], input)
+            }
+            _ => format!("{}!", self.nullable_type(ty, input)),
+        }
+    }
+
+    fn nullable_type(&mut self, ty: &TypeNode, input: bool) -> String {
+        match ty {
+            TypeNode::Simple(name) => match name.as_str() {
+                "i8" | "i16" | "i32" | "u8" | "u16" => "Int".to_string(),
+                "f32" | "f64" => "Float".to_string(),
+                "String" | "str" | "char" => "String".to_string(),
+                "bool" => "Boolean".to_string(),
+                _ => self.named_type(name, input),
+            },
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                self.nullable_type(inner, input)
+            }
+            TypeNode::Generic { name, args } if type_args(name, args).len() == 1 => {
+                match name.as_str() {
+                    "Option" | "Box" | "Rc" | "Arc" | "Cow" => self.nullable_type(&args[0], input),
+                    "Vec" | "VecDeque" | "HashSet" | "BTreeSet" | "[]" => {
+                        format!("[{}]", self.field_type(&args[0], input))
+                    }
+                    _ => self.named_type(name, input),
+                }
+            }
+            TypeNode::Generic { name, .. } => self.named_type(name, input),
+            TypeNode::Tuple(elements) if elements.is_empty() => self.scalar("Void"),
+            TypeNode::Tuple(_) => self.scalar("Tuple"),
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                match principal_trait(bounds) {
+                    Some(ty) => self.nullable_type(ty, input),
+                    None => self.scalar("Any"),
+                }
+            }
+        }
+    }
+
+    /// Refers to a type defined by this schema, or declares a custom scalar
+    /// for it. As an input, a struct refers to its input type, declaring it
+    /// on first use.
+    fn named_type(&mut self, name: &str, input: bool) -> String {
+        let name = name.rsplit("::").next().unwrap_or(name);
+        if input && self.defined.contains(name) && !self.enums.contains(name) {
+            match self.objects.get(name).cloned() {
+                Some(fields) => self.input_type(name, &fields),
+                None => self.scalar(&format!("{}Input", name)),
+            }
+        } else if self.defined.contains(name) {
+            name.to_string()
+        } else if name.starts_with(char::is_lowercase) {
+            self.scalar(&to_pascal_case(name))
+        } else {
+            self.scalar(name)
+        }
+    }
+
+    fn input_type(&mut self, name: &str, fields: &[FieldNode]) -> String {
+        let input_name = format!("{}Input", name);
+        // Inserted first, so that recursive structs refer to themselves
+        if self.inputs.insert(input_name.clone()) {
+            let definition = self.object("input", &input_name, fields);
+            self.definitions.push(definition);
+        }
+        input_name
+    }
+
+    fn scalar(&mut self, name: &str) -> String {
+        self.scalars.insert(name.to_string());
+        name.to_string()
+    }
+
+    fn finish(self) -> String {
+        let mut sections: Vec<String> = Vec::new();
+        if !self.scalars.is_empty() {
+            let scalars: Vec<String> = self
+                .scalars
+                .iter()
+                .map(|s| format!("scalar {}", s))
+                .collect();
+            sections.push(scalars.join("\n"));
+        }
+        sections.extend(self.definitions);
+        let mut schema = sections.join("\n\n");
+        schema.push('\n');
+        schema
+    }
+}
+
+/// Renders a `keyword Name { ... }` block. GraphQL requires at least one
+/// member, so empty blocks get a placeholder field.
+fn block(header: &str, members: Vec<String>) -> String {
+    let members = if members.is_empty() {
+        vec!["_: Boolean".to_string()]
+    } else {
+        members
+    };
+    let body: Vec<String> = members.iter().map(|m| format!("  {}", m)).collect();
+    format!("{} {{\n{}\n}}", header, body.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_graphql_schema() {
+        let input = r#"
+            pub struct Session {
+                r#type: String,
+                expires_at: u64,
+                user_ids: Vec<Uuid>,
+                retries: u16,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // 64-bit integers overflow GraphQL's `Int` and become scalars
+        let expected = "\
+scalar U64
+scalar Uuid
+
+type Session {
+  type: String!
+  expiresAt: U64!
+  userIds: [Uuid!]!
+  retries: Int!
+}
+";
+        assert_eq!(ast.to_graphql_schema(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert_eq!(
+            marker.to_graphql_schema(),
+            "type Marker {\n  _: Boolean\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_enum_and_trait_to_graphql_schema() {
+        let color = "pub enum Color { Red, DarkGreen }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            color.to_graphql_schema(),
+            "enum Color {\n  RED\n  DARK_GREEN\n}\n"
+        );
+
+        let message = "pub enum Message { Quit, Write(String) }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            message.to_graphql_schema(),
+            "type MessageQuit {\n  _: Boolean\n}\n\n\
+             type MessageWrite {\n  field0: String!\n}\n\n\
+             union Message = MessageQuit | MessageWrite\n"
+        );
+
+        let sessions = "pub trait SessionStore { fn find_by_token(&self, token: &str) -> Option<Session>; fn touch(&mut self); fn count(&self) -> u32; }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            sessions.to_graphql_schema(),
+            "scalar Session\nscalar U32\nscalar Void\n\n\
+             interface SessionStore {\n  \
+             findByToken(token: String!): Session\n  \
+             touch: Void\n  \
+             count: U32!\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_object_arguments_to_graphql_schema() {
+        let input = r#"
+            pub mod api {
+                pub struct Point { x: f64, y: f64 }
+                pub struct Path { points: Vec<Point>, next: Option<Box<Path>> }
+                pub enum Mode { Fast, Exact }
+                pub enum Shape { Dot(Point), Line(Point, Point) }
+                pub trait Canvas {
+                    fn draw(&self, path: &Path, mode: Mode) -> Canvas;
+                    fn fill(&self, shape: Shape) -> Option<Self>;
+                    fn origin(&self) -> Point;
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+        let schema = ast.to_graphql_schema();
+
+        assert!(schema.starts_with("scalar ShapeInput\n\n"));
+        assert!(schema.contains(
+            "interface Canvas {\n  \
+             draw(path: PathInput!, mode: Mode!): Canvas!\n  \
+             fill(shape: ShapeInput!): Canvas\n  \
+             origin: Point!\n}"
+        ));
+        assert!(
+            schema.contains("input PathInput {\n  points: [PointInput!]!\n  next: PathInput\n}")
+        );
+        assert!(schema.contains("input PointInput {\n  x: Float!\n  y: Float!\n}"));
+        assert!(schema.contains("type Point {\n  x: Float!\n  y: Float!\n}"));
+        assert_eq!(schema.matches("input PathInput").count(), 1);
+    }
+
+    #[test]
+    fn test_list_nullability_to_graphql_schema() {
+        let input = r#"
+            pub struct Order {
+                id: i64,
+                lines: Vec<Option<LineItem>>,
+                notes: Option<Vec<String>>,
+                customer: Box<Customer>,
+                placed_at: chrono::DateTime,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+scalar Customer
+scalar DateTime
+scalar I64
+scalar LineItem
+
+type Order {
+  id: I64!
+  lines: [LineItem]!
+  notes: [String!]
+  customer: Customer!
+  placedAt: DateTime!
+}
+";
+        assert_eq!(ast.to_graphql_schema(), expected);
+    }
+}
@@ -0,0 +1,348 @@
+use std::collections::BTreeSet;
+
+use super::{
+    angle_type_params, field_name, principal_trait, to_camel_case, to_pascal_case, type_args,
+};
+use crate::{AstNode, MethodNode, StructNode, TraitNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as Java source. Structs become classes with private
+    /// fields and public getters and setters, and traits become interfaces.
+    /// Trait methods without a receiver are left out, since static interface
+    /// methods need a body, as are other items.
+    pub fn to_java(&self) -> String {
+        let mut file = JavaFile::default();
+        file.add(self);
+        file.finish()
+    }
+}
+
+#[derive(Default)]
+struct JavaFile {
+    /// Classes imported from `java.util`.
+    imports: BTreeSet<String>,
+    declarations: Vec<String>,
+}
+
+impl JavaFile {
+    fn add(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Trait(node) => self.add_interface(node),
+            AstNode::Struct(node) => self.add_class(node),
+            AstNode::Module(node) => node.items.iter().for_each(|item| self.add(item)),
+            AstNode::Enum(_)
+            | AstNode::TypeAlias(_)
+            | AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Use(_)
+            | AstNode::Impl(_)
+            | AstNode::Extern(_)
+            | AstNode::Function(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => {}
+        }
+    }
+
+    fn add_class(&mut self, node: &StructNode) {
+        let name = format!("{}{}", node.name, angle_type_params(&node.generics));
+        let fields: Vec<(String, String)> = node
+            .fields
+            .iter()
+            .map(|f| {
+                (
+                    to_camel_case(&field_name(&f.name)),
+                    self.java_type(&f.field_type, &name, false),
+                )
+            })
+            .collect();
+
+        let mut members: Vec<String> = fields
+            .iter()
+            .map(|(name, ty)| format!("    private {} {};", ty, name))
+            .collect();
+        for (name, ty) in &fields {
+            let prefix = if ty == "boolean" { "is" } else { "get" };
+            members.push(format!(
+                "\n    public {} {}{}() {{\n        return {};\n    }}",
+                ty,
+                prefix,
+                to_pascal_case(name),
+                name
+            ));
+            members.push(format!(
+                "\n    public void set{}({} {}) {{\n        this.{} = {};\n    }}",
+                to_pascal_case(name),
+                ty,
+                name,
+                name,
+                name
+            ));
+        }
+        self.declarations
+            .push(block(&format!("public class {}", name), members));
+    }
+
+    fn add_interface(&mut self, node: &TraitNode) {
+        let name = format!("{}{}", node.name, angle_type_params(&node.generics));
+        let methods = node
+            .methods
+            .iter()
+            .filter(|m| m.receiver().is_some())
+            .map(|m| format!("    {};", self.method(m, &name)))
+            .collect();
+        self.declarations
+            .push(block(&format!("public interface {}", name), methods));
+    }
+
+    fn method(&mut self, method: &MethodNode, self_name: &str) -> String {
+        let params: Vec<String> = method
+            .params_without_self()
+            .iter()
+            .map(|p| {
+                format!(
+                    "{} {}",
+                    self.java_type(&p.param_type, self_name, false),
+                    to_camel_case(&p.name)
+                )
+            })
+            .collect();
+        let return_type = match method.return_type.as_deref() {
+            None => "void".to_string(),
+            Some(TypeNode::Tuple(elements)) if elements.is_empty() => "void".to_string(),
+            Some(ty) => self.java_type(ty, self_name, false),
+        };
+        format!(
+            "{} {}({})",
+            return_type,
+            to_camel_case(&method.name),
+            params.join(", ")
+        )
+    }
+
+    /// Maps a Rust type to Java. Primitives are boxed when `boxed` is set,
+    /// as generic type arguments require. `Self` resolves to `self_name`.
+    fn java_type(&mut self, ty: &TypeNode, self_name: &str, boxed: bool) -> String {
+        match ty {
+            TypeNode::Simple(name) => {
+                let (primitive, wrapper) = match name.as_str() {
+                    "i8" => ("byte", "Byte"),
+                    "i16" | "u8" => ("short", "Short"),
+                    "i32" | "u16" => ("int", "Integer"),
+                    "i64" | "u32" | "u64" | "isize" | "usize" => ("long", "Long"),
+                    "f32" => ("float", "Float"),
+                    "f64" => ("double", "Double"),
+                    "char" => ("char", "Character"),
+                    "bool" => ("boolean", "Boolean"),
+                    "String" | "str" => return "String".to_string(),
+                    "Self" => return self_name.to_string(),
+                    _ => return name.rsplit("::").next().unwrap_or(name).to_string(),
+                };
+                if boxed { wrapper } else { primitive }.to_string()
+            }
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                self.java_type(inner, self_name, boxed)
+            }
+            TypeNode::Generic { name, args } => {
+                let args = type_args(name, args);
+                let class = match (name.as_str(), args) {
+                    ("Box" | "Rc" | "Arc" | "Cow", [inner]) => {
+                        return self.java_type(inner, self_name, boxed);
+                    }
+                    ("Option", [_]) => "Optional",
+                    ("Vec" | "VecDeque" | "[]", [_]) => "List",
+                    ("HashSet" | "BTreeSet", [_]) => "Set",
+                    ("HashMap" | "BTreeMap", [_, _]) => "Map",
+                    _ => name.rsplit("::").next().unwrap_or(name),
+                };
+                if matches!(class, "Optional" | "List" | "Set" | "Map") {
+                    self.imports.insert(class.to_string());
+                }
+                let args: Vec<String> = args
+                    .iter()
+                    .map(|arg| self.java_type(arg, self_name, true))
+                    .collect();
+                format!("{}<{}>", class, args.join(", "))
+            }
+            TypeNode::Tuple(elements) if elements.is_empty() => "Void".to_string(),
+            TypeNode::Tuple(_) => "Object[]".to_string(),
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                match principal_trait(bounds) {
+                    Some(ty) => self.java_type(ty, self_name, boxed),
+                    None => "Object".to_string(),
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut sections = Vec::new();
+        if !self.imports.is_empty() {
+            let imports: Vec<String> = self
+                .imports
+                .iter()
+                .map(|import| format!("import java.util.{};", import))
+                .collect();
+            sections.push(imports.join("\n"));
+        }
+        sections.extend(self.declarations);
+        let mut source = sections.join("\n\n");
+        source.push('\n');
+        source
+    }
+}
+
+fn block(header: &str, members: Vec<String>) -> String {
+    if members.is_empty() {
+        return format!("{} {{\n}}", header);
+    }
+    format!("{} {{\n{}\n}}", header, members.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_java() {
+        let input = r#"
+            pub struct Pixel {
+                alpha: u8,
+                visible: bool,
+                corners: HashSet<(u32, u32)>,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // Unsigned types widen to the next signed primitive, and booleans get
+        // an `is` getter
+        let expected = "\
+import java.util.Set;
+
+public class Pixel {
+    private short alpha;
+    private boolean visible;
+    private Set<Object[]> corners;
+
+    public short getAlpha() {
+        return alpha;
+    }
+
+    public void setAlpha(short alpha) {
+        this.alpha = alpha;
+    }
+
+    public boolean isVisible() {
+        return visible;
+    }
+
+    public void setVisible(boolean visible) {
+        this.visible = visible;
+    }
+
+    public Set<Object[]> getCorners() {
+        return corners;
+    }
+
+    public void setCorners(Set<Object[]> corners) {
+        this.corners = corners;
+    }
+}
+";
+        assert_eq!(ast.to_java(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert_eq!(marker.to_java(), "public class Marker {\n}\n");
+    }
+
+    #[test]
+    fn test_trait_to_java() {
+        let input = r#"
+            pub trait Renderer {
+                fn draw(&mut self, pixel: Pixel, scale: u32) -> ();
+                fn clone_boxed(&self) -> Box<Self>;
+                fn fill(&self) -> (u8, u8);
+                fn create() -> Self;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+public interface Renderer {
+    void draw(Pixel pixel, long scale);
+    Renderer cloneBoxed();
+    Object[] fill();
+}
+";
+        assert_eq!(ast.to_java(), expected);
+
+        let factory = "pub trait Factory { fn create() -> Self; }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(factory.to_java(), "public interface Factory {\n}\n");
+    }
+
+    #[test]
+    fn test_generics_to_java() {
+        let input = r#"
+            pub mod store {
+                pub struct Wrapper<'a, T> { value: &'a T }
+                pub trait Repo<K, V> {
+                    fn get(&self, key: K) -> Option<V>;
+                    fn fork(&self) -> Self;
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+import java.util.Optional;
+
+public class Wrapper<T> {
+    private T value;
+
+    public T getValue() {
+        return value;
+    }
+
+    public void setValue(T value) {
+        this.value = value;
+    }
+}
+
+public interface Repo<K, V> {
+    Optional<V> get(K key);
+    Repo<K, V> fork();
+}
+";
+        assert_eq!(ast.to_java(), expected);
+    }
+
+    #[test]
+    fn test_boxed_generic_arguments_to_java() {
+        let input = r#"
+            pub trait Meter {
+                fn read(&self, channel: u8, gain: f32) -> Option<f64>;
+                fn history(&self) -> Vec<i64>;
+                fn flags(&self) -> HashMap<char, bool>;
+                fn samples(&self) -> Vec<[i8; 4]>;
+                fn ready(&self) -> bool;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+import java.util.List;
+import java.util.Map;
+import java.util.Optional;
+
+public interface Meter {
+    Optional<Double> read(short channel, float gain);
+    List<Long> history();
+    Map<Character, Boolean> flags();
+    List<List<Byte>> samples();
+    boolean ready();
+}
+";
+        assert_eq!(ast.to_java(), expected);
+    }
+}
@@ -0,0 +1,278 @@
+use super::{angle_type_params, field_name, principal_trait, to_camel_case, type_args};
+use crate::{AstNode, MethodNode, StructNode, TraitNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as Kotlin source. Structs become `data class`es with a
+    /// `val` constructor property per field and traits become `interface`s.
+    /// Trait methods without a receiver have no Kotlin interface counterpart
+    /// and are left out, as are other items.
+    pub fn to_kotlin(&self) -> String {
+        let mut declarations = Vec::new();
+        add_declarations(self, &mut declarations);
+        let mut source = declarations.join("\n\n");
+        source.push('\n');
+        source
+    }
+}
+
+fn add_declarations(node: &AstNode, declarations: &mut Vec<String>) {
+    match node {
+        AstNode::Trait(node) => declarations.push(interface(node)),
+        AstNode::Struct(node) => declarations.push(data_class(node)),
+        AstNode::Module(node) => node
+            .items
+            .iter()
+            .for_each(|item| add_declarations(item, declarations)),
+        AstNode::Enum(_)
+        | AstNode::TypeAlias(_)
+        | AstNode::Const(_)
+        | AstNode::Static(_)
+        | AstNode::Use(_)
+        | AstNode::Impl(_)
+        | AstNode::Extern(_)
+        | AstNode::Function(_)
+        | AstNode::MacroDef(_)
+        | AstNode::Opaque(_) => {}
+    }
+}
+
+fn data_class(node: &StructNode) -> String {
+    let name = format!("{}{}", node.name, angle_type_params(&node.generics));
+    // A data class needs at least one constructor property
+    if node.fields.is_empty() {
+        return format!("class {}", name);
+    }
+    let properties: Vec<String> = node
+        .fields
+        .iter()
+        .map(|f| {
+            format!(
+                "    val {}: {},",
+                to_camel_case(&field_name(&f.name)),
+                kotlin_type(&f.field_type, &name)
+            )
+        })
+        .collect();
+    format!("data class {}(\n{}\n)", name, properties.join("\n"))
+}
+
+fn interface(node: &TraitNode) -> String {
+    let name = format!("{}{}", node.name, angle_type_params(&node.generics));
+    let functions: Vec<String> = node
+        .methods
+        .iter()
+        .filter(|m| m.receiver().is_some())
+        .map(|m| format!("    {}", function(m, &name)))
+        .collect();
+    if functions.is_empty() {
+        return format!("interface {}", name);
+    }
+    format!("interface {} {{\n{}\n}}", name, functions.join("\n"))
+}
+
+fn function(method: &MethodNode, self_name: &str) -> String {
+    let params: Vec<String> = method
+        .params_without_self()
+        .iter()
+        .map(|p| {
+            format!(
+                "{}: {}",
+                to_camel_case(&p.name),
+                kotlin_type(&p.param_type, self_name)
+            )
+        })
+        .collect();
+    let return_type = match method.return_type.as_deref() {
+        None => String::new(),
+        Some(TypeNode::Tuple(elements)) if elements.is_empty() => String::new(),
+        Some(ty) => format!(": {}", kotlin_type(ty, self_name)),
+    };
+    format!(
+        "fun {}({}){}",
+        to_camel_case(&method.name),
+        params.join(", "),
+        return_type
+    )
+}
+
+/// Maps a Rust type to Kotlin. `Self` resolves to `self_name`, the class or
+/// interface being generated.
+fn kotlin_type(ty: &TypeNode, self_name: &str) -> String {
+    let map = |ty: &TypeNode| kotlin_type(ty, self_name);
+    match ty {
+        TypeNode::Simple(name) => match name.as_str() {
+            "i8" => "Byte".to_string(),
+            "i16" => "Short".to_string(),
+            "i32" => "Int".to_string(),
+            "i64" | "isize" => "Long".to_string(),
+            "u8" => "UByte".to_string(),
+            "u16" => "UShort".to_string(),
+            "u32" => "UInt".to_string(),
+            "u64" | "usize" => "ULong".to_string(),
+            "f32" => "Float".to_string(),
+            "f64" => "Double".to_string(),
+            "String" | "str" => "String".to_string(),
+            "char" => "Char".to_string(),
+            "bool" => "Boolean".to_string(),
+            "Self" => self_name.to_string(),
+            _ => name.rsplit("::").next().unwrap_or(name).to_string(),
+        },
+        TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => map(inner),
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => map(inner),
+            ("Option", [inner]) => {
+                let inner = map(inner);
+                if inner.ends_with('?') {
+                    inner
+                } else {
+                    format!("{}?", inner)
+                }
+            }
+            ("Vec" | "VecDeque" | "[]", [inner]) => format!("List<{}>", map(inner)),
+            ("HashSet" | "BTreeSet", [inner]) => format!("Set<{}>", map(inner)),
+            ("HashMap" | "BTreeMap", [key, value]) => {
+                format!("Map<{}, {}>", map(key), map(value))
+            }
+            _ => {
+                let args: Vec<String> = args.iter().map(map).collect();
+                format!(
+                    "{}<{}>",
+                    name.rsplit("::").next().unwrap_or(name),
+                    args.join(", ")
+                )
+            }
+        },
+        TypeNode::Tuple(elements) => match elements.as_slice() {
+            [] => "Unit".to_string(),
+            [first, second] => format!("Pair<{}, {}>", map(first), map(second)),
+            [first, second, third] => {
+                format!("Triple<{}, {}, {}>", map(first), map(second), map(third))
+            }
+            _ => "List<Any?>".to_string(),
+        },
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            principal_trait(bounds).map_or("Any".to_string(), map)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_kotlin() {
+        let input = r#"
+            pub struct Sample {
+                level: i8,
+                pitch: i16,
+                frame: i32,
+                offset: isize,
+                note: char,
+                labels: BTreeSet<String>,
+                quad: (u8, u8, u8, u8),
+                source: *const Buffer,
+                codec: Box<dyn Codec + Send>,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // Kotlin has no tuples beyond `Triple`
+        let expected = "\
+data class Sample(
+    val level: Byte,
+    val pitch: Short,
+    val frame: Int,
+    val offset: Long,
+    val note: Char,
+    val labels: Set<String>,
+    val quad: List<Any?>,
+    val source: Buffer,
+    val codec: Codec,
+)
+";
+        assert_eq!(ast.to_kotlin(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert_eq!(marker.to_kotlin(), "class Marker\n");
+    }
+
+    #[test]
+    fn test_trait_to_kotlin() {
+        let input = r#"
+            pub trait Mixer {
+                fn add_track(&mut self, track: Track) -> ();
+                fn mixed(&self) -> Self;
+                fn channel_count(&self) -> u16;
+                fn create(rate: u32) -> Self;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+interface Mixer {
+    fun addTrack(track: Track)
+    fun mixed(): Mixer
+    fun channelCount(): UShort
+}
+";
+        assert_eq!(ast.to_kotlin(), expected);
+
+        let factory = "pub trait Factory { fn create() -> Self; }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(factory.to_kotlin(), "interface Factory\n");
+    }
+
+    #[test]
+    fn test_generics_to_kotlin() {
+        let input = r#"
+            pub mod store {
+                pub struct Wrapper<'a, T> { value: &'a T, next: Option<Box<Self>> }
+                pub trait Repo<K, V> {
+                    fn get(&self, key: K) -> Option<V>;
+                    fn fork(&self) -> Self;
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+data class Wrapper<T>(
+    val value: T,
+    val next: Wrapper<T>?,
+)
+
+interface Repo<K, V> {
+    fun get(key: K): V?
+    fun fork(): Repo<K, V>
+}
+";
+        assert_eq!(ast.to_kotlin(), expected);
+    }
+
+    #[test]
+    fn test_tuples_and_unsigned_to_kotlin() {
+        let input = r#"
+            pub struct Route {
+                stops: Vec<(String, u32)>,
+                span: (f64, f64, f64),
+                fallback: Option<Option<u16>>,
+                attempts: u8,
+                distances: HashMap<String, u64>,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+data class Route(
+    val stops: List<Pair<String, UInt>>,
+    val span: Triple<Double, Double, Double>,
+    val fallback: UShort?,
+    val attempts: UByte,
+    val distances: Map<String, ULong>,
+)
+";
+        assert_eq!(ast.to_kotlin(), expected);
+    }
+}
@@ -0,0 +1,128 @@
+//! Code generators that translate parsed items into schemas, graphs and type
+//! definitions for other languages.
+
+mod cbindgen;
+mod csharp;
+mod dot;
+mod go;
+mod graphql;
+mod java;
+mod kotlin;
+#[cfg(feature = "serde_json")]
+mod openapi;
+mod proto;
+mod python;
+mod swift;
+
+use crate::{Bound, GenericParam, TypeNode};
+
+/// Returns the type an `impl Trait` or `dyn Trait` type is generated as in
+/// languages without such types: its first trait bound.
+fn principal_trait(bounds: &[Bound]) -> Option<&TypeNode> {
+    bounds.iter().find_map(|bound| match bound {
+        Bound::Trait(ty) => Some(ty),
+        Bound::Lifetime(_) | Bound::Negative(_) | Bound::Closure { .. } => None,
+    })
+}
+
+/// Returns the names of the type parameters among `generics`. Lifetimes and
+/// const parameters have no counterpart in the generated languages.
+fn type_param_names(generics: &[GenericParam]) -> Vec<&str> {
+    generics
+        .iter()
+        .filter(|g| matches!(g, GenericParam::Type { .. }))
+        .map(|g| g.name())
+        .collect()
+}
+
+/// Renders the type parameters of `generics` in angle brackets, as in
+/// `<K, V>`, or nothing if there are none.
+fn angle_type_params(generics: &[GenericParam]) -> String {
+    let names = type_param_names(generics);
+    if names.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", names.join(", "))
+    }
+}
+
+/// Returns the type arguments a generator maps. An array `[T; N]` is the
+/// `[]` generic with its length as a second argument, which is dropped so
+/// that arrays are generated like slices.
+fn type_args<'a>(name: &str, args: &'a [TypeNode]) -> &'a [TypeNode] {
+    if name == "[]" {
+        &args[..args.len().min(1)]
+    } else {
+        args
+    }
+}
+
+/// Converts a `snake_case` identifier to `camelCase`.
+fn to_camel_case(name: &str) -> String {
+    let pascal = to_pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Converts a `snake_case` or `camelCase` identifier to `PascalCase`.
+fn to_pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Converts a `PascalCase` or `snake_case` identifier to `SCREAMING_SNAKE_CASE`.
+fn to_screaming_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '_' {
+            result.push('_');
+            prev_lower = false;
+        } else if c.is_uppercase() {
+            if prev_lower {
+                result.push('_');
+            }
+            result.push(c);
+            prev_lower = false;
+        } else {
+            result.extend(c.to_uppercase());
+            prev_lower = true;
+        }
+    }
+    result
+}
+
+/// Names tuple fields, which are numbered in the AST, as `field0`, `field1`,
+/// ..., and drops the `r#` of raw identifiers.
+fn field_name(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        format!("field{}", name)
+    } else {
+        name.strip_prefix("r#").unwrap_or(name).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_conversions() {
+        assert_eq!(to_camel_case("created_at"), "createdAt");
+        assert_eq!(to_pascal_case("created_at"), "CreatedAt");
+        assert_eq!(to_screaming_snake_case("ChangeColor"), "CHANGE_COLOR");
+        assert_eq!(field_name("0"), "field0");
+        assert_eq!(field_name("r#type"), "type");
+    }
+}
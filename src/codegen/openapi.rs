@@ -0,0 +1,271 @@
+use serde_json::{json, Map, Value};
+
+use super::{principal_trait, type_args};
+use crate::{AstNode, EnumNode, FieldNode, StructNode, TypeNode};
+
+impl AstNode {
+    /// Describes the item as an OpenAPI 3.0 schema component, matching the
+    /// shape serde gives the type by default. Structs become `object`
+    /// schemas and enums either a string `enum` or, when variants carry
+    /// data, a `oneOf` of externally tagged objects. Type aliases describe
+    /// the aliased type; other items produce `null`.
+    pub fn to_openapi_schema(&self) -> Value {
+        match self {
+            AstNode::Struct(node) => object_schema(&node.fields),
+            AstNode::Enum(node) => enum_schema(node),
+            AstNode::TypeAlias(node) => type_schema(&node.aliased_type),
+            AstNode::Trait(_)
+            | AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Module(_)
+            | AstNode::Use(_)
+            | AstNode::Impl(_)
+            | AstNode::Extern(_)
+            | AstNode::Function(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => Value::Null,
+        }
+    }
+}
+
+/// Builds an `object` schema with a property per field. Fields are required
+/// unless their type is an `Option`.
+fn object_schema(fields: &[FieldNode]) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for field in fields {
+        let name = serde_name(&field.name);
+        properties.insert(name.clone(), type_schema(&field.field_type));
+        if !is_option(&field.field_type) {
+            required.push(Value::String(name));
+        }
+    }
+
+    let mut schema = json!({ "type": "object", "properties": properties });
+    if !required.is_empty() {
+        schema["required"] = Value::Array(required);
+    }
+    schema
+}
+
+fn enum_schema(node: &EnumNode) -> Value {
+    if node.variants.iter().all(|v| v.associated_data.is_none()) {
+        let names: Vec<String> = node.variants.iter().map(|v| serde_name(&v.name)).collect();
+        return json!({ "type": "string", "enum": names });
+    }
+
+    let variants: Vec<Value> = node
+        .variants
+        .iter()
+        .map(|variant| {
+            let name = serde_name(&variant.name);
+            let data = match variant.associated_data.as_deref() {
+                None => return json!({ "type": "string", "enum": [name] }),
+                Some(AstNode::Struct(data)) => variant_data_schema(data),
+                Some(_) => json!({}),
+            };
+            json!({
+                "type": "object",
+                "properties": { name.clone(): data },
+                "required": [name],
+            })
+        })
+        .collect();
+    json!({ "oneOf": variants })
+}
+
+/// Tuple variants, whose fields are numbered, serialize as their single
+/// field or as an array of fields; struct variants as an object.
+fn variant_data_schema(data: &StructNode) -> Value {
+    let is_tuple = data
+        .fields
+        .iter()
+        .all(|f| f.name.chars().all(|c| c.is_ascii_digit()));
+    match data.fields.as_slice() {
+        [single] if is_tuple => type_schema(&single.field_type),
+        fields if is_tuple => {
+            let types: Vec<TypeNode> = fields.iter().map(|f| (*f.field_type).clone()).collect();
+            type_schema(&TypeNode::Tuple(types))
+        }
+        fields => object_schema(fields),
+    }
+}
+
+/// Serde names raw identifiers such as `r#type` without their `r#`.
+fn serde_name(name: &str) -> String {
+    name.strip_prefix("r#").unwrap_or(name).to_string()
+}
+
+fn is_option(ty: &TypeNode) -> bool {
+    matches!(ty, TypeNode::Generic { name, .. } if name == "Option")
+}
+
+/// Maps a Rust type to a schema. Types that are not built in refer to a
+/// schema component of the same name.
+fn type_schema(ty: &TypeNode) -> Value {
+    match ty {
+        TypeNode::Simple(name) => match name.as_str() {
+            "i8" | "i16" | "i32" => json!({ "type": "integer", "format": "int32" }),
+            "u8" | "u16" => json!({ "type": "integer", "format": "int32", "minimum": 0 }),
+            "i64" | "isize" => json!({ "type": "integer", "format": "int64" }),
+            // `u32` does not fit in `int32`
+            "u32" | "u64" | "usize" => {
+                json!({ "type": "integer", "format": "int64", "minimum": 0 })
+            }
+            "f32" => json!({ "type": "number", "format": "float" }),
+            "f64" => json!({ "type": "number", "format": "double" }),
+            "bool" => json!({ "type": "boolean" }),
+            "String" | "str" | "char" => json!({ "type": "string" }),
+            _ => {
+                let name = name.rsplit("::").next().unwrap_or(name);
+                json!({ "$ref": format!("#/components/schemas/{}", name) })
+            }
+        },
+        TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+            type_schema(inner)
+        }
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => type_schema(inner),
+            ("Option", [inner]) => {
+                let mut schema = type_schema(inner);
+                if schema.get("$ref").is_some() {
+                    // Siblings of `$ref` are ignored, so wrap the reference
+                    schema = json!({ "allOf": [schema] });
+                }
+                schema["nullable"] = Value::Bool(true);
+                schema
+            }
+            ("Vec" | "VecDeque" | "[]", [inner]) => {
+                json!({ "type": "array", "items": type_schema(inner) })
+            }
+            ("HashSet" | "BTreeSet", [inner]) => {
+                json!({ "type": "array", "items": type_schema(inner), "uniqueItems": true })
+            }
+            ("HashMap" | "BTreeMap", [_, value]) => {
+                json!({ "type": "object", "additionalProperties": type_schema(value) })
+            }
+            _ => {
+                let name = name.rsplit("::").next().unwrap_or(name);
+                json!({ "$ref": format!("#/components/schemas/{}", name) })
+            }
+        },
+        // OpenAPI 3.0 cannot describe a null-only value
+        TypeNode::Tuple(elements) if elements.is_empty() => json!({ "nullable": true }),
+        TypeNode::Tuple(elements) => {
+            let items: Vec<Value> = elements.iter().map(type_schema).collect();
+            json!({
+                "type": "array",
+                "items": { "oneOf": items },
+                "minItems": elements.len(),
+                "maxItems": elements.len(),
+            })
+        }
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            principal_trait(bounds).map_or(json!({}), type_schema)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_openapi_schema() {
+        let input = r#"
+            pub struct Order {
+                r#type: String,
+                quantities: HashMap<String, f32>,
+                tags: BTreeSet<String>,
+                location: (f64, f64),
+                coupon: Option<Coupon>,
+                customer: Box<Customer>,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let number = json!({ "type": "number", "format": "double" });
+        let expected = json!({
+            "type": "object",
+            "properties": {
+                "type": { "type": "string" },
+                "quantities": {
+                    "type": "object",
+                    "additionalProperties": { "type": "number", "format": "float" },
+                },
+                "tags": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+                "location": {
+                    "type": "array",
+                    "items": { "oneOf": [number, number] },
+                    "minItems": 2,
+                    "maxItems": 2,
+                },
+                "coupon": {
+                    "allOf": [{ "$ref": "#/components/schemas/Coupon" }],
+                    "nullable": true,
+                },
+                "customer": { "$ref": "#/components/schemas/Customer" },
+            },
+            "required": ["type", "quantities", "tags", "location", "customer"],
+        });
+        assert_eq!(ast.to_openapi_schema(), expected);
+    }
+
+    #[test]
+    fn test_enum_to_openapi_schema() {
+        let unit = "pub enum Status { Active, r#in }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            unit.to_openapi_schema(),
+            json!({ "type": "string", "enum": ["Active", "in"] })
+        );
+
+        let input = "pub enum Shape { Empty, Circle(f64), Rect { w: f64, h: f64 } }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let number = json!({ "type": "number", "format": "double" });
+        let expected = json!({
+            "oneOf": [
+                { "type": "string", "enum": ["Empty"] },
+                {
+                    "type": "object",
+                    "properties": { "Circle": number },
+                    "required": ["Circle"],
+                },
+                {
+                    "type": "object",
+                    "properties": {
+                        "Rect": {
+                            "type": "object",
+                            "properties": { "w": number, "h": number },
+                            "required": ["w", "h"],
+                        },
+                    },
+                    "required": ["Rect"],
+                },
+            ],
+        });
+        assert_eq!(ast.to_openapi_schema(), expected);
+    }
+
+    #[test]
+    fn test_integer_formats_to_openapi_schema() {
+        let input = "pub struct Counters { a: i16, b: u16, c: i32, d: u32, e: isize, f: usize }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let properties = &ast.to_openapi_schema()["properties"];
+        let int32 = json!({ "type": "integer", "format": "int32" });
+        let int64 = json!({ "type": "integer", "format": "int64" });
+        let uint32 = json!({ "type": "integer", "format": "int32", "minimum": 0 });
+        let uint64 = json!({ "type": "integer", "format": "int64", "minimum": 0 });
+        assert_eq!(properties["a"], int32);
+        assert_eq!(properties["b"], uint32);
+        assert_eq!(properties["c"], int32);
+        assert_eq!(properties["d"], uint64);
+        assert_eq!(properties["e"], int64);
+        assert_eq!(properties["f"], uint64);
+    }
+}
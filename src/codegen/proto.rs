@@ -0,0 +1,284 @@
+use super::{field_name, to_screaming_snake_case, type_args, type_param_names};
+use crate::{AstNode, EnumNode, ExportError, FieldNode, StructNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as a proto3 schema. Structs become messages with their
+    /// fields numbered in order. Enums without data become proto enums, and
+    /// enums whose variants carry data become a message with a `oneof` of a
+    /// nested message per variant. Items of modules are exported in turn and
+    /// other items are left out.
+    ///
+    /// Fails with [`ExportError::UnsupportedType`] on a type protobuf has no
+    /// counterpart for, such as a function pointer, tuple or nested `Vec`.
+    pub fn to_proto(&self) -> Result<String, ExportError> {
+        let mut definitions = Vec::new();
+        add_definitions(self, &mut definitions)?;
+        let mut source = String::from("syntax = \"proto3\";\n");
+        for definition in definitions {
+            source.push('\n');
+            source.push_str(&definition);
+            source.push('\n');
+        }
+        Ok(source)
+    }
+}
+
+fn add_definitions(node: &AstNode, definitions: &mut Vec<String>) -> Result<(), ExportError> {
+    match node {
+        AstNode::Struct(node) => definitions.push(message(node)?),
+        AstNode::Enum(node) => definitions.push(enumeration(node)?),
+        AstNode::Module(node) => {
+            for item in &node.items {
+                add_definitions(item, definitions)?;
+            }
+        }
+        AstNode::Trait(_)
+        | AstNode::TypeAlias(_)
+        | AstNode::Const(_)
+        | AstNode::Static(_)
+        | AstNode::Use(_)
+        | AstNode::Impl(_)
+        | AstNode::Extern(_)
+        | AstNode::Function(_)
+        | AstNode::MacroDef(_)
+        | AstNode::Opaque(_) => {}
+    }
+    Ok(())
+}
+
+fn message(node: &StructNode) -> Result<String, ExportError> {
+    let body = fields(&node.fields, &type_param_names(&node.generics), "  ")?;
+    Ok(block(&format!("message {}", node.name), &body))
+}
+
+/// Renders proto fields numbered from 1, indented by `indent`.
+fn fields(fields: &[FieldNode], generics: &[&str], indent: &str) -> Result<String, ExportError> {
+    let mut lines = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        lines.push(format!(
+            "{}{} {} = {};",
+            indent,
+            field_type(&field.field_type, generics)?,
+            field_name(&field.name),
+            i + 1
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn enumeration(node: &EnumNode) -> Result<String, ExportError> {
+    if node.variants.iter().all(|v| v.associated_data.is_none()) {
+        // Enum values share a namespace with their enum's siblings, so they
+        // are prefixed with the enum name as the style guide recommends
+        let prefix = to_screaming_snake_case(&node.name);
+        // Integer discriminants are kept when the first is zero, as proto3
+        // requires, and variants are numbered in order otherwise
+        let discriminants: Option<Vec<i32>> = node
+            .variants
+            .iter()
+            .map(|v| v.discriminant.as_deref()?.parse().ok())
+            .collect();
+        let numbers = match discriminants {
+            Some(numbers) if numbers.first() == Some(&0) => numbers,
+            _ => (0..node.variants.len() as i32).collect(),
+        };
+        let values: Vec<String> = node
+            .variants
+            .iter()
+            .zip(numbers)
+            .map(|(v, n)| format!("  {}_{} = {};", prefix, to_screaming_snake_case(&v.name), n))
+            .collect();
+        return Ok(block(&format!("enum {}", node.name), &values.join("\n")));
+    }
+
+    let generics = type_param_names(&node.generics);
+    let mut members = Vec::new();
+    let mut cases = Vec::new();
+    for (i, variant) in node.variants.iter().enumerate() {
+        let body = match variant.associated_data.as_deref() {
+            Some(AstNode::Struct(data)) => fields(&data.fields, &generics, "    ")?,
+            _ => String::new(),
+        };
+        members.push(block(&format!("  message {}", variant.name), &body).replace("\n}", "\n  }"));
+        cases.push(format!(
+            "    {} {} = {};",
+            variant.name,
+            to_screaming_snake_case(&variant.name).to_lowercase(),
+            i + 1
+        ));
+    }
+    members.push(block("  oneof variant", &cases.join("\n")).replace("\n}", "\n  }"));
+    Ok(block(
+        &format!("message {}", node.name),
+        &members.join("\n\n"),
+    ))
+}
+
+/// Maps a field type to a proto field type, with any `optional` or
+/// `repeated` label.
+fn field_type(ty: &TypeNode, generics: &[&str]) -> Result<String, ExportError> {
+    let unsupported = || ExportError::UnsupportedType(ty.display());
+    match ty {
+        TypeNode::Reference { inner, .. } => field_type(inner, generics),
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => field_type(inner, generics),
+            ("Vec" | "[]", [TypeNode::Simple(byte)]) if byte == "u8" => scalar_type(ty, generics),
+            ("Option", [inner]) => Ok(format!("optional {}", scalar_type(inner, generics)?)),
+            ("Vec" | "VecDeque" | "HashSet" | "BTreeSet" | "[]", [inner]) => {
+                Ok(format!("repeated {}", scalar_type(inner, generics)?))
+            }
+            ("HashMap" | "BTreeMap", [key, value]) => Ok(format!(
+                "map<{}, {}>",
+                scalar_type(key, generics)?,
+                scalar_type(value, generics)?
+            )),
+            _ => Err(unsupported()),
+        },
+        _ => scalar_type(ty, generics),
+    }
+}
+
+/// Maps a type to a proto type that takes no label, as the elements of
+/// repeated fields and maps must be.
+fn scalar_type(ty: &TypeNode, generics: &[&str]) -> Result<String, ExportError> {
+    let unsupported = || ExportError::UnsupportedType(ty.display());
+    match ty {
+        TypeNode::Simple(name) => {
+            let proto = match name.as_str() {
+                "i8" | "i16" | "i32" => "int32",
+                "i64" | "isize" => "int64",
+                "u8" | "u16" | "u32" => "uint32",
+                "u64" | "usize" => "uint64",
+                "f32" => "float",
+                "f64" => "double",
+                "bool" => "bool",
+                "String" | "str" | "char" => "string",
+                "i128" | "u128" => return Err(unsupported()),
+                _ => {
+                    let is_path = name
+                        .split("::")
+                        .all(|s| s.starts_with(|c: char| c.is_alphabetic() || c == '_'));
+                    if !is_path || generics.contains(&name.as_str()) || name.contains(' ') {
+                        return Err(unsupported());
+                    }
+                    name.rsplit("::").next().unwrap_or(name)
+                }
+            };
+            Ok(proto.to_string())
+        }
+        TypeNode::Reference { inner, .. } => scalar_type(inner, generics),
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => scalar_type(inner, generics),
+            ("Vec" | "[]", [TypeNode::Simple(byte)]) if byte == "u8" => Ok("bytes".to_string()),
+            _ => Err(unsupported()),
+        },
+        TypeNode::RawPointer { .. }
+        | TypeNode::Tuple(_)
+        | TypeNode::ImplTrait(_)
+        | TypeNode::DynTrait(_) => Err(unsupported()),
+    }
+}
+
+fn block(header: &str, body: &str) -> String {
+    if body.is_empty() {
+        return format!("{} {{}}", header);
+    }
+    format!("{} {{\n{}\n}}", header, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AstNode, ExportError};
+
+    #[test]
+    fn test_struct_to_proto() {
+        let input = "pub struct Point { x: f64, y: f64, label: Option<String> }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+syntax = \"proto3\";
+
+message Point {
+  double x = 1;
+  double y = 2;
+  optional string label = 3;
+}
+";
+        assert_eq!(ast.to_proto().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_enum_to_proto() {
+        let unit = "pub enum Status { Active, Suspended }"
+            .parse::<AstNode>()
+            .unwrap();
+        let expected = "\
+syntax = \"proto3\";
+
+enum Status {
+  STATUS_ACTIVE = 0;
+  STATUS_SUSPENDED = 1;
+}
+";
+        assert_eq!(unit.to_proto().unwrap(), expected);
+
+        let codes = "#[repr(i32)] pub enum Code { Ok = 0, NotFound = 5, Internal = 13 }"
+            .parse::<AstNode>()
+            .unwrap();
+        let expected = "\
+syntax = \"proto3\";
+
+enum Code {
+  CODE_OK = 0;
+  CODE_NOT_FOUND = 5;
+  CODE_INTERNAL = 13;
+}
+";
+        assert_eq!(codes.to_proto().unwrap(), expected);
+
+        let flags = "pub enum Flag { A = 1, B = 1 << 1 }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert!(flags
+            .to_proto()
+            .unwrap()
+            .contains("  FLAG_A = 0;\n  FLAG_B = 1;\n"));
+
+        let input = "pub enum Shape { Empty, Circle(f64), Rect { w: f64, h: f64 } }";
+        let ast = input.parse::<AstNode>().unwrap();
+        let expected = "\
+syntax = \"proto3\";
+
+message Shape {
+  message Empty {}
+
+  message Circle {
+    double field0 = 1;
+  }
+
+  message Rect {
+    double w = 1;
+    double h = 2;
+  }
+
+  oneof variant {
+    Empty empty = 1;
+    Circle circle = 2;
+    Rect rect = 3;
+  }
+}
+";
+        assert_eq!(ast.to_proto().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unsupported_type_to_proto() {
+        let ast = "pub struct Handler { callback: Box<dyn Fn(u8) -> u8>, pair: (u8, u8) }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            ast.to_proto(),
+            Err(ExportError::UnsupportedType("dyn Fn(u8) -> u8".to_string()))
+        );
+    }
+}
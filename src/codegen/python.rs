@@ -0,0 +1,476 @@
+use std::collections::BTreeSet;
+
+use super::{field_name, principal_trait, type_args, type_param_names};
+use crate::{AstNode, MethodNode, StructNode, TraitNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as Python source. Structs become `@dataclass` classes
+    /// whose fields default to an empty value of their type, and traits
+    /// become `typing.Protocol` classes with method stubs. Other items
+    /// produce no classes.
+    pub fn to_python(&self) -> String {
+        let mut module = Module::default();
+        module.add(self);
+        module.finish()
+    }
+}
+
+#[derive(Default)]
+struct Module {
+    /// Names imported from `typing`.
+    typing: BTreeSet<&'static str>,
+    uses_dataclass: bool,
+    /// Type parameters declared as `TypeVar`s, in order of first use.
+    type_vars: Vec<String>,
+    classes: Vec<String>,
+    /// Name of the class being generated, which `Self` refers to.
+    current_class: String,
+}
+
+impl Module {
+    fn add(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Trait(node) => self.add_protocol(node),
+            AstNode::Struct(node) => self.add_dataclass(node),
+            AstNode::Module(node) => node.items.iter().for_each(|item| self.add(item)),
+            AstNode::Enum(_)
+            | AstNode::TypeAlias(_)
+            | AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Use(_)
+            | AstNode::Impl(_)
+            | AstNode::Extern(_)
+            | AstNode::Function(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => {}
+        }
+    }
+
+    fn add_dataclass(&mut self, node: &StructNode) {
+        self.uses_dataclass = true;
+        self.current_class = node.name.clone();
+        let generics = type_param_names(&node.generics);
+        let fields = node
+            .fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "{}: {} = {}",
+                    field_name(&f.name),
+                    self.python_type(&f.field_type),
+                    FieldDefault::of(&f.field_type, &generics).to_field()
+                )
+            })
+            .collect();
+        let bases = match self.declare_type_vars(&generics) {
+            Some(params) => {
+                self.typing.insert("Generic");
+                format!("(Generic[{}])", params)
+            }
+            None => String::new(),
+        };
+        self.classes.push(class(
+            &format!("@dataclass\nclass {}{}", node.name, bases),
+            fields,
+        ));
+    }
+
+    fn add_protocol(&mut self, node: &TraitNode) {
+        self.typing.insert("Protocol");
+        self.current_class = node.name.clone();
+        let methods = node
+            .methods
+            .iter()
+            .map(|method| self.method_stub(method))
+            .collect();
+        let base = match self.declare_type_vars(&type_param_names(&node.generics)) {
+            Some(params) => format!("Protocol[{}]", params),
+            None => "Protocol".to_string(),
+        };
+        self.classes
+            .push(class(&format!("class {}({})", node.name, base), methods));
+    }
+
+    /// Declares the type parameters of a class as `TypeVar`s, returning them
+    /// as the parameters of its generic base, if it has any.
+    fn declare_type_vars(&mut self, names: &[&str]) -> Option<String> {
+        if names.is_empty() {
+            return None;
+        }
+        self.typing.insert("TypeVar");
+        for name in names {
+            if !self.type_vars.iter().any(|declared| declared == name) {
+                self.type_vars.push(name.to_string());
+            }
+        }
+        Some(names.join(", "))
+    }
+
+    fn method_stub(&mut self, method: &MethodNode) -> String {
+        let has_receiver = method.receiver().is_some();
+        let mut params = Vec::new();
+        if has_receiver {
+            params.push("self".to_string());
+        }
+        for param in method.params_without_self() {
+            params.push(format!(
+                "{}: {}",
+                param.name,
+                self.python_type(&param.param_type)
+            ));
+        }
+        let return_type = match &method.return_type {
+            Some(ty) => self.python_type(ty),
+            None => "None".to_string(),
+        };
+        let stub = format!(
+            "def {}({}) -> {}: ...",
+            method.name,
+            params.join(", "),
+            return_type
+        );
+        if has_receiver {
+            stub
+        } else {
+            format!("@staticmethod\n    {}", stub)
+        }
+    }
+
+    fn python_type(&mut self, ty: &TypeNode) -> String {
+        match ty {
+            TypeNode::Simple(name) => match name.as_str() {
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "u128" | "usize" => "int".to_string(),
+                "f32" | "f64" => "float".to_string(),
+                "String" | "str" | "char" => "str".to_string(),
+                "bool" => "bool".to_string(),
+                "Self" => self.current_class.clone(),
+                _ => name.rsplit("::").next().unwrap_or(name).to_string(),
+            },
+            TypeNode::Reference { inner, .. } => self.python_type(inner),
+            // Raw pointers may be null
+            TypeNode::RawPointer { inner, .. } => {
+                self.typing.insert("Optional");
+                format!("Optional[{}]", self.python_type(inner))
+            }
+            TypeNode::Generic { name, args } => {
+                let args = type_args(name, args);
+                let typing = match name.as_str() {
+                    "Box" | "Rc" | "Arc" | "Cow" if args.len() == 1 => {
+                        return self.python_type(&args[0]);
+                    }
+                    "Option" => "Optional",
+                    "Vec" | "VecDeque" | "[]" => "List",
+                    "HashSet" | "BTreeSet" => "Set",
+                    "HashMap" | "BTreeMap" => "Dict",
+                    _ => return name.rsplit("::").next().unwrap_or(name).to_string(),
+                };
+                self.typing.insert(typing);
+                let args: Vec<String> = args.iter().map(|arg| self.python_type(arg)).collect();
+                format!("{}[{}]", typing, args.join(", "))
+            }
+            TypeNode::Tuple(elements) if elements.is_empty() => "None".to_string(),
+            TypeNode::Tuple(elements) => {
+                self.typing.insert("Tuple");
+                let elements: Vec<String> = elements.iter().map(|e| self.python_type(e)).collect();
+                format!("Tuple[{}]", elements.join(", "))
+            }
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                match principal_trait(bounds) {
+                    Some(ty) => self.python_type(ty),
+                    None => {
+                        self.typing.insert("Any");
+                        "Any".to_string()
+                    }
+                }
+            }
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut imports = Vec::new();
+        if self.uses_dataclass {
+            imports.push("from dataclasses import dataclass, field".to_string());
+        }
+        if !self.typing.is_empty() {
+            let names: Vec<&str> = self.typing.into_iter().collect();
+            imports.push(format!("from typing import {}", names.join(", ")));
+        }
+
+        let mut sections = Vec::new();
+        if !self.classes.is_empty() {
+            // Classes may refer to each other before they are defined.
+            sections.push("from __future__ import annotations".to_string());
+        }
+        if !imports.is_empty() {
+            sections.push(imports.join("\n"));
+        }
+        if !self.type_vars.is_empty() {
+            let declarations: Vec<String> = self
+                .type_vars
+                .iter()
+                .map(|name| format!("{} = TypeVar(\"{}\")", name, name))
+                .collect();
+            sections.push(declarations.join("\n"));
+        }
+        let mut source = sections.join("\n\n");
+        for class in self.classes {
+            source.push_str("\n\n\n");
+            source.push_str(&class);
+        }
+        source.push('\n');
+        source
+    }
+}
+
+/// The default of a dataclass field.
+enum FieldDefault {
+    /// An immutable value, passed as `field(default=...)`.
+    Value(String),
+    /// A callable producing a fresh value, passed as `field(default_factory=...)`.
+    Factory(String),
+}
+
+impl FieldDefault {
+    /// Returns the empty value of a Rust type, or a default-constructed
+    /// instance for other classes. Fields of one of the type parameters
+    /// `generics`, which cannot be constructed, default to `None`.
+    fn of(ty: &TypeNode, generics: &[&str]) -> FieldDefault {
+        match ty {
+            TypeNode::Simple(name) if generics.contains(&name.as_str()) => {
+                FieldDefault::Value("None".to_string())
+            }
+            TypeNode::Simple(name) => match name.as_str() {
+                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64"
+                | "u128" | "usize" => FieldDefault::Value("0".to_string()),
+                "f32" | "f64" => FieldDefault::Value("0.0".to_string()),
+                "String" | "str" | "char" => FieldDefault::Value("\"\"".to_string()),
+                "bool" => FieldDefault::Value("False".to_string()),
+                _ => FieldDefault::Factory(name.rsplit("::").next().unwrap_or(name).to_string()),
+            },
+            TypeNode::Reference { inner, .. } => FieldDefault::of(inner, generics),
+            TypeNode::RawPointer { .. } => FieldDefault::Value("None".to_string()),
+            TypeNode::Generic { name, args } => match name.as_str() {
+                "Box" | "Rc" | "Arc" | "Cow" if args.len() == 1 => {
+                    FieldDefault::of(&args[0], generics)
+                }
+                "Option" => FieldDefault::Value("None".to_string()),
+                "Vec" | "VecDeque" | "[]" => FieldDefault::Factory("list".to_string()),
+                "HashSet" | "BTreeSet" => FieldDefault::Factory("set".to_string()),
+                "HashMap" | "BTreeMap" => FieldDefault::Factory("dict".to_string()),
+                _ => FieldDefault::Factory(name.rsplit("::").next().unwrap_or(name).to_string()),
+            },
+            TypeNode::Tuple(elements) if elements.is_empty() => {
+                FieldDefault::Value("None".to_string())
+            }
+            TypeNode::Tuple(elements) => {
+                let values: Vec<String> = elements
+                    .iter()
+                    .map(|e| match FieldDefault::of(e, generics) {
+                        FieldDefault::Value(value) => value,
+                        FieldDefault::Factory(factory) => format!("{}()", factory),
+                    })
+                    .collect();
+                match values.as_slice() {
+                    [single] => FieldDefault::Value(format!("({},)", single)),
+                    _ => FieldDefault::Value(format!("({})", values.join(", "))),
+                }
+            }
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                match principal_trait(bounds) {
+                    Some(ty) => FieldDefault::of(ty, generics),
+                    None => FieldDefault::Value("None".to_string()),
+                }
+            }
+        }
+    }
+
+    fn to_field(&self) -> String {
+        match self {
+            FieldDefault::Value(value) => format!("field(default={})", value),
+            FieldDefault::Factory(factory) => format!("field(default_factory={})", factory),
+        }
+    }
+}
+
+/// Renders a class with its members indented, or `pass` when it has none.
+fn class(header: &str, members: Vec<String>) -> String {
+    let members = if members.is_empty() {
+        vec!["pass".to_string()]
+    } else {
+        members
+    };
+    let body: Vec<String> = members.iter().map(|m| format!("    {}", m)).collect();
+    format!("{}:\n{}", header, body.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_python() {
+        let input = r#"
+            pub struct Reading {
+                sensor: char,
+                celsius: f32,
+                raw: u128,
+                valid: bool,
+                label: Box<str>,
+                note: &'static str,
+                unit: (),
+                window: (u8,),
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // Immutable defaults are plain values, and a one-element tuple keeps
+        // its trailing comma
+        let expected = "\
+from __future__ import annotations
+
+from dataclasses import dataclass, field
+from typing import Tuple
+
+
+@dataclass
+class Reading:
+    sensor: str = field(default=\"\")
+    celsius: float = field(default=0.0)
+    raw: int = field(default=0)
+    valid: bool = field(default=False)
+    label: str = field(default=\"\")
+    note: str = field(default=\"\")
+    unit: None = field(default=None)
+    window: Tuple[int] = field(default=(0,))
+";
+        assert_eq!(ast.to_python(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert!(marker
+            .to_python()
+            .ends_with("@dataclass\nclass Marker:\n    pass\n"));
+    }
+
+    #[test]
+    fn test_trait_to_python() {
+        let input = r#"
+            pub trait Sensor {
+                fn calibrate(&mut self, offset: f32) -> Self;
+                fn listeners(&self) -> Vec<Box<dyn Listener>>;
+                fn attach(&mut self, listener: &dyn Listener + Send);
+                fn reset();
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+from __future__ import annotations
+
+from typing import List, Protocol
+
+
+class Sensor(Protocol):
+    def calibrate(self, offset: float) -> Sensor: ...
+    def listeners(self) -> List[Listener]: ...
+    def attach(self, listener: Listener) -> None: ...
+    @staticmethod
+    def reset() -> None: ...
+";
+        assert_eq!(ast.to_python(), expected);
+    }
+
+    #[test]
+    fn test_receivers_to_python() {
+        let input = r#"
+            pub trait Tree {
+                fn adopt(&mut self, itself: Node);
+                fn root(myself: u8) -> Self;
+                fn detach(self: Box<Self>) -> Node;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+from __future__ import annotations
+
+from typing import Protocol
+
+
+class Tree(Protocol):
+    def adopt(self, itself: Node) -> None: ...
+    @staticmethod
+    def root(myself: int) -> Tree: ...
+    def detach(self) -> Node: ...
+";
+        assert_eq!(ast.to_python(), expected);
+    }
+
+    #[test]
+    fn test_generics_to_python() {
+        let input = r#"
+            pub mod store {
+                pub struct Wrapper<'a, T> { value: &'a T, items: Vec<T>, pair: (T, u8) }
+                pub trait Repo<K, T> {
+                    fn get(&self, key: K) -> Option<T>;
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+from __future__ import annotations
+
+from dataclasses import dataclass, field
+from typing import Generic, List, Optional, Protocol, Tuple, TypeVar
+
+T = TypeVar(\"T\")
+K = TypeVar(\"K\")
+
+
+@dataclass
+class Wrapper(Generic[T]):
+    value: T = field(default=None)
+    items: List[T] = field(default_factory=list)
+    pair: Tuple[T, int] = field(default=(None, 0))
+
+
+class Repo(Protocol[K, T]):
+    def get(self, key: K) -> Optional[T]: ...
+";
+        assert_eq!(ast.to_python(), expected);
+    }
+
+    #[test]
+    fn test_mutable_defaults_to_python() {
+        let input = r#"
+            pub struct Inventory {
+                items: HashMap<String, u32>,
+                tags: HashSet<String>,
+                history: Vec<Vec<u8>>,
+                location: Warehouse,
+                owner: Option<Account>,
+                pair: (i32, String),
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+from __future__ import annotations
+
+from dataclasses import dataclass, field
+from typing import Dict, List, Optional, Set, Tuple
+
+
+@dataclass
+class Inventory:
+    items: Dict[str, int] = field(default_factory=dict)
+    tags: Set[str] = field(default_factory=set)
+    history: List[List[int]] = field(default_factory=list)
+    location: Warehouse = field(default_factory=Warehouse)
+    owner: Optional[Account] = field(default=None)
+    pair: Tuple[int, str] = field(default=(0, \"\"))
+";
+        assert_eq!(ast.to_python(), expected);
+    }
+}
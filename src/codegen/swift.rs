@@ -0,0 +1,281 @@
+use super::{
+    angle_type_params, field_name, principal_trait, to_camel_case, type_args, type_param_names,
+};
+use crate::{AstNode, MethodNode, StructNode, TraitNode, TypeNode};
+
+impl AstNode {
+    /// Emits the item as Swift source. Structs become `struct`s with stored
+    /// properties and traits become `protocol`s. Other items produce no
+    /// declarations.
+    pub fn to_swift(&self) -> String {
+        let mut declarations = Vec::new();
+        add_declarations(self, &mut declarations);
+        let mut source = declarations.join("\n\n");
+        source.push('\n');
+        source
+    }
+}
+
+fn add_declarations(node: &AstNode, declarations: &mut Vec<String>) {
+    match node {
+        AstNode::Trait(node) => declarations.push(protocol(node)),
+        AstNode::Struct(node) => declarations.push(structure(node)),
+        AstNode::Module(node) => node
+            .items
+            .iter()
+            .for_each(|item| add_declarations(item, declarations)),
+        AstNode::Enum(_)
+        | AstNode::TypeAlias(_)
+        | AstNode::Const(_)
+        | AstNode::Static(_)
+        | AstNode::Use(_)
+        | AstNode::Impl(_)
+        | AstNode::Extern(_)
+        | AstNode::Function(_)
+        | AstNode::MacroDef(_)
+        | AstNode::Opaque(_) => {}
+    }
+}
+
+fn structure(node: &StructNode) -> String {
+    let properties = node
+        .fields
+        .iter()
+        .map(|f| {
+            format!(
+                "let {}: {}",
+                to_camel_case(&field_name(&f.name)),
+                swift_type(&f.field_type)
+            )
+        })
+        .collect();
+    let header = format!("struct {}{}", node.name, angle_type_params(&node.generics));
+    block(&header, properties)
+}
+
+/// Protocols cannot have type parameters, so those of the trait become
+/// associated types.
+fn protocol(node: &TraitNode) -> String {
+    let requirements = type_param_names(&node.generics)
+        .into_iter()
+        .map(|name| format!("associatedtype {}", name))
+        .chain(node.methods.iter().map(requirement))
+        .collect();
+    block(&format!("protocol {}", node.name), requirements)
+}
+
+/// Renders a method as a protocol requirement. Methods without a receiver
+/// become `static` requirements.
+fn requirement(method: &MethodNode) -> String {
+    let has_receiver = method.receiver().is_some();
+    let params: Vec<String> = method
+        .params_without_self()
+        .iter()
+        .map(|param| {
+            format!(
+                "{}: {}",
+                to_camel_case(&param.name),
+                swift_type(&param.param_type)
+            )
+        })
+        .collect();
+    let return_type = match method.return_type.as_deref() {
+        None => String::new(),
+        Some(TypeNode::Tuple(elements)) if elements.is_empty() => String::new(),
+        Some(ty) => format!(" -> {}", swift_type(ty)),
+    };
+    format!(
+        "{}func {}({}){}",
+        if has_receiver { "" } else { "static " },
+        to_camel_case(&method.name),
+        params.join(", "),
+        return_type
+    )
+}
+
+fn swift_type(ty: &TypeNode) -> String {
+    match ty {
+        TypeNode::Simple(name) => match name.as_str() {
+            "i8" => "Int8".to_string(),
+            "i16" => "Int16".to_string(),
+            "i32" | "isize" => "Int".to_string(),
+            "i64" => "Int64".to_string(),
+            "u8" => "UInt8".to_string(),
+            "u16" => "UInt16".to_string(),
+            "u32" => "UInt32".to_string(),
+            "u64" => "UInt64".to_string(),
+            "usize" => "UInt".to_string(),
+            "f32" => "Float".to_string(),
+            "f64" => "Double".to_string(),
+            "String" | "str" => "String".to_string(),
+            "char" => "Character".to_string(),
+            "bool" => "Bool".to_string(),
+            _ => name.rsplit("::").next().unwrap_or(name).to_string(),
+        },
+        TypeNode::Reference { inner, .. } => swift_type(inner),
+        TypeNode::RawPointer { mutable, inner } => {
+            let pointer = if *mutable {
+                "UnsafeMutablePointer"
+            } else {
+                "UnsafePointer"
+            };
+            format!("{}<{}>", pointer, swift_type(inner))
+        }
+        TypeNode::Generic { name, args } => match (name.as_str(), type_args(name, args)) {
+            ("Box" | "Rc" | "Arc" | "Cow", [inner]) => swift_type(inner),
+            ("Option", [inner]) => format!("{}?", swift_type(inner)),
+            ("Vec" | "VecDeque" | "[]", [inner]) => format!("[{}]", swift_type(inner)),
+            ("HashSet" | "BTreeSet", [inner]) => format!("Set<{}>", swift_type(inner)),
+            ("HashMap" | "BTreeMap", [key, value]) => {
+                format!("[{}: {}]", swift_type(key), swift_type(value))
+            }
+            _ => {
+                let args: Vec<String> = args.iter().map(swift_type).collect();
+                format!(
+                    "{}<{}>",
+                    name.rsplit("::").next().unwrap_or(name),
+                    args.join(", ")
+                )
+            }
+        },
+        TypeNode::Tuple(elements) if elements.is_empty() => "Void".to_string(),
+        TypeNode::Tuple(elements) => {
+            let elements: Vec<String> = elements.iter().map(swift_type).collect();
+            format!("({})", elements.join(", "))
+        }
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            principal_trait(bounds).map_or("Any".to_string(), swift_type)
+        }
+    }
+}
+
+/// Renders a `keyword Name { ... }` declaration with its members indented.
+fn block(header: &str, members: Vec<String>) -> String {
+    if members.is_empty() {
+        return format!("{} {{}}", header);
+    }
+    let body: Vec<String> = members.iter().map(|m| format!("    {}", m)).collect();
+    format!("{} {{\n{}\n}}", header, body.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AstNode;
+
+    #[test]
+    fn test_struct_to_swift() {
+        let input = r#"
+            pub struct Packet {
+                seq: u32,
+                flags: u8,
+                delta: i16,
+                len: usize,
+                offset: isize,
+                kind: char,
+                tags: BTreeSet<String>,
+                headers: HashMap<String, Vec<u8>>,
+                buffer: *mut u8,
+                checksum: *const u32,
+                payload: Option<Box<Payload>>,
+                empty: (),
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+struct Packet {
+    let seq: UInt32
+    let flags: UInt8
+    let delta: Int16
+    let len: UInt
+    let offset: Int
+    let kind: Character
+    let tags: Set<String>
+    let headers: [String: [UInt8]]
+    let buffer: UnsafeMutablePointer<UInt8>
+    let checksum: UnsafePointer<UInt32>
+    let payload: Payload?
+    let empty: Void
+}
+";
+        assert_eq!(ast.to_swift(), expected);
+
+        let marker = "pub struct Marker {}".parse::<AstNode>().unwrap();
+        assert_eq!(marker.to_swift(), "struct Marker {}\n");
+    }
+
+    #[test]
+    fn test_trait_to_swift() {
+        let input = r#"
+            pub trait Transport {
+                fn send_packet(&mut self, packet: &Packet);
+                fn flush(&mut self) -> ();
+                fn peer_addr(&self) -> Option<(String, u16)>;
+                fn max_payload_len() -> usize;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        // Unit returns are left out, and methods without a receiver are static
+        let expected = "\
+protocol Transport {
+    func sendPacket(packet: Packet)
+    func flush()
+    func peerAddr() -> (String, UInt16)?
+    static func maxPayloadLen() -> UInt
+}
+";
+        assert_eq!(ast.to_swift(), expected);
+    }
+
+    #[test]
+    fn test_generics_to_swift() {
+        let input = r#"
+            pub mod store {
+                pub struct Wrapper<'a, T> { value: &'a T }
+                pub trait Repo<K, V> {
+                    fn get(&self, key: K) -> Option<V>;
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+struct Wrapper<T> {
+    let value: T
+}
+
+protocol Repo {
+    associatedtype K
+    associatedtype V
+    func get(key: K) -> V?
+}
+";
+        assert_eq!(ast.to_swift(), expected);
+    }
+
+    #[test]
+    fn test_protocol_self_to_swift() {
+        let input = r#"
+            pub trait Shape {
+                fn scaled(&self, factor: f64) -> Self;
+                fn union(&self, other: &Self) -> Vec<Self>;
+                fn unit() -> Self;
+                fn bounds(&self) -> Option<(f64, f64)>;
+                fn palette(&self) -> HashMap<String, [u8; 3]>;
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = "\
+protocol Shape {
+    func scaled(factor: Double) -> Self
+    func union(other: Self) -> [Self]
+    static func unit() -> Self
+    func bounds() -> (Double, Double)?
+    func palette() -> [String: [UInt8]]
+}
+";
+        assert_eq!(ast.to_swift(), expected);
+    }
+}
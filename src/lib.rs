@@ -1,647 +1,8169 @@
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
 use std::str::FromStr;
 
-#[derive(Debug, PartialEq)]
+mod borrowed;
+mod codegen;
+
+pub use borrowed::{
+    AstNodeRef, EnumNodeRef, FieldNodeRef, GenericParamRef, MethodNodeRef, ParamNodeRef,
+    StructNodeRef, TraitNodeRef, TypeNodeRef, VariantNodeRef, WherePredicateRef,
+};
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum AstNode {
     Trait(TraitNode),
     Struct(StructNode),
     Enum(EnumNode),
+    TypeAlias(TypeAliasNode),
+    Const(ConstNode),
+    Static(StaticNode),
+    Module(ModuleNode),
+    Use(UseNode),
+    Impl(ImplNode),
+    Extern(ExternBlock),
+    Function(FunctionNode),
+    MacroDef(MacroDefNode),
+    /// The source text of an item that is not a supported construct, kept
+    /// when parsing with [`OnUnknown::OpaqueItem`].
+    Opaque(String),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct TraitNode {
+    pub visibility: Visibility,
     pub name: String,
+    pub generics: Vec<GenericParam>,
+    /// The supertrait and lifetime bounds after the name, as in
+    /// `trait Foo<'a>: 'a + Clone`.
+    pub supertraits: Vec<Bound>,
+    pub where_clause: Vec<WherePredicate>,
     pub methods: Vec<MethodNode>,
+    /// The outer attributes of the item, such as `#[derive(Debug)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct StructNode {
+    pub visibility: Visibility,
     pub name: String,
+    pub generics: Vec<GenericParam>,
+    pub where_clause: Vec<WherePredicate>,
     pub fields: Vec<FieldNode>,
+    /// Methods attached from inherent impl blocks by [`AstNode::merge_impl`].
+    pub inherent_methods: Vec<MethodNode>,
+    /// The outer attributes of the item, such as `#[derive(Debug)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct EnumNode {
+    pub visibility: Visibility,
     pub name: String,
+    pub generics: Vec<GenericParam>,
+    pub where_clause: Vec<WherePredicate>,
     pub variants: Vec<VariantNode>,
+    /// Methods attached from inherent impl blocks by [`AstNode::merge_impl`].
+    pub inherent_methods: Vec<MethodNode>,
+    /// The outer attributes of the item, such as `#[derive(Debug)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeAliasNode {
+    pub visibility: Visibility,
+    pub name: String,
+    pub generics: Vec<GenericParam>,
+    pub aliased_type: TypeNode,
+    /// The outer attributes of the item, such as `#[allow(dead_code)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// A `const` item. The value is kept as raw source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstNode {
+    pub visibility: Visibility,
+    pub name: String,
+    pub const_type: TypeNode,
+    pub value: String,
+    /// The outer attributes of the item, such as `#[doc(hidden)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// A `static` or `static mut` item. The value is kept as raw source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaticNode {
+    pub visibility: Visibility,
+    pub name: String,
+    pub static_type: TypeNode,
+    pub value: String,
+    pub is_mut: bool,
+    /// The outer attributes of the item, such as `#[used]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// A `mod` item. Declarations without a body, like `mod foo;`, have no items.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleNode {
+    pub visibility: Visibility,
+    pub name: String,
+    pub items: Vec<AstNode>,
+    /// The outer attributes of the item, such as `#[cfg(test)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// A `use` item. The path is kept as written, including any `{...}` groups
+/// and `as` renames.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UseNode {
+    pub visibility: Visibility,
+    pub path: String,
+    /// The outer attributes of the item, such as `#[doc(no_inline)]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+/// An `extern` block of foreign function declarations, such as
+/// `extern "C" { fn abs(x: i32) -> i32; }`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternBlock {
+    /// The ABI string without its quotes, such as `C` or `system`. Blocks
+    /// written without one use the `C` ABI.
+    pub abi: String,
+    /// The declared functions, each carrying the ABI of the block. They have
+    /// no body, so a signature is all there is to them.
+    pub functions: Vec<FunctionNode>,
+    /// The outer attributes of the item, such as `#[link(name = "m")]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// A free function with an explicit ABI, such as
+/// `pub extern "C" fn on_event(code: i32) {}`. Free functions without one
+/// are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionNode {
+    /// The ABI string without its quotes, as in [`ExternBlock::abi`].
+    pub abi: String,
+    pub signature: MethodNode,
+    /// The outer attributes of the item, such as `#[no_mangle]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// A `macro_rules!` definition. The rules are not parsed; `body` holds the
+/// source text between the outer delimiters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MacroDefNode {
+    pub name: String,
+    pub body: String,
+    /// The outer attributes of the item, such as `#[macro_export]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// An `impl` block, either inherent (`impl Foo`) or implementing a trait
+/// (`impl Display for Foo`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImplNode {
+    pub generics: Vec<GenericParam>,
+    pub where_clause: Vec<WherePredicate>,
+    pub trait_type: Option<TypeNode>,
+    pub self_type: TypeNode,
+    pub items: Vec<ImplItem>,
+    /// Set for `unsafe impl`, as `Send` and `Sync` impls require.
+    pub is_unsafe: bool,
+    /// Set for negative impls such as `impl !Send for Foo`.
+    pub is_negative: bool,
+    /// The outer attributes of the item, such as `#[cfg(feature = "serde")]`.
+    pub attributes: Vec<Attribute>,
+    /// The outer doc comments of the item, one line per line of text.
+    pub doc: Option<String>,
+}
+
+/// An item inside an `impl` block. Method bodies are not kept.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImplItem {
+    Method(MethodNode),
+    Const(ConstNode),
+    Type(TypeAliasNode),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct MethodNode {
+    pub visibility: Visibility,
     pub name: String,
+    pub generics: Vec<GenericParam>,
     pub params: Vec<ParamNode>,
     pub return_type: Option<Box<TypeNode>>,
+    /// The predicates of a `where` clause after the return type, as in
+    /// `fn by_value(self) where Self: Sized`.
+    pub where_clause: Vec<WherePredicate>,
+    /// Whether the method is declared `async fn`.
+    pub is_async: bool,
+    /// Whether the method is written with a body. Trait methods with one
+    /// are provided by default; the body itself is not kept.
+    pub has_body: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ParamNode {
     pub name: String,
     pub param_type: Box<TypeNode>,
 }
 
-#[derive(Debug, PartialEq)]
+/// The kind of `self` parameter a method takes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Receiver {
+    /// `self` or `mut self`.
+    SelfValue,
+    /// `&self`, with or without a lifetime.
+    RefSelf,
+    /// `&mut self`, with or without a lifetime.
+    RefMutSelf,
+    /// An explicitly typed receiver, such as `self: Box<Self>`.
+    Typed(TypeNode),
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct FieldNode {
+    pub visibility: Visibility,
     pub name: String,
     pub field_type: Box<TypeNode>,
+    /// The attributes of the field, such as `#[serde(skip)]`.
+    pub attributes: Vec<Attribute>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct VariantNode {
     pub name: String,
     pub associated_data: Option<Box<AstNode>>,
+    /// The explicit discriminant expression, such as `2` in `Code = 2`.
+    pub discriminant: Option<String>,
+    /// The attributes of the variant, such as `#[deprecated]`.
+    pub attributes: Vec<Attribute>,
+}
+
+/// The visibility an item is declared with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Visibility {
+    /// No visibility qualifier (or `pub(self)`).
+    #[default]
+    Private,
+    Public,
+    Crate,
+    Super,
+    /// `pub(in path)`, holding the path, such as `crate::net`.
+    PubIn(String),
+}
+
+/// An attribute such as `#[derive(Debug)]` or `#![allow(dead_code)]`. The
+/// `args` hold the raw text inside the delimiters, or the value after `=`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub path: String,
+    pub args: Option<String>,
+}
+
+/// The layout requested by a `#[repr(...)]` attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repr {
+    C,
+    Transparent,
+    /// A primitive integer representation such as `u8`, for enums.
+    Int(String),
+    /// `packed`, or `packed(N)` with its alignment.
+    Packed(Option<u64>),
+    Align(u64),
+    /// Several representations, from `#[repr(C, packed)]` or repeated
+    /// attributes, in source order.
+    Combined(Vec<Repr>),
+}
+
+/// File-level information that precedes the items of a source file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileHeader {
+    pub inner_attributes: Vec<Attribute>,
+    /// The inner `//!` and `/*! */` doc comments of the file.
+    pub doc: Option<String>,
+}
+
+/// A parsed source file: its header and the items it contains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub header: FileHeader,
+    pub items: Vec<AstNode>,
+}
+
+/// An error produced when the input cannot be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The input is malformed.
+    Invalid(String),
+    /// The input starts with a keyword, or other token, that does not begin
+    /// a supported item.
+    UnsupportedConstruct(String),
+    /// Types or modules are nested deeper than [`ParseOptions::max_depth`].
+    RecursionLimit,
+}
+
+/// An error produced when an impl block or another definition cannot be
+/// merged into an item.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MergeError {
+    /// The item is not a struct or enum.
+    UnsupportedTarget,
+    /// The impl block implements a trait, so its methods are not inherent.
+    TraitImpl,
+    /// The impl block is for a different type than the item.
+    TypeMismatch { expected: String, found: String },
+    /// The definitions are not both structs, both enums or both traits.
+    IncompatibleKinds,
+    /// The definitions have different names.
+    NameMismatch { expected: String, found: String },
+    /// Both definitions have a field, variant or method of this name.
+    DuplicateMember(String),
+}
+
+/// An error produced when one definition cannot be subtracted from another.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubtractError {
+    /// The definitions are not both structs, both enums or both traits.
+    IncompatibleKinds,
+    /// The definitions have different names.
+    NameMismatch { expected: String, found: String },
+    /// The field, variant or method of this name is not in the item.
+    MissingMember(String),
+}
+
+/// An error produced when an item cannot be exported to another schema
+/// language.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportError {
+    /// The type, shown as written, has no counterpart in the schema
+    /// language.
+    UnsupportedType(String),
+}
+
+/// The keywords that begin the items [`Parser::parse_any`] understands.
+pub const SUPPORTED_CONSTRUCTS: &[&str] = &[
+    "trait",
+    "struct",
+    "enum",
+    "type",
+    "const",
+    "static",
+    "mod",
+    "use",
+    "impl",
+    "extern",
+    "macro_rules",
+];
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Invalid(message) => write!(f, "{}", message),
+            ParseError::UnsupportedConstruct(found) => write!(
+                f,
+                "Unsupported construct `{}`, expected one of: {}",
+                found,
+                SUPPORTED_CONSTRUCTS.join(", ")
+            ),
+            ParseError::RecursionLimit => write!(f, "Input exceeds the maximum nesting depth"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::fmt::Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::UnsupportedTarget => {
+                write!(f, "Impl blocks can only be merged into structs and enums")
+            }
+            MergeError::TraitImpl => write!(f, "Trait impls cannot be merged as inherent methods"),
+            MergeError::TypeMismatch { expected, found } => {
+                write!(f, "Impl block is for `{}`, expected `{}`", found, expected)
+            }
+            MergeError::IncompatibleKinds => {
+                write!(f, "Only two structs, enums or traits can be merged")
+            }
+            MergeError::NameMismatch { expected, found } => {
+                write!(f, "Cannot merge `{}` into `{}`", found, expected)
+            }
+            MergeError::DuplicateMember(name) => {
+                write!(f, "`{}` is defined in both items", name)
+            }
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+impl std::error::Error for MergeError {}
+
+impl std::fmt::Display for SubtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubtractError::IncompatibleKinds => {
+                write!(
+                    f,
+                    "Only structs, enums or traits can be subtracted from each other"
+                )
+            }
+            SubtractError::NameMismatch { expected, found } => {
+                write!(f, "Cannot subtract `{}` from `{}`", found, expected)
+            }
+            SubtractError::MissingMember(name) => {
+                write!(f, "`{}` is not defined in the item", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SubtractError {}
+
+impl std::fmt::Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportError::UnsupportedType(ty) => {
+                write!(f, "`{}` cannot be exported", ty)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<String> for ParseError {
+    fn from(message: String) -> Self {
+        ParseError::Invalid(message)
+    }
+}
+
+impl From<&str> for ParseError {
+    fn from(message: &str) -> Self {
+        ParseError::Invalid(message.to_string())
+    }
+}
+
+/// Limits applied while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// How deeply types, and modules, may nest before parsing fails with
+    /// [`ParseError::RecursionLimit`]. Each nested module uses up one level
+    /// of the depth available to the types inside it.
+    pub max_depth: usize,
+    /// What to do with the items of a file or module that cannot be parsed,
+    /// such as macro invocations, functions or unit structs.
+    pub on_unknown: OnUnknown,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            max_depth: 128,
+            on_unknown: OnUnknown::Error,
+        }
+    }
+}
+
+/// How [`Parser::parse_file_with_options`] treats items that cannot be
+/// parsed, either because they are not a supported construct or because
+/// they take a form the parser does not understand, such as a unit struct.
+/// Exceeding [`ParseOptions::max_depth`] always fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnUnknown {
+    /// Leave the item out.
+    Skip,
+    /// Keep the item's source text as an [`AstNode::Opaque`].
+    OpaqueItem,
+    /// Fail with the error.
+    Error,
+}
+
+/// A generic parameter declared by an item, e.g. `'a`, `T: Clone` or
+/// `const N: usize`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GenericParam {
+    Lifetime {
+        name: String,
+        bounds: Vec<Bound>,
+    },
+    Type {
+        name: String,
+        bounds: Vec<Bound>,
+        /// The default type, as in `T = String`.
+        default: Option<TypeNode>,
+    },
+    Const {
+        name: String,
+        ty: TypeNode,
+    },
+}
+
+/// A predicate of a `where` clause, e.g. `Self::Item: Clone`. The subject
+/// may be any type, including associated types and qualified paths such as
+/// `<T as Trait>::Assoc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WherePredicate {
+    pub subject: TypeNode,
+    pub bounds: Vec<Bound>,
+}
+
+/// A bound on a generic parameter or `where` predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Bound {
+    /// A lifetime the bounded type or lifetime must outlive, e.g. `'a`.
+    Lifetime(String),
+    /// A trait bound such as `Clone` or `Iterator<Item = u8>`.
+    Trait(TypeNode),
+    /// A negative auto-trait bound such as `!Send`.
+    Negative(TypeNode),
+    /// A closure trait bound written with parenthesized parameters, e.g.
+    /// `Fn(i32, &str) -> bool`, `FnMut()` or `FnOnce(String)`.
+    Closure {
+        name: String,
+        params: Vec<TypeNode>,
+        return_type: Option<Box<TypeNode>>,
+    },
+}
+
+/// The kind of a generic parameter, ordered the way Rust requires them to be
+/// declared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum GenericParamKind {
+    Lifetime,
+    Type,
+    Const,
+}
+
+/// A generic parameter declared after one that Rust requires to come later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderWarning {
+    pub item: String,
+    pub param: String,
+    pub kind: GenericParamKind,
+    pub should_precede: String,
+    pub should_precede_kind: GenericParamKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum TypeNode {
     Simple(String),
-    Reference(Box<TypeNode>),
-    Generic { name: String, args: Vec<TypeNode> },
+    /// `&T`, `&mut T` or either with a lifetime, such as `&'a T`.
+    Reference {
+        /// The lifetime including its `'`, such as `'a`.
+        lifetime: Option<String>,
+        mutable: bool,
+        inner: Box<TypeNode>,
+    },
+    /// `*const T` or `*mut T`.
+    RawPointer {
+        mutable: bool,
+        inner: Box<TypeNode>,
+    },
+    Generic {
+        name: String,
+        args: Vec<TypeNode>,
+    },
+    Tuple(Vec<TypeNode>),
+    ImplTrait(Vec<Bound>),
+    DynTrait(Vec<Bound>),
 }
 
 pub struct Parser;
 
 impl Parser {
-    pub fn parse(input: &str) -> Result<AstNode, String> {
+    pub fn parse(input: &str) -> Result<AstNode, ParseError> {
+        Self::parse_any(input)
+    }
+
+    /// Parses a single item of any supported kind, detected from the keyword
+    /// that follows its optional attributes and visibility. Outer attributes
+    /// and doc comments are attached to the item.
+    pub fn parse_any(input: &str) -> Result<AstNode, ParseError> {
+        Self::parse_with_options(input, &ParseOptions::default())
+    }
+
+    /// Like [`Parser::parse_any`], with the limits given by `options`.
+    pub fn parse_with_options(input: &str, options: &ParseOptions) -> Result<AstNode, ParseError> {
+        Self::parse_any_within(input, options.max_depth, options.on_unknown)
+    }
+
+    fn parse_any_within(
+        input: &str,
+        depth: usize,
+        on_unknown: OnUnknown,
+    ) -> Result<AstNode, ParseError> {
+        let mut docs = Vec::new();
+        let (attributes, input) = Self::parse_outer_attributes(input, &mut docs)?;
+        let input = input.trim_end();
+        if input.is_empty() {
+            return Err("Empty input".into());
+        }
+
+        let mut node = Self::parse_item(input, depth, on_unknown)?;
+        let doc = join_docs(&docs);
+        match &mut node {
+            AstNode::Trait(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Struct(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Enum(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::TypeAlias(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Const(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Static(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Module(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Use(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Impl(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Extern(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::Function(node) => (node.attributes, node.doc) = (attributes, doc),
+            AstNode::MacroDef(node) => (node.attributes, node.doc) = (attributes, doc),
+            // Items are only kept opaque by `parse_file`, with their source
+            // text, attributes included
+            AstNode::Opaque(_) => {}
+        }
+        Ok(node)
+    }
+
+    /// Splits the outer doc comments and `#[...]` attributes off an item,
+    /// collecting the docs into `docs`.
+    fn parse_outer_attributes<'a>(
+        input: &'a str,
+        docs: &mut Vec<&'a str>,
+    ) -> Result<(Vec<Attribute>, &'a str), ParseError> {
+        let mut attributes = Vec::new();
+        let mut input = take_docs(input, false, docs);
+        while let Some(attr) = input.strip_prefix('#') {
+            let attr = attr.trim_start();
+            if !attr.starts_with('[') {
+                return Err("Invalid attribute".into());
+            }
+            let close = find_closing(attr).ok_or("Unclosed attribute")?;
+            attributes.push(Self::parse_attribute(&attr[1..close])?);
+            input = take_docs(&attr[close + 1..], false, docs);
+        }
+        Ok((attributes, input))
+    }
+
+    /// Dispatches on the keyword that follows the visibility of an item.
+    fn parse_item(input: &str, depth: usize, on_unknown: OnUnknown) -> Result<AstNode, ParseError> {
+        let (_, rest) = Self::parse_visibility(input)?;
+        let keyword_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        match &rest[..keyword_end] {
+            "trait" => Self::parse_trait(input, depth),
+            "struct" => Self::parse_struct(input, depth),
+            "enum" => Self::parse_enum(input, depth),
+            "type" => Self::parse_type_alias(input, depth).map(AstNode::TypeAlias),
+            "const" => Self::parse_const(input, depth).map(AstNode::Const),
+            "static" => Self::parse_static(input, depth),
+            "mod" => Self::parse_module(input, depth, on_unknown),
+            "use" => Self::parse_use(input),
+            "impl" => Self::parse_impl(input, depth),
+            "unsafe" if after_keyword(rest, "unsafe")?.starts_with("impl") => {
+                Self::parse_impl(input, depth)
+            }
+            "unsafe" if after_keyword(rest, "unsafe")?.starts_with("extern") => {
+                Self::parse_extern(input, depth)
+            }
+            "extern" => Self::parse_extern(input, depth),
+            "macro_rules" => Self::parse_macro_def(input),
+            "" => Err(ParseError::UnsupportedConstruct(
+                rest.split_whitespace().next().unwrap_or(rest).to_string(),
+            )),
+            other => Err(ParseError::UnsupportedConstruct(other.to_string())),
+        }
+    }
+
+    /// Parses every top-level item in `input`, such as the contents of a
+    /// source file. File-level inner attributes are skipped; use
+    /// [`Parser::parse_file`] to retrieve them.
+    pub fn parse_many(input: &str) -> Result<Vec<AstNode>, ParseError> {
+        Ok(Self::parse_file(input)?.items)
+    }
+
+    /// Parses a whole source file, collecting the leading `#![...]` inner
+    /// attributes and inner doc comments into the file header.
+    pub fn parse_file(input: &str) -> Result<SourceFile, ParseError> {
+        Self::parse_file_with_options(input, &ParseOptions::default())
+    }
+
+    /// Like [`Parser::parse_file`], with the limits and handling of unknown
+    /// items given by `options`.
+    pub fn parse_file_with_options(
+        input: &str,
+        options: &ParseOptions,
+    ) -> Result<SourceFile, ParseError> {
+        Self::parse_file_within(input, options.max_depth, options.on_unknown)
+    }
+
+    fn parse_file_within(
+        input: &str,
+        depth: usize,
+        on_unknown: OnUnknown,
+    ) -> Result<SourceFile, ParseError> {
+        let mut header = FileHeader::default();
+        let mut docs = Vec::new();
+        let mut rest = take_docs(input, true, &mut docs);
+        while let Some(attr) = rest.strip_prefix("#!") {
+            let attr = attr.trim_start();
+            if !attr.starts_with('[') {
+                return Err("Invalid inner attribute".into());
+            }
+            let close = find_closing(attr).ok_or("Unclosed inner attribute")?;
+            header
+                .inner_attributes
+                .push(Self::parse_attribute(&attr[1..close])?);
+            rest = take_docs(&attr[close + 1..], true, &mut docs);
+        }
+        header.doc = join_docs(&docs);
+
+        let mut items = Vec::new();
+        for item in split_items(rest) {
+            match (Self::parse_any_within(item, depth, on_unknown), on_unknown) {
+                (Err(ParseError::RecursionLimit), _) => return Err(ParseError::RecursionLimit),
+                (Err(_), OnUnknown::Skip) => {}
+                (Err(_), OnUnknown::OpaqueItem) => items.push(AstNode::Opaque(item.to_string())),
+                (result, _) => items.push(result?),
+            }
+        }
+
+        Ok(SourceFile { header, items })
+    }
+
+    /// Parses the contents of an attribute, without the surrounding `#[` and
+    /// `]`.
+    fn parse_attribute(input: &str) -> Result<Attribute, ParseError> {
         let input = input.trim();
-        if input.starts_with("pub trait") {
-            Parser::parse_trait(input)
-        } else if input.starts_with("pub struct") {
-            Parser::parse_struct(input)
-        } else if input.starts_with("pub enum") {
-            Parser::parse_enum(input)
-        } else {
-            Err("Unsupported or invalid Rust construct".to_string())
+        let path_end = input.find(['(', '[', '{', '=']).unwrap_or(input.len());
+        let path = input[..path_end].trim();
+        if path.is_empty() {
+            return Err("Invalid attribute".into());
         }
+
+        let rest = input[path_end..].trim();
+        let args = if let Some(value) = rest.strip_prefix('=') {
+            Some(value.trim().to_string())
+        } else if rest.is_empty() {
+            None
+        } else {
+            let close = find_closing(rest).ok_or("Unclosed attribute arguments")?;
+            Some(rest[1..close].trim().to_string())
+        };
+
+        Ok(Attribute {
+            path: path.to_string(),
+            args,
+        })
     }
 
-    fn parse_trait(input: &str) -> Result<AstNode, String> {
-        let trait_name = input
-            .split_whitespace()
-            .nth(2)
-            .ok_or("Invalid trait definition")?
-            .to_string();
+    /// Parses the method signatures between the braces of a trait, for
+    /// callers that have the body without the surrounding definition.
+    pub fn parse_trait_body(body: &str) -> Result<Vec<MethodNode>, ParseError> {
+        Self::parse_trait_body_within(body, ParseOptions::default().max_depth)
+    }
+
+    /// Parses a single method signature such as `fn len(&self) -> usize`,
+    /// with or without the terminating `;`.
+    pub fn parse_method_signature(signature: &str) -> Result<MethodNode, ParseError> {
+        let signature = signature.split_whitespace().collect::<Vec<_>>().join(" ");
+        Self::parse_method(&signature, ParseOptions::default().max_depth)
+    }
+
+    fn parse_trait_body_within(body: &str, depth: usize) -> Result<Vec<MethodNode>, ParseError> {
+        split_items(body)
+            .into_iter()
+            .map(|method_str| {
+                // Signatures may span several lines
+                let method_str = method_str.split_whitespace().collect::<Vec<_>>().join(" ");
+                Self::parse_method(&method_str, depth)
+            })
+            .collect()
+    }
+
+    fn parse_trait(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let (trait_name, generics, rest) = Self::parse_item_name(input, "trait", depth)?;
+        let (header, where_clause) = split_where_clause(rest.split('{').next().unwrap_or(rest));
+        let supertraits = match header.strip_prefix(':') {
+            Some(bounds) => Self::parse_bounds(bounds, depth)?,
+            None => Vec::new(),
+        };
+        let where_clause = Self::parse_where_clause(where_clause, depth)?;
 
         let body_start = input.find('{').ok_or("Missing trait body")?;
         let body_end = input.rfind('}').ok_or("Missing closing brace")?;
         if body_end <= body_start {
-            return Err("Invalid trait body".to_string());
-        }
-        let body_content = &input[body_start + 1..body_end].trim();
-
-        let method_strings: Vec<&str> = body_content
-            .split(';')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
-
-        let mut methods = Vec::new();
-        for method_str in method_strings {
-            methods.push(Self::parse_method(method_str)?);
+            return Err("Invalid trait body".into());
         }
+        let methods = Self::parse_trait_body_within(&input[body_start + 1..body_end], depth)?;
 
         Ok(AstNode::Trait(TraitNode {
+            visibility,
             name: trait_name,
+            generics,
+            supertraits,
+            where_clause,
             methods,
+            attributes: Vec::new(),
+            doc: None,
         }))
     }
 
-    fn parse_struct(input: &str) -> Result<AstNode, String> {
-        let struct_name = input
-            .split_whitespace()
-            .nth(2)
-            .ok_or("Invalid struct definition")?
-            .to_string();
+    /// Parses the fields between the braces of a struct, for callers that
+    /// have the body without the surrounding definition.
+    pub fn parse_struct_body(body: &str) -> Result<Vec<FieldNode>, ParseError> {
+        Self::parse_fields(body, ParseOptions::default().max_depth)
+    }
+
+    fn parse_struct(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let (struct_name, generics, rest) = Self::parse_item_name(input, "struct", depth)?;
+        let (_, where_clause) = split_where_clause(rest.split('{').next().unwrap_or(rest));
+        let where_clause = Self::parse_where_clause(where_clause, depth)?;
 
         let body_start = input.find('{').ok_or("Missing struct body")?;
         let body_end = input.rfind('}').ok_or("Missing closing brace")?;
         if body_end <= body_start {
-            return Err("Invalid struct body".to_string());
+            return Err("Invalid struct body".into());
         }
         let body_content = &input[body_start + 1..body_end].trim();
 
-        let fields = body_content
-            .split(',')
-            .map(|field_str| {
-                let parts: Vec<&str> = field_str.split(':').collect();
-                if parts.len() != 2 {
-                    return Err("Invalid field format".to_string());
-                }
-                Ok(FieldNode {
-                    name: parts[0].trim().to_string(),
-                    field_type: Box::new(Self::parse_type(parts[1].trim())?),
-                })
-            })
-            .collect::<Result<Vec<FieldNode>, String>>()?;
+        let fields = Self::parse_fields(body_content, depth)?;
 
         Ok(AstNode::Struct(StructNode {
+            visibility,
             name: struct_name,
+            generics,
+            where_clause,
             fields,
+            inherent_methods: Vec::new(),
+            attributes: Vec::new(),
+            doc: None,
         }))
     }
 
-    fn parse_enum(input: &str) -> Result<AstNode, String> {
-        let enum_name = input
-            .split_whitespace()
-            .nth(2)
-            .ok_or("Invalid enum definition")?
-            .to_string();
-
-        let body_start = input.find('{').ok_or("Missing enum body")?;
-        let body_end = input.rfind('}').ok_or("Missing closing brace")?;
-        if body_end <= body_start {
-            return Err("Invalid enum body".to_string());
-        }
-        let body_content = &input[body_start + 1..body_end].trim();
+    /// Parses the variants between the braces of an enum, for callers that
+    /// have the body without the surrounding definition.
+    pub fn parse_enum_body(body: &str) -> Result<Vec<VariantNode>, ParseError> {
+        Self::parse_enum_body_within(body, ParseOptions::default().max_depth)
+    }
 
-        let variant_strings: Vec<&str> = body_content
-            .split(',')
+    fn parse_enum_body_within(body: &str, depth: usize) -> Result<Vec<VariantNode>, ParseError> {
+        let variant_strings: Vec<&str> = split_top_level(body, ',')
+            .into_iter()
             .map(|s| s.trim())
             .filter(|s| !s.is_empty())
             .collect();
 
         let mut variants = Vec::new();
         for variant_str in variant_strings {
-            if variant_str.contains('(') && variant_str.contains(')') {
-                // Variant with associated data
-                let name = variant_str.split('(').next().unwrap().trim().to_string();
-                let data_str = variant_str.split('(').nth(1).unwrap().trim_end_matches(')');
-                // For simplicity, assume associated data is a struct
-                let associated_ast = Parser::parse(data_str)?;
-                variants.push(VariantNode {
-                    name,
-                    associated_data: Some(Box::new(associated_ast)),
-                });
-            } else {
-                // Simple variant
-                variants.push(VariantNode {
-                    name: variant_str.to_string(),
-                    associated_data: None,
-                });
-            }
+            let (attributes, variant_str) = Self::parse_member_attributes(variant_str)?;
+            let (name, data, discriminant) = split_variant(variant_str)?;
+            let associated_data = match data.map(|d| (d, &d[1..d.len() - 1])) {
+                Some((d, data_str)) if d.starts_with('(') => {
+                    Some(Box::new(Self::parse_tuple_variant(data_str, depth)?))
+                }
+                // Struct variant, stored as an anonymous struct
+                Some((_, data_str)) => Some(Box::new(AstNode::Struct(StructNode {
+                    name: "".to_string(),
+                    fields: Self::parse_fields(data_str, depth)?,
+                    ..Default::default()
+                }))),
+                None => None,
+            };
+            variants.push(VariantNode {
+                name: name.to_string(),
+                associated_data,
+                discriminant: discriminant.map(str::to_string),
+                attributes,
+            });
+        }
+
+        Ok(variants)
+    }
+
+    fn parse_enum(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let (enum_name, generics, rest) = Self::parse_item_name(input, "enum", depth)?;
+        let (_, where_clause) = split_where_clause(rest.split('{').next().unwrap_or(rest));
+        let where_clause = Self::parse_where_clause(where_clause, depth)?;
+
+        let body_start = input.find('{').ok_or("Missing enum body")?;
+        let body_end = input.rfind('}').ok_or("Missing closing brace")?;
+        if body_end <= body_start {
+            return Err("Invalid enum body".into());
         }
+        let variants = Self::parse_enum_body_within(&input[body_start + 1..body_end], depth)?;
 
         Ok(AstNode::Enum(EnumNode {
+            visibility,
             name: enum_name,
+            generics,
+            where_clause,
             variants,
+            inherent_methods: Vec::new(),
+            attributes: Vec::new(),
+            doc: None,
         }))
     }
 
-    fn parse_method(input: &str) -> Result<MethodNode, String> {
-        let input = input.trim();
-        let parts: Vec<&str> = input.split(&['(', ')']).collect();
-        if parts.len() < 2 {
-            return Err("Invalid method format".to_string());
+    fn parse_use(input: &str) -> Result<AstNode, ParseError> {
+        let (visibility, rest) = Self::parse_visibility(input)?;
+        let path = rest
+            .strip_prefix("use ")
+            .ok_or("Invalid use declaration")?
+            .trim()
+            .trim_end_matches(';')
+            .trim();
+        if path.is_empty() {
+            return Err("Invalid use declaration".into());
         }
 
-        let name = parts[0]
-            .split_whitespace()
-            .nth(1)
-            .ok_or("Invalid method name")?
-            .to_string();
-
-        let params = Self::parse_params(parts[1])?;
-
-        let return_type = if input.contains("->") {
-            let return_str = input
-                .split("->")
-                .nth(1)
-                .unwrap()
-                .trim()
-                .trim_end_matches(';')
-                .to_string();
-            Some(Box::new(Self::parse_type(&return_str)?))
-        } else {
-            None
-        };
-
-        Ok(MethodNode {
-            name,
-            params,
-            return_type,
-        })
+        Ok(AstNode::Use(UseNode {
+            visibility,
+            path: path.to_string(),
+            attributes: Vec::new(),
+            doc: None,
+        }))
     }
 
-    fn parse_params(input: &str) -> Result<Vec<ParamNode>, String> {
-        if input.trim().is_empty() {
-            return Ok(Vec::new());
+    /// Parses an `extern` block or an `extern` function definition. Only
+    /// function declarations are supported inside a block.
+    fn parse_extern(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let rest = after_keyword(input, "extern")?;
+        let (abi, rest) = match rest.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').ok_or("Unclosed extern ABI")?;
+                (&quoted[..end], quoted[end + 1..].trim_start())
+            }
+            None => ("C", rest),
+        };
+        if rest
+            .strip_prefix("fn")
+            .is_some_and(|r| r.starts_with(char::is_whitespace))
+        {
+            return Ok(AstNode::Function(FunctionNode {
+                abi: abi.to_string(),
+                signature: Self::parse_method(input, depth)?,
+                attributes: Vec::new(),
+                doc: None,
+            }));
         }
-
-        input
-            .split(',')
-            .map(|param| {
-                let param = param.trim();
-                if param == "&self" {
-                    Ok(ParamNode {
-                        name: "&self".to_string(),
-                        param_type: Box::new(TypeNode::Reference(Box::new(TypeNode::Simple("self".to_string())))),
-                    })
-                } else if param == "self" {
-                    Ok(ParamNode {
-                        name: "self".to_string(),
-                        param_type: Box::new(TypeNode::Simple("self".to_string())),
-                    })
-                } else {
-                    let parts: Vec<&str> = param.split(':').collect();
-                    if parts.len() != 2 {
-                        return Err("Invalid parameter format".to_string());
-                    }
-                    Ok(ParamNode {
-                        name: parts[0].trim().to_string(),
-                        param_type: Box::new(Self::parse_type(parts[1].trim())?),
-                    })
-                }
-            })
-            .collect()
-    }
-
-    fn parse_type(input: &str) -> Result<TypeNode, String> {
-        if input.starts_with('&') {
-            let inner = input.trim_start_matches('&').trim();
-            let inner_type = Self::parse_type(inner)?;
-            Ok(TypeNode::Reference(Box::new(inner_type)))
-        } else if input.starts_with('[') && input.ends_with(']') {
-            let inner_str = &input[1..input.len()-1].trim();
-            let inner_type = Self::parse_type(inner_str)?;
-            Ok(TypeNode::Generic {
-                name: "[]".to_string(),
-                args: vec![inner_type],
-            })
-        } else if input.contains('<') && input.contains('>') {
-            let name = input.split('<').next().unwrap().trim().to_string();
-            let args_str = input
-                .split('<')
-                .nth(1)
-                .unwrap()
-                .trim_end_matches('>')
-                .trim();
-            let args: Result<Vec<TypeNode>, String> = args_str
-                .split(',')
-                .map(|arg| Self::parse_type(arg.trim()))
-                .collect();
-            Ok(TypeNode::Generic { name, args: args? })
-        } else {
-            Ok(TypeNode::Simple(input.to_string()))
+        if !rest.starts_with('{') {
+            // `extern crate`
+            return Err(ParseError::UnsupportedConstruct("extern".to_string()));
         }
-    }
+        let body_end = find_closing(rest).ok_or("Missing closing brace")?;
 
-    fn parse_tuple_variant(input: &str) -> Result<AstNode, String> {
-        let fields: Vec<FieldNode> = input
-            .split(',')
+        let functions = split_top_level(&rest[1..body_end], ';')
+            .into_iter()
             .map(|s| s.trim())
             .filter(|s| !s.is_empty())
-            .enumerate()
-            .map(|(i, s)| -> Result<FieldNode, String> {
-                Ok(FieldNode {
-                    name: format!("{}", i),
-                    field_type: Box::new(Self::parse_type(s)?),
+            .map(|function| {
+                let mut docs = Vec::new();
+                let (attributes, function) = Self::parse_outer_attributes(function, &mut docs)?;
+                let function = function.split_whitespace().collect::<Vec<_>>().join(" ");
+                Ok(FunctionNode {
+                    abi: abi.to_string(),
+                    signature: Self::parse_method(&function, depth)?,
+                    attributes,
+                    doc: join_docs(&docs),
                 })
             })
-            .collect::<Result<Vec<FieldNode>, String>>()?;
+            .collect::<Result<Vec<FunctionNode>, ParseError>>()?;
 
-        Ok(AstNode::Struct(StructNode {
-            name: "".to_string(),
-            fields,
+        Ok(AstNode::Extern(ExternBlock {
+            abi: abi.to_string(),
+            functions,
+            attributes: Vec::new(),
+            doc: None,
         }))
     }
-}
 
-impl FromStr for AstNode {
-    type Err = String;
+    /// Parses a `macro_rules!` definition, keeping its rules as raw text.
+    fn parse_macro_def(input: &str) -> Result<AstNode, ParseError> {
+        let rest = input
+            .trim_start()
+            .strip_prefix("macro_rules!")
+            .ok_or("Invalid macro_rules definition")?
+            .trim_start();
+        let name_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let (name, rest) = rest.split_at(name_end);
+        if name.is_empty() {
+            return Err("Invalid macro_rules definition".into());
+        }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Parser::parse(s)
+        let rest = rest.trim().trim_end_matches(';').trim_end();
+        let close = match rest.chars().next() {
+            Some('{') => '}',
+            Some('(') => ')',
+            Some('[') => ']',
+            _ => return Err("Missing macro_rules body".into()),
+        };
+        if rest.len() < 2 || !rest.ends_with(close) {
+            return Err("Missing closing delimiter".into());
+        }
+
+        Ok(AstNode::MacroDef(MacroDefNode {
+            name: name.to_string(),
+            body: rest[1..rest.len() - 1].trim().to_string(),
+            attributes: Vec::new(),
+            doc: None,
+        }))
     }
-}
 
-// Tree Display Implementation with Recursive Traversal
-impl AstNode {
-    pub fn display_tree(&self) {
-        self.display_tree_internal("");
+    /// Parses a leading visibility qualifier, returning it along with the rest
+    /// of the input.
+    fn parse_visibility(input: &str) -> Result<(Visibility, &str), ParseError> {
+        let input = input.trim_start();
+        let Some(rest) = input.strip_prefix("pub") else {
+            return Ok((Visibility::Private, input));
+        };
+        let rest = if rest.starts_with(|c: char| c.is_whitespace() || c == '(') {
+            rest.trim_start()
+        } else {
+            // An identifier that merely starts with `pub`
+            return Ok((Visibility::Private, input));
+        };
+
+        if !rest.starts_with('(') {
+            return Ok((Visibility::Public, rest));
+        }
+        let close = find_closing(rest).ok_or("Unclosed visibility restriction")?;
+        let visibility = match rest[1..close].trim() {
+            "crate" => Visibility::Crate,
+            "super" => Visibility::Super,
+            "self" => Visibility::Private,
+            other if other.starts_with("in") && other[2..].starts_with(char::is_whitespace) => {
+                Visibility::PubIn(other[2..].split_whitespace().collect())
+            }
+            other => return Err(format!("Unsupported visibility `pub({})`", other).into()),
+        };
+        Ok((visibility, rest[close + 1..].trim_start()))
     }
 
-    fn display_tree_internal(&self, prefix: &str) {
-        match self {
-            AstNode::Trait(trait_node) => {
-                println!("{}- Trait: {}", prefix, trait_node.name);
-                let len = trait_node.methods.len();
-                for (i, method) in trait_node.methods.iter().enumerate() {
-                    let is_last = i == len - 1;
-                    let branch = if is_last { "└──" } else { "├──" };
-                    let new_prefix = format!("{}{} ", prefix, branch);
-                    method.display_tree_internal(&new_prefix, is_last);
+    fn parse_module(
+        input: &str,
+        depth: usize,
+        on_unknown: OnUnknown,
+    ) -> Result<AstNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let rest = after_keyword(input, "mod")?;
+        let name_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        if name.is_empty() {
+            return Err("Invalid mod definition".into());
+        }
+
+        let rest = rest[name_end..].trim();
+        let items = if rest.starts_with('{') {
+            let body_end = find_closing(rest).ok_or("Missing closing brace")?;
+            if depth == 0 {
+                return Err(ParseError::RecursionLimit);
+            }
+            Self::parse_file_within(&rest[1..body_end], depth - 1, on_unknown)?.items
+        } else if rest == ";" {
+            Vec::new()
+        } else {
+            return Err("Invalid mod definition".into());
+        };
+
+        Ok(AstNode::Module(ModuleNode {
+            visibility,
+            name: name.to_string(),
+            items,
+            attributes: Vec::new(),
+            doc: None,
+        }))
+    }
+
+    fn parse_impl(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let is_unsafe = input.trim_start().starts_with("unsafe ");
+        let rest = after_keyword(input, "impl")?;
+        let (generics, rest) = if rest.starts_with('<') {
+            let close = find_closing(rest).ok_or("Unclosed generic parameter list")?;
+            (
+                Self::parse_generic_params(&rest[1..close], depth)?,
+                rest[close + 1..].trim_start(),
+            )
+        } else {
+            (Vec::new(), rest)
+        };
+
+        let body_start = rest.find('{').ok_or("Missing impl body")?;
+        let body_end = rest.rfind('}').ok_or("Missing closing brace")?;
+        if body_end <= body_start {
+            return Err("Invalid impl body".into());
+        }
+
+        let (header, where_clause) = split_where_clause(&rest[..body_start]);
+        let (trait_type, self_type) = match header.split_once(" for ") {
+            Some((trait_str, self_str)) => (Some(trait_str.trim()), self_str.trim()),
+            None => (None, header),
+        };
+        let (is_negative, trait_type) = match trait_type.map(|t| t.strip_prefix('!')) {
+            Some(Some(trait_str)) => (true, Some(trait_str.trim_start())),
+            _ => (false, trait_type),
+        };
+        let trait_type = match trait_type {
+            Some(trait_str) => Some(Self::parse_type(trait_str, depth)?),
+            None => None,
+        };
+        if self_type.is_empty() || self_type.starts_with('!') {
+            return Err("Invalid impl definition".into());
+        }
+
+        let items = split_items(&rest[body_start + 1..body_end])
+            .into_iter()
+            .map(|item| Self::parse_impl_item(item, depth))
+            .collect::<Result<Vec<ImplItem>, ParseError>>()?;
+
+        Ok(AstNode::Impl(ImplNode {
+            generics,
+            where_clause: Self::parse_where_clause(where_clause, depth)?,
+            trait_type,
+            self_type: Self::parse_type(self_type, depth)?,
+            items,
+            is_unsafe,
+            is_negative,
+            attributes: Vec::new(),
+            doc: None,
+        }))
+    }
+
+    /// Parses a method, associated const or associated type of an `impl`
+    /// block. Visibility qualifiers are accepted and method bodies skipped.
+    fn parse_impl_item(input: &str, depth: usize) -> Result<ImplItem, ParseError> {
+        let (_, rest) = Self::parse_visibility(input)?;
+        if rest.starts_with("fn ") || rest.starts_with("const fn ") {
+            Ok(ImplItem::Method(Self::parse_method(input, depth)?))
+        } else if rest.starts_with("const ") {
+            Ok(ImplItem::Const(Self::parse_const(input, depth)?))
+        } else if rest.starts_with("type ") {
+            Ok(ImplItem::Type(Self::parse_type_alias(input, depth)?))
+        } else {
+            Err("Unsupported impl item".into())
+        }
+    }
+
+    fn parse_type_alias(input: &str, depth: usize) -> Result<TypeAliasNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let (name, generics, rest) = Self::parse_item_name(input, "type", depth)?;
+        let aliased = rest
+            .strip_prefix('=')
+            .ok_or("Missing aliased type")?
+            .trim()
+            .trim_end_matches(';')
+            .trim();
+        if aliased.is_empty() {
+            return Err("Missing aliased type".into());
+        }
+
+        Ok(TypeAliasNode {
+            visibility,
+            name,
+            generics,
+            aliased_type: Self::parse_type(aliased, depth)?,
+            attributes: Vec::new(),
+            doc: None,
+        })
+    }
+
+    fn parse_const(input: &str, depth: usize) -> Result<ConstNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let rest = after_keyword(input, "const")?;
+        if rest.starts_with("fn ") {
+            return Err(ParseError::UnsupportedConstruct("const fn".to_string()));
+        }
+
+        let (name, const_type, value) = Self::parse_binding(rest, "const", depth)?;
+        Ok(ConstNode {
+            visibility,
+            name,
+            const_type,
+            value,
+            attributes: Vec::new(),
+            doc: None,
+        })
+    }
+
+    fn parse_static(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let (visibility, _) = Self::parse_visibility(input)?;
+        let rest = after_keyword(input, "static")?;
+        let (is_mut, rest) = match rest.strip_prefix("mut ") {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, rest),
+        };
+
+        let (name, static_type, value) = Self::parse_binding(rest, "static", depth)?;
+        Ok(AstNode::Static(StaticNode {
+            visibility,
+            name,
+            static_type,
+            value,
+            is_mut,
+            attributes: Vec::new(),
+            doc: None,
+        }))
+    }
+
+    /// Parses the `NAME: Type = value;` part that follows the keyword of a
+    /// `const` or `static` item, keeping the value as raw text.
+    fn parse_binding(
+        rest: &str,
+        keyword: &str,
+        depth: usize,
+    ) -> Result<(String, TypeNode, String), ParseError> {
+        let (name, rest) = rest
+            .split_once(':')
+            .ok_or(format!("Missing {} type", keyword))?;
+        let name = name.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid {} definition", keyword).into());
+        }
+
+        let type_str = split_top_level(rest, '=')[0];
+        let value = rest[type_str.len()..]
+            .strip_prefix('=')
+            .ok_or(format!("Missing {} value", keyword))?
+            .trim()
+            .trim_end_matches(';')
+            .trim();
+        if value.is_empty() {
+            return Err(format!("Missing {} value", keyword).into());
+        }
+
+        Ok((
+            name.to_string(),
+            Self::parse_type(type_str.trim(), depth)?,
+            value.to_string(),
+        ))
+    }
+
+    /// Parses the name and generic parameters that follow `keyword` in an
+    /// item header such as `pub struct Name<T> {`, returning them along with
+    /// the rest of the input.
+    fn parse_item_name<'a>(
+        input: &'a str,
+        keyword: &str,
+        depth: usize,
+    ) -> Result<(String, Vec<GenericParam>, &'a str), ParseError> {
+        let rest = after_keyword(input, keyword)?;
+        let name_end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        if name.is_empty() {
+            return Err(format!("Invalid {} definition", keyword).into());
+        }
+
+        let rest = rest[name_end..].trim_start();
+        let (generics, rest) = if rest.starts_with('<') {
+            let close = find_closing(rest).ok_or("Unclosed generic parameter list")?;
+            (
+                Self::parse_generic_params(&rest[1..close], depth)?,
+                rest[close + 1..].trim_start(),
+            )
+        } else {
+            (Vec::new(), rest)
+        };
+
+        Ok((name.to_string(), generics, rest))
+    }
+
+    fn parse_generic_params(input: &str, depth: usize) -> Result<Vec<GenericParam>, ParseError> {
+        split_top_level(input, ',')
+            .into_iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|param| {
+                if let Some(rest) = param.strip_prefix("const ") {
+                    let (name, ty) = rest
+                        .split_once(':')
+                        .ok_or("Const generic parameter is missing its type")?;
+                    Ok(GenericParam::Const {
+                        name: name.trim().to_string(),
+                        ty: Self::parse_type(ty.trim(), depth)?,
+                    })
+                } else {
+                    let (param, default) = split_default(param)?;
+                    let (name, bounds) = param.split_once(':').unwrap_or((param, ""));
+                    let name = name.trim().to_string();
+                    let bounds = Self::parse_bounds(bounds, depth)?;
+                    if !name.starts_with('\'') {
+                        let default = default.map(|d| Self::parse_type(d, depth)).transpose()?;
+                        Ok(GenericParam::Type {
+                            name,
+                            bounds,
+                            default,
+                        })
+                    } else if default.is_none() {
+                        Ok(GenericParam::Lifetime { name, bounds })
+                    } else {
+                        Err(format!("Lifetime parameter `{}` cannot have a default", name).into())
+                    }
                 }
+            })
+            .collect()
+    }
+
+    /// Parses a `+`-separated list of bounds, such as `Clone + Send + 'a`.
+    fn parse_bounds(input: &str, depth: usize) -> Result<Vec<Bound>, ParseError> {
+        split_top_level(input, '+')
+            .into_iter()
+            .map(|b| b.trim())
+            .filter(|b| !b.is_empty())
+            .map(|b| Self::parse_bound(b, depth))
+            .collect()
+    }
+
+    fn parse_bound(input: &str, depth: usize) -> Result<Bound, ParseError> {
+        if depth == 0 {
+            return Err(ParseError::RecursionLimit);
+        }
+        if input.starts_with('(') && find_closing(input) == Some(input.len() - 1) {
+            // Parentheses only group the bound
+            return Self::parse_bound(input[1..input.len() - 1].trim(), depth - 1);
+        }
+        if input.starts_with('\'') {
+            return Ok(Bound::Lifetime(input.to_string()));
+        }
+        // A `!` or `?` prefix needs a trait to apply to
+        if matches!(input.strip_prefix(['!', '?']), Some(rest) if rest.trim().is_empty()) {
+            return Err(format!("Invalid bound `{}`", input).into());
+        }
+        if let Some(negated) = input.strip_prefix('!') {
+            return Ok(Bound::Negative(Self::parse_type(
+                negated.trim_start(),
+                depth - 1,
+            )?));
+        }
+
+        let closure = input.find('(').filter(|&open| {
+            let name = input[..open].trim();
+            matches!(name.rsplit("::").next(), Some("Fn" | "FnMut" | "FnOnce"))
+        });
+        let Some(open) = closure else {
+            return Ok(Bound::Trait(Self::parse_type(input, depth)?));
+        };
+        let close = open + find_closing(&input[open..]).ok_or("Unclosed closure parameters")?;
+        let params = split_top_level(&input[open + 1..close], ',')
+            .into_iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(|p| Self::parse_type(p, depth - 1))
+            .collect::<Result<Vec<TypeNode>, ParseError>>()?;
+        let rest = input[close + 1..].trim();
+        let return_type = match rest.strip_prefix("->") {
+            Some(return_str) => Some(Box::new(Self::parse_type(return_str.trim(), depth - 1)?)),
+            None if rest.is_empty() => None,
+            None => return Err("Invalid closure bound".into()),
+        };
+        Ok(Bound::Closure {
+            name: input[..open].trim().to_string(),
+            params,
+            return_type,
+        })
+    }
+
+    /// Parses the predicates of a `where` clause, without the keyword.
+    fn parse_where_clause(input: &str, depth: usize) -> Result<Vec<WherePredicate>, ParseError> {
+        split_top_level(input, ',')
+            .into_iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|predicate| {
+                // Predicates may span several lines
+                let predicate = predicate.split_whitespace().collect::<Vec<_>>().join(" ");
+                let colon =
+                    bound_colon(&predicate).ok_or("Where predicate is missing its bounds")?;
+                let bounds = Self::parse_bounds(&predicate[colon + 1..], depth)?;
+                Ok(WherePredicate {
+                    subject: Self::parse_type(predicate[..colon].trim(), depth)?,
+                    bounds,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_fields(input: &str, depth: usize) -> Result<Vec<FieldNode>, ParseError> {
+        split_top_level(input, ',')
+            .into_iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|field_str| {
+                let (attributes, field_str) = Self::parse_member_attributes(field_str)?;
+                let (visibility, field_str) = Self::parse_visibility(field_str)?;
+                let (name, field_type) = field_str.split_once(':').ok_or("Invalid field format")?;
+                Ok(FieldNode {
+                    visibility,
+                    name: name.trim().to_string(),
+                    field_type: Box::new(Self::parse_type(field_type.trim(), depth)?),
+                    attributes,
+                })
+            })
+            .collect()
+    }
+
+    /// Splits the leading `#[...]` attributes off a field or variant.
+    fn parse_member_attributes(input: &str) -> Result<(Vec<Attribute>, &str), ParseError> {
+        let mut attributes = Vec::new();
+        let mut rest = input.trim_start();
+        while let Some(attr) = rest.strip_prefix('#') {
+            let attr = attr.trim_start();
+            if !attr.starts_with('[') {
+                return Err("Invalid attribute".into());
             }
-            AstNode::Struct(struct_node) => {
-                println!("{}- Struct: {}", prefix, struct_node.name);
-                let len = struct_node.fields.len();
-                for (i, field) in struct_node.fields.iter().enumerate() {
-                    let is_last = i == len - 1;
-                    let branch = if is_last { "└──" } else { "├──" };
-                    let new_prefix = format!("{}{} ", prefix, branch);
-                    field.display_tree_internal(&new_prefix, is_last);
+            let close = find_closing(attr).ok_or("Unclosed attribute")?;
+            attributes.push(Self::parse_attribute(&attr[1..close])?);
+            rest = attr[close + 1..].trim_start();
+        }
+        Ok((attributes, rest))
+    }
+
+    fn parse_method(input: &str, depth: usize) -> Result<MethodNode, ParseError> {
+        let input = input.trim();
+        let (visibility, qualifiers) = Self::parse_visibility(input)?;
+        let is_async = qualifiers
+            .split_whitespace()
+            .take_while(|word| *word != "fn")
+            .any(|word| word == "async");
+        let (name, generics, rest) = Self::parse_item_name(input, "fn", depth)?;
+        if !rest.starts_with('(') {
+            return Err("Invalid method format".into());
+        }
+        let close = find_closing(rest).ok_or("Invalid method format")?;
+
+        let params = Self::parse_params(&rest[1..close], depth)?;
+
+        // Only an arrow directly after the parameter list introduces the
+        // return type; arrows inside parameter types belong to those types.
+        let after_params = &rest[close + 1..];
+        let (after_params, has_body) = match after_params.find('{') {
+            Some(body) => (&after_params[..body], true),
+            None => (after_params, false),
+        };
+        let after_params = after_params.trim().trim_end_matches(';').trim();
+        let (after_params, where_clause) = split_where_clause(after_params);
+        let return_type = match after_params.strip_prefix("->") {
+            Some(return_str) => Some(Box::new(Self::parse_type(return_str.trim(), depth)?)),
+            None => None,
+        };
+        let where_clause = Self::parse_where_clause(where_clause, depth)?;
+
+        Ok(MethodNode {
+            visibility,
+            name,
+            generics,
+            params,
+            return_type,
+            where_clause,
+            is_async,
+            has_body,
+        })
+    }
+
+    fn parse_params(input: &str, depth: usize) -> Result<Vec<ParamNode>, ParseError> {
+        if input.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        split_top_level(input, ',')
+            .into_iter()
+            .map(|param| param.trim())
+            .filter(|param| !param.is_empty())
+            .map(|param| {
+                // Shorthand receivers, such as `&'a mut self`, get a
+                // placeholder `self` type
+                if is_receiver(param) {
+                    let name = receiver_name(param);
+                    let placeholder = TypeNode::Simple("self".to_string());
+                    let param_type = match split_reference(&name) {
+                        Some((lifetime, mutable, _)) => TypeNode::Reference {
+                            lifetime: lifetime.map(str::to_string),
+                            mutable,
+                            inner: Box::new(placeholder),
+                        },
+                        None => placeholder,
+                    };
+                    Ok(ParamNode {
+                        name,
+                        param_type: Box::new(param_type),
+                    })
+                } else {
+                    let (name, param_type) =
+                        param.split_once(':').ok_or("Invalid parameter format")?;
+                    if !is_param_pattern(name) {
+                        return Err("Invalid parameter format".into());
+                    }
+                    Ok(ParamNode {
+                        name: name.trim().to_string(),
+                        param_type: Box::new(Self::parse_type(param_type.trim(), depth)?),
+                    })
+                }
+            })
+            .collect()
+    }
+
+    fn parse_type(input: &str, depth: usize) -> Result<TypeNode, ParseError> {
+        if depth == 0 {
+            return Err(ParseError::RecursionLimit);
+        }
+        if input.starts_with('&') {
+            let Some((lifetime, mutable, inner)) = split_reference(input) else {
+                return Err(format!("Invalid reference type `{}`", input).into());
+            };
+            Ok(TypeNode::Reference {
+                lifetime: lifetime.map(str::to_string),
+                mutable,
+                inner: Box::new(Self::parse_type(inner, depth - 1)?),
+            })
+        } else if input.starts_with('*') {
+            let Some((mutable, inner)) = split_raw_pointer(input) else {
+                return Err(format!("Invalid raw pointer type `{}`", input).into());
+            };
+            Ok(TypeNode::RawPointer {
+                mutable,
+                inner: Box::new(Self::parse_type(inner, depth - 1)?),
+            })
+        } else if let Some(bounds) = input.strip_prefix("impl ") {
+            Ok(TypeNode::ImplTrait(Self::parse_bounds(bounds, depth - 1)?))
+        } else if let Some(bounds) = input.strip_prefix("dyn ") {
+            Ok(TypeNode::DynTrait(Self::parse_bounds(bounds, depth - 1)?))
+        } else if input.starts_with('(') && find_closing(input) == Some(input.len() - 1) {
+            let inner = &input[1..input.len() - 1];
+            let elements: Vec<&str> = split_top_level(inner, ',')
+                .into_iter()
+                .map(|s| s.trim())
+                .collect();
+            match elements.as_slice() {
+                // `()` is the unit type and `(T)` merely groups `T`
+                [""] => Ok(TypeNode::Tuple(Vec::new())),
+                [single] => Self::parse_type(single, depth - 1),
+                _ => {
+                    let elements = elements
+                        .iter()
+                        .filter(|s| !s.is_empty())
+                        .map(|s| Self::parse_type(s, depth - 1))
+                        .collect::<Result<Vec<TypeNode>, ParseError>>()?;
+                    Ok(TypeNode::Tuple(elements))
                 }
             }
-            AstNode::Enum(enum_node) => {
-                println!("{}- Enum: {}", prefix, enum_node.name);
-                let len = enum_node.variants.len();
-                for (i, variant) in enum_node.variants.iter().enumerate() {
-                    let is_last = i == len - 1;
-                    let branch = if is_last { "└──" } else { "├──" };
-                    let new_prefix = format!("{}{} ", prefix, branch);
-                    variant.display_tree_internal(&new_prefix, is_last);
+        } else if input.starts_with('[') && input.ends_with(']') {
+            // Arrays are the `[]` generic with their length as a second argument
+            let (element, len) = match split_top_level(&input[1..input.len() - 1], ';')[..] {
+                [element] => (element, None),
+                [element, len] => (element, Some(len.trim())),
+                _ => return Err(format!("Invalid array type `{}`", input).into()),
+            };
+            let mut args = vec![Self::parse_type(element.trim(), depth - 1)?];
+            args.extend(len.map(|len| TypeNode::Simple(len.to_string())));
+            Ok(TypeNode::Generic {
+                name: "[]".to_string(),
+                args,
+            })
+        } else if let Some(open) = input
+            .find('<')
+            .filter(|&open| find_closing(&input[open..]) == Some(input.len() - 1 - open))
+        {
+            let name = input[..open].trim().to_string();
+            let args: Result<Vec<TypeNode>, ParseError> =
+                split_top_level(&input[open + 1..input.len() - 1], ',')
+                    .into_iter()
+                    .map(|arg| arg.trim())
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| Self::parse_type(arg, depth - 1))
+                    .collect();
+            Ok(TypeNode::Generic { name, args: args? })
+        } else {
+            Ok(TypeNode::Simple(input.to_string()))
+        }
+    }
+
+    fn parse_tuple_variant(input: &str, depth: usize) -> Result<AstNode, ParseError> {
+        let fields: Vec<FieldNode> = split_top_level(input, ',')
+            .into_iter()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .enumerate()
+            .map(|(i, s)| -> Result<FieldNode, ParseError> {
+                let (attributes, s) = Self::parse_member_attributes(s)?;
+                Ok(FieldNode {
+                    visibility: Visibility::Private,
+                    name: format!("{}", i),
+                    field_type: Box::new(Self::parse_type(s, depth)?),
+                    attributes,
+                })
+            })
+            .collect::<Result<Vec<FieldNode>, ParseError>>()?;
+
+        Ok(AstNode::Struct(StructNode {
+            name: "".to_string(),
+            fields,
+            ..Default::default()
+        }))
+    }
+}
+
+/// Splits an item header at its `where` keyword, returning the part before it
+/// and the predicates after it. The predicates are empty without a clause.
+fn split_where_clause(header: &str) -> (&str, &str) {
+    let header = header.trim();
+    header
+        .match_indices("where")
+        .find(|(i, _)| {
+            (*i == 0 || header[..*i].ends_with(char::is_whitespace))
+                && header[i + "where".len()..].starts_with(char::is_whitespace)
+        })
+        .map_or((header, ""), |(i, _)| {
+            (header[..i].trim_end(), header[i + "where".len()..].trim())
+        })
+}
+
+/// Splits a generic parameter at the `=` of its default, skipping any in
+/// bounds such as `Iterator<Item = u8>`, returning the trimmed default.
+fn split_default(param: &str) -> Result<(&str, Option<&str>), ParseError> {
+    match split_top_level(param, '=')[..] {
+        [param] => Ok((param, None)),
+        [param, default] if !default.trim().is_empty() => Ok((param, Some(default.trim()))),
+        _ => Err(format!("Invalid generic parameter `{}`", param).into()),
+    }
+}
+
+/// Whether a parameter is a shorthand receiver such as `self` or
+/// `&'a mut self`.
+fn is_receiver(param: &str) -> bool {
+    !param.contains(':') && param.ends_with("self")
+}
+
+/// Normalizes the whitespace of a shorthand receiver, so that `& mut  self`
+/// becomes `&mut self`.
+fn receiver_name(param: &str) -> String {
+    let name = param.split_whitespace().collect::<Vec<_>>().join(" ");
+    name.replacen("& ", "&", 1)
+}
+
+/// Whether the text before the `:` of a parameter is a pattern. Only binding
+/// modes can precede an identifier pattern, so `&self data` is a missing
+/// comma.
+fn is_param_pattern(name: &str) -> bool {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    name.trim_start().starts_with(['(', '['])
+        || words.len() <= 1
+        || words[..words.len() - 1]
+            .iter()
+            .all(|w| matches!(*w, "mut" | "ref" | "&" | "&mut"))
+}
+
+/// Finds the colon that separates a bounded type from its bounds, skipping
+/// the `::` of paths and anything nested in brackets.
+fn bound_colon(predicate: &str) -> Option<usize> {
+    let bytes = predicate.as_bytes();
+    let mut depth = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'<' | b'(' | b'[' => depth += 1,
+            b'>' | b')' | b']' => depth = depth.saturating_sub(1),
+            b':' if depth == 0 => {
+                let in_path = bytes.get(i + 1) == Some(&b':') || (i > 0 && bytes[i - 1] == b':');
+                if !in_path {
+                    return Some(i);
                 }
             }
+            _ => {}
         }
     }
+    None
 }
 
-impl MethodNode {
-    fn display_tree_internal(&self, prefix: &str, is_last: bool) {
-        let _ = is_last;
-        println!("{}Method: {}", prefix, self.name);
-        let len = self.params.len();
-        for (i, param) in self.params.iter().enumerate() {
-            let is_last_param = i == len - 1;
-            let branch = if is_last_param {
-                "└──"
+/// Returns the input following the first occurrence of `keyword` as a whole
+/// token after the visibility, so that a path such as `pub(in crate::typed)`
+/// is not mistaken for the keyword.
+fn after_keyword<'a>(input: &'a str, keyword: &str) -> Result<&'a str, ParseError> {
+    let (_, rest) = Parser::parse_visibility(input)?;
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut offset = 0;
+    while let Some(pos) = rest[offset..].find(keyword) {
+        let start = offset + pos;
+        let end = start + keyword.len();
+        if rest[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !is_ident(c))
+            && rest[end..].chars().next().is_none_or(|c| !is_ident(c))
+        {
+            return Ok(rest[end..].trim_start());
+        }
+        offset = end;
+    }
+    Err(format!("Invalid {} definition", keyword).into())
+}
+
+/// Splits an enum variant into its name, its data including the surrounding
+/// delimiters for tuple and struct variants, and its explicit discriminant.
+fn split_variant(variant: &str) -> Result<(&str, Option<&str>, Option<&str>), ParseError> {
+    let invalid = || format!("Invalid enum variant `{}`", variant).into();
+    let ident = variant.strip_prefix("r#").unwrap_or(variant);
+    let ident_len = ident
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(ident.len());
+    if ident_len == 0 || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let (name, rest) = variant.split_at(variant.len() - ident.len() + ident_len);
+    let rest = rest.trim_start();
+    let (data, rest) = match rest.chars().next() {
+        Some(open @ ('(' | '{')) => {
+            let close = find_closing(rest).ok_or_else(invalid)?;
+            if !rest[close..].starts_with(if open == '(' { ')' } else { '}' }) {
+                return Err(invalid());
+            }
+            (Some(&rest[..=close]), rest[close + 1..].trim_start())
+        }
+        _ => (None, rest),
+    };
+    match rest.strip_prefix('=').map(str::trim) {
+        None if rest.is_empty() => Ok((name, data, None)),
+        Some(discriminant) if !discriminant.is_empty() => Ok((name, data, Some(discriminant))),
+        _ => Err(invalid()),
+    }
+}
+
+/// Splits a reference type such as `&'a mut T` into its lifetime, whether it
+/// is mutable and its referent type. Only the first `&` is split off, so
+/// `&&T` leaves `&T`.
+fn split_reference(input: &str) -> Option<(Option<&str>, bool, &str)> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut rest = input.strip_prefix('&')?.trim_start();
+    let lifetime = match rest.strip_prefix('\'') {
+        Some(name) => {
+            let len = name.find(|c: char| !is_ident(c)).unwrap_or(name.len());
+            if len == 0 {
+                return None;
+            }
+            let (lifetime, after) = rest.split_at(len + 1);
+            rest = after.trim_start();
+            Some(lifetime)
+        }
+        None => None,
+    };
+    let mutable = match rest.strip_prefix("mut") {
+        Some(after) if !after.starts_with(is_ident) => {
+            rest = after.trim_start();
+            true
+        }
+        _ => false,
+    };
+    (!rest.is_empty()).then_some((lifetime, mutable, rest))
+}
+
+/// Splits a raw pointer type such as `*mut u8` into whether it is mutable
+/// and its pointee type.
+fn split_raw_pointer(input: &str) -> Option<(bool, &str)> {
+    let rest = input.strip_prefix('*')?.trim_start();
+    [("const", false), ("mut", true)]
+        .into_iter()
+        .find_map(|(qualifier, mutable)| {
+            let inner = rest.strip_prefix(qualifier)?;
+            inner
+                .starts_with(|c: char| c.is_whitespace() || c == '(' || c == '[')
+                .then(|| (mutable, inner.trim_start()))
+        })
+}
+
+/// Returns the byte index of the delimiter closing the one `input` starts
+/// with, accounting for nested `<>`, `()`, `[]` and `{}`.
+fn find_closing(input: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut prev = ' ';
+    for (i, c) in input.char_indices() {
+        match c {
+            '>' if prev == '-' => {}
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' | ')' | ']' | '}' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    None
+}
+
+/// Returns the length of the string literal, char literal or comment at the
+/// start of `input`, if there is one. Byte and raw strings count as literals;
+/// lifetimes and raw identifiers like `r#type` do not.
+fn literal_len(input: &str) -> Option<usize> {
+    let bytes = input.as_bytes();
+    match *bytes.first()? {
+        b'r' | b'b' => {
+            let rest = input.strip_prefix('b').unwrap_or(input);
+            let prefix = input.len() - rest.len();
+            match rest.strip_prefix('r') {
+                Some(raw) => {
+                    // A raw string ends at a quote followed by as many `#`s
+                    // as it opened with, so inner quotes need no escaping
+                    let hashes = raw.len() - raw.trim_start_matches('#').len();
+                    if raw.as_bytes().get(hashes) != Some(&b'"') {
+                        return None;
+                    }
+                    let body_start = prefix + hashes + 2;
+                    let terminator = format!("\"{}", "#".repeat(hashes));
+                    Some(
+                        input[body_start..]
+                            .find(&terminator)
+                            .map_or(input.len(), |i| body_start + i + terminator.len()),
+                    )
+                }
+                None if prefix == 1 && rest.starts_with(['"', '\'']) => {
+                    literal_len(rest).map(|len| len + 1)
+                }
+                None => None,
+            }
+        }
+        b'"' => {
+            let mut i = 1;
+            while i < bytes.len() {
+                match bytes[i] {
+                    b'\\' => i += 2,
+                    b'"' => return Some(i + 1),
+                    _ => i += 1,
+                }
+            }
+            Some(bytes.len())
+        }
+        b'\'' => {
+            if bytes.get(1) == Some(&b'\\') {
+                input[2..].find('\'').map(|i| i + 3)
             } else {
-                "├──"
-            };
-            let param_prefix = format!("{}{} ", prefix, branch);
-            param.display_tree_internal(&param_prefix, is_last_param);
+                let end = 1 + input[1..].chars().next()?.len_utf8();
+                (bytes.get(end) == Some(&b'\'')).then_some(end + 1)
+            }
         }
-        if let Some(return_type) = &self.return_type {
-            let branch = if len == 0 { "└──" } else { "├──" };
-            let return_prefix = format!("{}{} ", prefix, branch);
-            println!("{}Return Type: {}", return_prefix, return_type.display());
+        b'/' if bytes.get(1) == Some(&b'/') => Some(input.find('\n').unwrap_or(input.len())),
+        b'/' if bytes.get(1) == Some(&b'*') => {
+            Some(input[2..].find("*/").map_or(input.len(), |i| i + 4))
         }
+        _ => None,
     }
 }
 
-impl FieldNode {
-    fn display_tree_internal(&self, prefix: &str, _is_last: bool) {
-        println!(
-            "{}Field: {}: {}",
-            prefix,
-            self.name,
-            self.field_type.display()
+/// Skips leading whitespace and comments, collecting inner (`//!`, `/*! */`)
+/// or outer (`///`, `/** */`) doc comments as written into `docs`. When
+/// collecting inner docs, stops at the first outer doc comment, which belongs
+/// to the item that follows.
+fn take_docs<'a>(mut input: &'a str, inner: bool, docs: &mut Vec<&'a str>) -> &'a str {
+    loop {
+        input = input.trim_start();
+        if let Some(len) = doc_comment(input, inner) {
+            docs.push(&input[..len]);
+            input = &input[len..];
+        } else if inner && doc_comment(input, false).is_some() {
+            return input;
+        } else if input.starts_with("//") || input.starts_with("/*") {
+            input = &input[literal_len(input).unwrap_or(input.len())..];
+        } else {
+            return input;
+        }
+    }
+}
+
+/// Recognizes an inner or outer doc comment at the start of `input`,
+/// returning its length. `////` and `/***` open ordinary comments.
+fn doc_comment(input: &str, inner: bool) -> Option<usize> {
+    let (line_prefix, block_prefix) = if inner {
+        ("//!", "/*!")
+    } else {
+        ("///", "/**")
+    };
+
+    let ordinary = if let Some(text) = input.strip_prefix(line_prefix) {
+        text.starts_with('/')
+    } else if let Some(text) = input.strip_prefix(block_prefix) {
+        // `/**/` is an empty ordinary comment
+        text.starts_with('*') || text.starts_with('/')
+    } else {
+        return None;
+    };
+    if !inner && ordinary {
+        return None;
+    }
+    literal_len(input)
+}
+
+/// Returns the text of a doc comment recognized by [`doc_comment`].
+fn doc_text(comment: &str) -> String {
+    match comment.get(..3) {
+        Some("/**" | "/*!") => normalize_block_doc(comment[3..].trim_end_matches("*/")),
+        _ => {
+            let text = comment.get(3..).unwrap_or("");
+            text.strip_prefix(' ')
+                .unwrap_or(text)
+                .trim_end()
+                .to_string()
+        }
+    }
+}
+
+/// Normalizes the content of a block doc comment. A leading `*` gutter on
+/// the continuation lines, or otherwise their common indentation, is
+/// removed, as are blank first and last lines.
+fn normalize_block_doc(content: &str) -> String {
+    let mut lines = content.lines();
+    let first = lines.next().unwrap_or("").trim();
+    let rest: Vec<&str> = lines.collect();
+
+    let non_blank = || rest.iter().filter(|l| !l.trim().is_empty());
+    let has_gutter = non_blank().all(|l| l.trim_start().starts_with('*'));
+    // Counted in characters, as indentation may use multi-byte whitespace
+    let indent = non_blank()
+        .map(|l| l.chars().take_while(|c| c.is_whitespace()).count())
+        .min()
+        .unwrap_or(0);
+
+    let mut result = vec![first];
+    for line in rest {
+        let line = if has_gutter {
+            let line = line.trim_start();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.strip_prefix(' ').unwrap_or(line)
+        } else if line.trim().is_empty() {
+            ""
+        } else {
+            line.char_indices()
+                .nth(indent)
+                .map_or("", |(i, _)| &line[i..])
+        };
+        result.push(line.trim_end());
+    }
+
+    while result.first() == Some(&"") {
+        result.remove(0);
+    }
+    while result.last() == Some(&"") {
+        result.pop();
+    }
+    result.join("\n")
+}
+
+/// Joins the text of collected doc comments, or returns `None` if there are
+/// none.
+fn join_docs(docs: &[&str]) -> Option<String> {
+    if docs.is_empty() {
+        None
+    } else {
+        Some(
+            docs.iter()
+                .map(|doc| doc_text(doc))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        )
+    }
+}
+
+/// Splits source text into its top-level items. An item ends at a `;` outside
+/// any brackets or at the `}` closing its outermost brace block.
+fn split_items(input: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = None;
+    let mut i = 0;
+    while i < input.len() {
+        let rest = &input[i..];
+        if let Some(len) = literal_len(rest) {
+            // Outer doc comments belong to the item that follows them
+            if start.is_none() && doc_comment(rest, false).is_some() {
+                start = Some(i);
+            }
+            i += len;
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        let item_start = match start {
+            Some(item_start) => item_start,
+            None if c.is_whitespace() || c == ';' => {
+                i += c.len_utf8();
+                continue;
+            }
+            None => {
+                start = Some(i);
+                i
+            }
+        };
+
+        let mut end = None;
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    // Keep the `;` ending items like `const X: S = S {};`
+                    let after = &input[i + 1..];
+                    end = Some(match after.trim_start().strip_prefix(';') {
+                        Some(tail) => input.len() - tail.len(),
+                        None => i + 1,
+                    });
+                }
+            }
+            ';' if depth == 0 => end = Some(i + 1),
+            _ => {}
+        }
+
+        match end {
+            Some(end) => {
+                items.push(input[item_start..end].trim());
+                start = None;
+                i = end;
+            }
+            None => i += c.len_utf8(),
+        }
+    }
+    if let Some(item_start) = start {
+        items.push(input[item_start..].trim());
+    }
+    items
+}
+
+/// Splits `input` on `separator`, ignoring separators nested inside
+/// `<>`, `()`, `[]` or `{}`.
+fn split_top_level(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut prev = ' ';
+    for (i, c) in input.char_indices() {
+        match c {
+            // The `>` of a `->` arrow does not close a generic
+            '>' if prev == '-' => {}
+            '<' | '(' | '[' | '{' => depth += 1,
+            '>' | ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&input[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    parts.push(&input[start..]);
+    parts
+}
+
+impl FromStr for AstNode {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Parser::parse(s)
+    }
+}
+
+impl TryFrom<&str> for AstNode {
+    type Error = ParseError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Parser::parse(s)
+    }
+}
+
+impl TryFrom<String> for AstNode {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Parser::parse(&s)
+    }
+}
+
+// Tree Display Implementation with Recursive Traversal
+impl AstNode {
+    pub fn display_tree(&self) {
+        self.write_tree_to(&mut std::io::stdout())
+            .expect("failed printing to stdout");
+    }
+
+    /// Writes the tree printed by [`AstNode::display_tree`] to `out`. With the
+    /// `ansi` feature enabled, node labels such as `Trait:` and `Method:` are
+    /// colored for terminals.
+    pub fn write_tree_to(&self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let tree = self.tree_string();
+        #[cfg(feature = "ansi")]
+        let tree = colorize_tree(&tree);
+        out.write_all(tree.as_bytes())
+    }
+
+    /// Renders the tree printed by [`AstNode::display_tree`] into a string,
+    /// without colors even when the `ansi` feature is enabled.
+    pub fn tree_string(&self) -> String {
+        let mut out = String::new();
+        self.display_tree_internal(&mut out, "")
+            .expect("writing to a String cannot fail");
+        out
+    }
+
+    fn display_tree_internal(&self, out: &mut String, prefix: &str) -> fmt::Result {
+        match self {
+            AstNode::Trait(trait_node) => {
+                writeln!(
+                    out,
+                    "{}- Trait: {}{}{}{}",
+                    prefix,
+                    trait_node.name,
+                    display_generics(&trait_node.generics),
+                    display_supertraits(&trait_node.supertraits),
+                    display_where_clause(&trait_node.where_clause)
+                )?;
+                let len = trait_node.methods.len();
+                for (i, method) in trait_node.methods.iter().enumerate() {
+                    let is_last = i == len - 1;
+                    let branch = if is_last { "└──" } else { "├──" };
+                    let new_prefix = format!("{}{} ", prefix, branch);
+                    method.display_tree_internal(out, &new_prefix, is_last)?;
+                }
+            }
+            AstNode::Struct(struct_node) => {
+                writeln!(
+                    out,
+                    "{}- Struct: {}{}{}",
+                    prefix,
+                    struct_node.name,
+                    display_generics(&struct_node.generics),
+                    display_where_clause(&struct_node.where_clause)
+                )?;
+                let len = struct_node.fields.len() + struct_node.inherent_methods.len();
+                for (i, field) in struct_node.fields.iter().enumerate() {
+                    let is_last = i == len - 1;
+                    let branch = if is_last { "└──" } else { "├──" };
+                    let new_prefix = format!("{}{} ", prefix, branch);
+                    field.display_tree_internal(out, &new_prefix, is_last)?;
+                }
+                display_inherent_methods(out, prefix, &struct_node.inherent_methods)?;
+            }
+            AstNode::Enum(enum_node) => {
+                writeln!(
+                    out,
+                    "{}- Enum: {}{}{}",
+                    prefix,
+                    enum_node.name,
+                    display_generics(&enum_node.generics),
+                    display_where_clause(&enum_node.where_clause)
+                )?;
+                let len = enum_node.variants.len() + enum_node.inherent_methods.len();
+                for (i, variant) in enum_node.variants.iter().enumerate() {
+                    let is_last = i == len - 1;
+                    let branch = if is_last { "└──" } else { "├──" };
+                    let new_prefix = format!("{}{} ", prefix, branch);
+                    variant.display_tree_internal(out, &new_prefix, is_last)?;
+                }
+                display_inherent_methods(out, prefix, &enum_node.inherent_methods)?;
+            }
+            AstNode::TypeAlias(alias_node) => {
+                writeln!(
+                    out,
+                    "{}- Type Alias: {}{} = {}",
+                    prefix,
+                    alias_node.name,
+                    display_generics(&alias_node.generics),
+                    alias_node.aliased_type.display()
+                )?;
+            }
+            AstNode::Const(const_node) => {
+                writeln!(
+                    out,
+                    "{}- Const: {}: {} = {}",
+                    prefix,
+                    const_node.name,
+                    const_node.const_type.display(),
+                    const_node.value
+                )?;
+            }
+            AstNode::Static(static_node) => {
+                writeln!(
+                    out,
+                    "{}- Static: {}{}: {} = {}",
+                    prefix,
+                    if static_node.is_mut { "mut " } else { "" },
+                    static_node.name,
+                    static_node.static_type.display(),
+                    static_node.value
+                )?;
+            }
+            AstNode::Use(use_node) => {
+                writeln!(
+                    out,
+                    "{}- Use: {}{}",
+                    prefix,
+                    visibility_prefix(&use_node.visibility),
+                    use_node.path
+                )?;
+            }
+            AstNode::Impl(impl_node) => {
+                let trait_part = display_impl_trait(impl_node);
+                writeln!(
+                    out,
+                    "{}- Impl{}: {}{}{}",
+                    prefix,
+                    display_generics(&impl_node.generics),
+                    trait_part,
+                    impl_node.self_type.display(),
+                    display_where_clause(&impl_node.where_clause)
+                )?;
+                let len = impl_node.items.len();
+                for (i, item) in impl_node.items.iter().enumerate() {
+                    let is_last = i == len - 1;
+                    let branch = if is_last { "└──" } else { "├──" };
+                    let new_prefix = format!("{}{} ", prefix, branch);
+                    match item {
+                        ImplItem::Method(method) => {
+                            method.display_tree_internal(out, &new_prefix, is_last)?;
+                        }
+                        ImplItem::Const(const_node) => writeln!(
+                            out,
+                            "{}Const: {}: {} = {}",
+                            new_prefix,
+                            const_node.name,
+                            const_node.const_type.display(),
+                            const_node.value
+                        )?,
+                        ImplItem::Type(alias_node) => writeln!(
+                            out,
+                            "{}Type: {} = {}",
+                            new_prefix,
+                            alias_node.name,
+                            alias_node.aliased_type.display()
+                        )?,
+                    }
+                }
+            }
+            AstNode::Module(module_node) => {
+                writeln!(out, "{}- Module: {}", prefix, module_node.name)?;
+                let item_prefix = format!("{}    ", prefix);
+                for item in &module_node.items {
+                    item.display_tree_internal(out, &item_prefix)?;
+                }
+            }
+            AstNode::Extern(extern_block) => {
+                writeln!(out, "{}- Extern: \"{}\"", prefix, extern_block.abi)?;
+                let len = extern_block.functions.len();
+                for (i, function) in extern_block.functions.iter().enumerate() {
+                    let is_last = i == len - 1;
+                    let branch = if is_last { "└──" } else { "├──" };
+                    let new_prefix = format!("{}{} ", prefix, branch);
+                    function
+                        .signature
+                        .display_tree_internal(out, &new_prefix, is_last)?;
+                }
+            }
+            AstNode::Function(function) => {
+                writeln!(out, "{}- Function: extern \"{}\"", prefix, function.abi)?;
+                let new_prefix = format!("{}└── ", prefix);
+                function
+                    .signature
+                    .display_tree_internal(out, &new_prefix, true)?;
+            }
+            AstNode::MacroDef(macro_def) => {
+                writeln!(out, "{}- Macro: {}", prefix, macro_def.name)?;
+            }
+            AstNode::Opaque(source) => {
+                writeln!(out, "{}- Opaque: {}", prefix, opaque_label(source))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl MethodNode {
+    fn display_tree_internal(&self, out: &mut String, prefix: &str, is_last: bool) -> fmt::Result {
+        let _ = is_last;
+        writeln!(
+            out,
+            "{}Method: {}{}",
+            prefix,
+            self.name,
+            display_generics(&self.generics)
+        )?;
+        let len = self.params.len();
+        for (i, param) in self.params.iter().enumerate() {
+            let is_last_param = i == len - 1;
+            let branch = if is_last_param {
+                "└──"
+            } else {
+                "├──"
+            };
+            let param_prefix = format!("{}{} ", prefix, branch);
+            param.display_tree_internal(out, &param_prefix, is_last_param)?;
+        }
+        if let Some(return_type) = &self.return_type {
+            let branch = if len == 0 { "└──" } else { "├──" };
+            let return_prefix = format!("{}{} ", prefix, branch);
+            writeln!(
+                out,
+                "{}Return Type: {}",
+                return_prefix,
+                return_type.display()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl MethodNode {
+    /// Renders the method as a one-line signature without a body, such as
+    /// `fn process(&self, input: &str) -> String`.
+    pub fn signature(&self) -> String {
+        let params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| {
+                if is_shorthand_receiver(p) {
+                    p.name.clone()
+                } else {
+                    format!("{}: {}", p.name, p.param_type.display())
+                }
+            })
+            .collect();
+        let return_type = match &self.return_type {
+            Some(ty) => format!(" -> {}", ty.display()),
+            None => String::new(),
+        };
+        format!(
+            "{}{}fn {}{}({}){}{}",
+            visibility_prefix(&self.visibility),
+            if self.is_async { "async " } else { "" },
+            self.name,
+            display_generics(&self.generics),
+            params.join(", "),
+            return_type,
+            display_where_clause(&self.where_clause)
+        )
+    }
+}
+
+impl FunctionNode {
+    /// Renders the function as a one-line signature without a body, such as
+    /// `pub extern "C" fn on_event(code: i32)`.
+    pub fn to_signature_string(&self) -> String {
+        let signature = self.signature.signature();
+        // The ABI goes right before `fn`, after the visibility and `async`
+        let at = visibility_prefix(&self.signature.visibility).len()
+            + if self.signature.is_async {
+                "async ".len()
+            } else {
+                0
+            };
+        format!(
+            "{}extern \"{}\" {}",
+            &signature[..at],
+            self.abi,
+            &signature[at..]
+        )
+    }
+}
+
+impl FieldNode {
+    /// Renders the field as it is written in a struct body, such as
+    /// `x: f64` or `pub name: String`.
+    pub fn to_source_string(&self) -> String {
+        format!(
+            "{}{}: {}",
+            visibility_prefix(&self.visibility),
+            self.name,
+            self.field_type.display()
+        )
+    }
+
+    fn display_tree_internal(&self, out: &mut String, prefix: &str, _is_last: bool) -> fmt::Result {
+        writeln!(out, "{}Field: {}", prefix, self.to_source_string())?;
+        Ok(())
+    }
+}
+
+impl VariantNode {
+    /// Renders the variant as it is written in an enum body, such as `Red`,
+    /// `Write(String)`, `Move { x: i32, y: i32 }` or `Code = 2`.
+    pub fn to_source_string(&self) -> String {
+        let data = match self.associated_data.as_deref() {
+            Some(AstNode::Struct(data)) if data.name.is_empty() => inline_fields(&data.fields),
+            _ => String::new(),
+        };
+        match &self.discriminant {
+            Some(discriminant) => format!("{}{} = {}", self.name, data, discriminant),
+            None => format!("{}{}", self.name, data),
+        }
+    }
+
+    fn display_tree_internal(&self, out: &mut String, prefix: &str, _is_last: bool) -> fmt::Result {
+        writeln!(out, "{}Variant: {}", prefix, self.to_source_string())?;
+        match self.associated_data.as_deref() {
+            // Tuple and struct variant data is parsed into an anonymous
+            // struct, which reads better inline than as a nested item.
+            Some(AstNode::Struct(data)) if data.name.is_empty() => {}
+            Some(associated_data) => {
+                associated_data.display_tree_internal(out, &format!("{}    ", prefix))?;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Renders the fields of variant data as `(A, B)` for tuple variants or
+/// `{ a: A, b: B }` for struct variants.
+fn inline_fields(fields: &[FieldNode]) -> String {
+    let is_tuple = fields
+        .iter()
+        .all(|f| f.name.chars().all(|c| c.is_ascii_digit()));
+    if is_tuple {
+        let types: Vec<String> = fields.iter().map(|f| f.field_type.display()).collect();
+        format!("({})", types.join(", "))
+    } else {
+        let fields: Vec<String> = fields.iter().map(|f| f.to_source_string()).collect();
+        format!(" {{ {} }}", fields.join(", "))
+    }
+}
+
+impl TypeNode {
+    fn display(&self) -> String {
+        match self {
+            TypeNode::Simple(name) => name.clone(),
+            TypeNode::Reference {
+                lifetime,
+                mutable,
+                inner,
+            } => {
+                let mut prefix = String::from("&");
+                if let Some(lifetime) = lifetime {
+                    prefix.push_str(lifetime);
+                    prefix.push(' ');
+                }
+                if *mutable {
+                    prefix.push_str("mut ");
+                }
+                match &**inner {
+                    // `&dyn A + B` is ambiguous, so multiple bounds are grouped
+                    TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds)
+                        if bounds.len() > 1 =>
+                    {
+                        format!("{}({})", prefix, inner.display())
+                    }
+                    _ => format!("{}{}", prefix, inner.display()),
+                }
+            }
+            TypeNode::RawPointer { mutable, inner } => {
+                let qualifier = if *mutable { "mut" } else { "const" };
+                match &**inner {
+                    TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds)
+                        if bounds.len() > 1 =>
+                    {
+                        format!("*{} ({})", qualifier, inner.display())
+                    }
+                    _ => format!("*{} {}", qualifier, inner.display()),
+                }
+            }
+            TypeNode::Generic { name, args } => match (name.as_str(), args.as_slice()) {
+                ("[]", [element]) => format!("[{}]", element.display()),
+                ("[]", [element, len]) => format!("[{}; {}]", element.display(), len.display()),
+                _ => {
+                    let args_display: Vec<String> = args.iter().map(|arg| arg.display()).collect();
+                    format!("{}<{}>", name, args_display.join(", "))
+                }
+            },
+            TypeNode::Tuple(elements) => match elements.as_slice() {
+                [single] => format!("({},)", single.display()),
+                _ => {
+                    let elements_display: Vec<String> =
+                        elements.iter().map(|e| e.display()).collect();
+                    format!("({})", elements_display.join(", "))
+                }
+            },
+            TypeNode::ImplTrait(bounds) => format!("impl {}", display_bounds(bounds)),
+            TypeNode::DynTrait(bounds) => format!("dyn {}", display_bounds(bounds)),
+        }
+    }
+}
+
+impl GenericParam {
+    fn display(&self) -> String {
+        match self {
+            GenericParam::Lifetime { name, bounds } => display_bounded(name, bounds),
+            GenericParam::Type {
+                name,
+                bounds,
+                default,
+            } => match default {
+                Some(default) => {
+                    format!("{} = {}", display_bounded(name, bounds), default.display())
+                }
+                None => display_bounded(name, bounds),
+            },
+            GenericParam::Const { name, ty } => format!("const {}: {}", name, ty.display()),
+        }
+    }
+}
+
+fn display_bounded(name: &str, bounds: &[Bound]) -> String {
+    if bounds.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}: {}", name, display_bounds(bounds))
+    }
+}
+
+impl std::fmt::Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Visibility::Private => Ok(()),
+            Visibility::Public => write!(f, "pub"),
+            Visibility::Crate => write!(f, "pub(crate)"),
+            Visibility::Super => write!(f, "pub(super)"),
+            Visibility::PubIn(path) => write!(f, "pub(in {})", path),
+        }
+    }
+}
+
+/// Renders a visibility followed by a space, or nothing for private items.
+fn visibility_prefix(visibility: &Visibility) -> String {
+    match visibility {
+        Visibility::Private => String::new(),
+        _ => format!("{} ", visibility),
+    }
+}
+
+/// The ANSI SGR codes of the node labels that begin tree lines. `Impl` is
+/// followed by the impl's generics rather than a colon.
+#[cfg(feature = "ansi")]
+const TREE_COLORS: &[(&str, &str)] = &[
+    ("Trait:", "36"),
+    ("Impl", "36"),
+    ("Extern:", "36"),
+    ("Function:", "36"),
+    ("Struct:", "34"),
+    ("Enum:", "34"),
+    ("Type Alias:", "34"),
+    ("Module:", "1"),
+    ("Const:", "35"),
+    ("Static:", "35"),
+    ("Use:", "35"),
+    ("Type:", "35"),
+    ("Macro:", "35"),
+    ("Opaque:", "35"),
+    ("Method:", "32"),
+    ("Field:", "33"),
+    ("Variant:", "33"),
+    ("Param:", "90"),
+    ("Return Type:", "90"),
+];
+
+/// Colors the label after the branch characters of each line of a tree.
+#[cfg(feature = "ansi")]
+fn colorize_tree(tree: &str) -> String {
+    let mut colored = String::with_capacity(tree.len());
+    for line in tree.split_inclusive('\n') {
+        let text = line.trim_start_matches([' ', '-', '│', '├', '└', '─']);
+        let (branch, text) = line.split_at(line.len() - text.len());
+        colored.push_str(branch);
+        match TREE_COLORS
+            .iter()
+            .find(|(label, _)| text.starts_with(label))
+        {
+            Some((label, code)) => {
+                write!(
+                    colored,
+                    "\x1b[{}m{}\x1b[0m{}",
+                    code,
+                    label,
+                    &text[label.len()..]
+                )
+                .expect("writing to a String cannot fail");
+            }
+            None => colored.push_str(text),
+        }
+    }
+    colored
+}
+
+/// Shortens the source text of an opaque item to its first line.
+fn opaque_label(source: &str) -> &str {
+    source.lines().next().unwrap_or("").trim()
+}
+
+/// Renders the supertrait bounds of a trait as `: A + B`, or nothing when
+/// there are none.
+fn display_supertraits(bounds: &[Bound]) -> String {
+    if bounds.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", display_bounds(bounds))
+    }
+}
+
+/// Renders the trait part of an impl header, such as `unsafe Send for ` or
+/// `!Sync for `, or nothing for an inherent impl.
+fn display_impl_trait(node: &ImplNode) -> String {
+    match &node.trait_type {
+        Some(trait_type) => format!(
+            "{}{}{} for ",
+            if node.is_unsafe { "unsafe " } else { "" },
+            if node.is_negative { "!" } else { "" },
+            trait_type.display()
+        ),
+        None => String::new(),
+    }
+}
+
+fn display_generics(generics: &[GenericParam]) -> String {
+    if generics.is_empty() {
+        return String::new();
+    }
+    let params: Vec<String> = generics.iter().map(|g| g.display()).collect();
+    format!("<{}>", params.join(", "))
+}
+
+/// Renders the methods merged into a struct or enum, which follow its fields
+/// or variants as the last children of the item.
+fn display_inherent_methods(out: &mut String, prefix: &str, methods: &[MethodNode]) -> fmt::Result {
+    for (i, method) in methods.iter().enumerate() {
+        let is_last = i == methods.len() - 1;
+        let branch = if is_last { "└──" } else { "├──" };
+        let new_prefix = format!("{}{} ", prefix, branch);
+        method.display_tree_internal(out, &new_prefix, is_last)?;
+    }
+    Ok(())
+}
+
+impl Bound {
+    fn display(&self) -> String {
+        match self {
+            Bound::Lifetime(name) => name.clone(),
+            Bound::Trait(ty) => ty.display(),
+            Bound::Negative(ty) => format!("!{}", ty.display()),
+            Bound::Closure {
+                name,
+                params,
+                return_type,
+            } => {
+                let params: Vec<String> = params.iter().map(|p| p.display()).collect();
+                match return_type {
+                    Some(ty) => format!("{}({}) -> {}", name, params.join(", "), ty.display()),
+                    None => format!("{}({})", name, params.join(", ")),
+                }
+            }
+        }
+    }
+}
+
+/// Joins bounds with ` + `. A closure bound with a return type is
+/// parenthesized when other bounds follow it, since the arrow would
+/// otherwise take in the rest of the list.
+fn display_bounds(bounds: &[Bound]) -> String {
+    let bounds: Vec<String> = bounds
+        .iter()
+        .enumerate()
+        .map(|(i, bound)| match bound {
+            Bound::Closure {
+                return_type: Some(_),
+                ..
+            } if i + 1 < bounds.len() => format!("({})", bound.display()),
+            _ => bound.display(),
+        })
+        .collect();
+    bounds.join(" + ")
+}
+
+/// Renders a `where` clause with a leading space, or nothing if it is empty.
+fn display_where_clause(predicates: &[WherePredicate]) -> String {
+    if predicates.is_empty() {
+        return String::new();
+    }
+    let predicates: Vec<String> = predicates
+        .iter()
+        .map(|p| format!("{}: {}", p.subject.display(), display_bounds(&p.bounds)))
+        .collect();
+    format!(" where {}", predicates.join(", "))
+}
+
+impl ParamNode {
+    fn display_tree_internal(&self, out: &mut String, prefix: &str, _is_last: bool) -> fmt::Result {
+        writeln!(
+            out,
+            "{}Param: {}: {}",
+            prefix,
+            self.name,
+            self.param_type.display()
+        )?;
+        Ok(())
+    }
+}
+
+// Compact Summaries
+/// The width [`AstNode::to_compact_string`] truncates summaries to.
+const COMPACT_WIDTH: usize = 80;
+
+impl AstNode {
+    /// Summarizes the item on one line, such as `struct Point { x: f64, y: f64 }`
+    /// or `trait Shape { fn area(&self) -> f64 }`, truncated with `...` to 80
+    /// characters. The item's visibility, attributes and doc comments are
+    /// left out.
+    pub fn to_compact_string(&self) -> String {
+        self.to_compact_string_with_width(COMPACT_WIDTH)
+    }
+
+    /// Like [`AstNode::to_compact_string`], truncated to `width` characters,
+    /// including the `...`.
+    pub fn to_compact_string_with_width(&self, width: usize) -> String {
+        let summary = self.compact_summary();
+        let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+        if summary.chars().count() <= width {
+            return summary;
+        }
+        let mut truncated: String = summary.chars().take(width.saturating_sub(3)).collect();
+        truncated.push_str(&"..."[..width.min(3)]);
+        truncated
+    }
+
+    fn compact_summary(&self) -> String {
+        let signatures = |methods: &[MethodNode]| -> String {
+            let signatures: Vec<String> = methods.iter().map(|m| m.signature()).collect();
+            format!(" {{ {} }}", signatures.join("; "))
+        };
+        match self {
+            AstNode::Trait(node) => format!(
+                "trait {}{}{}{}",
+                node.name,
+                display_generics(&node.generics),
+                display_supertraits(&node.supertraits),
+                signatures(&node.methods)
+            ),
+            AstNode::Struct(node) => {
+                let fields = if node.fields.is_empty() {
+                    String::new()
+                } else {
+                    inline_fields(&node.fields)
+                };
+                format!(
+                    "struct {}{}{}",
+                    node.name,
+                    display_generics(&node.generics),
+                    fields
+                )
+            }
+            AstNode::Enum(node) => {
+                let variants: Vec<String> =
+                    node.variants.iter().map(|v| v.to_source_string()).collect();
+                format!(
+                    "enum {}{} {{ {} }}",
+                    node.name,
+                    display_generics(&node.generics),
+                    variants.join(", ")
+                )
+            }
+            AstNode::TypeAlias(node) => {
+                format!(
+                    "type {}{} = {}",
+                    node.name,
+                    display_generics(&node.generics),
+                    node.aliased_type.display()
+                )
+            }
+            AstNode::Const(node) => format!(
+                "const {}: {} = {}",
+                node.name,
+                node.const_type.display(),
+                node.value
+            ),
+            AstNode::Static(node) => format!(
+                "static {}{}: {} = {}",
+                if node.is_mut { "mut " } else { "" },
+                node.name,
+                node.static_type.display(),
+                node.value
+            ),
+            AstNode::Module(node) => {
+                let items: Vec<String> = node.items.iter().map(|i| i.compact_summary()).collect();
+                format!("mod {} {{ {} }}", node.name, items.join("; "))
+            }
+            AstNode::Use(node) => format!("use {}", node.path),
+            AstNode::Impl(node) => {
+                let methods: Vec<MethodNode> = node
+                    .items
+                    .iter()
+                    .filter_map(|item| match item {
+                        ImplItem::Method(m) => Some(m.clone()),
+                        ImplItem::Const(_) | ImplItem::Type(_) => None,
+                    })
+                    .collect();
+                format!(
+                    "impl{} {}{}{}",
+                    display_generics(&node.generics),
+                    display_impl_trait(node),
+                    node.self_type.display(),
+                    signatures(&methods)
+                )
+            }
+            AstNode::Extern(node) => {
+                let functions: Vec<MethodNode> =
+                    node.functions.iter().map(|f| f.signature.clone()).collect();
+                format!("extern \"{}\"{}", node.abi, signatures(&functions))
+            }
+            AstNode::Function(node) => node.to_signature_string(),
+            AstNode::MacroDef(node) => format!("macro_rules! {} {{ ... }}", node.name),
+            AstNode::Opaque(source) => source.clone(),
+        }
+    }
+}
+
+// Structural Comparison Ignoring Item Names
+impl AstNode {
+    /// Returns `true` if both nodes have the same shape: the same kind and the
+    /// same number and types of fields, methods or variants in the same order.
+    /// The names of the items themselves and of their members are ignored.
+    pub fn structural_eq(&self, other: &AstNode) -> bool {
+        match (self, other) {
+            (AstNode::Trait(a), AstNode::Trait(b)) => a.structural_eq(b),
+            (AstNode::Struct(a), AstNode::Struct(b)) => a.structural_eq(b),
+            (AstNode::Enum(a), AstNode::Enum(b)) => a.structural_eq(b),
+            (AstNode::TypeAlias(a), AstNode::TypeAlias(b)) => {
+                a.generics.len() == b.generics.len()
+                    && a.aliased_type.structural_eq(&b.aliased_type)
+            }
+            (AstNode::Const(a), AstNode::Const(b)) => a.const_type.structural_eq(&b.const_type),
+            (AstNode::Static(a), AstNode::Static(b)) => {
+                a.is_mut == b.is_mut && a.static_type.structural_eq(&b.static_type)
+            }
+            (AstNode::Use(a), AstNode::Use(b)) => a.path == b.path,
+            (AstNode::Extern(a), AstNode::Extern(b)) => {
+                a.abi == b.abi
+                    && a.functions.len() == b.functions.len()
+                    && a.functions
+                        .iter()
+                        .zip(&b.functions)
+                        .all(|(a, b)| a.abi == b.abi && a.signature.structural_eq(&b.signature))
+            }
+            (AstNode::Function(a), AstNode::Function(b)) => {
+                a.abi == b.abi && a.signature.structural_eq(&b.signature)
+            }
+            (AstNode::MacroDef(a), AstNode::MacroDef(b)) => a.body == b.body,
+            (AstNode::Opaque(a), AstNode::Opaque(b)) => a == b,
+            (AstNode::Impl(a), AstNode::Impl(b)) => a.structural_eq(b),
+            (AstNode::Module(a), AstNode::Module(b)) => {
+                a.items.len() == b.items.len()
+                    && a.items
+                        .iter()
+                        .zip(&b.items)
+                        .all(|(a, b)| a.structural_eq(b))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl TraitNode {
+    pub fn structural_eq(&self, other: &TraitNode) -> bool {
+        self.methods.len() == other.methods.len()
+            && self
+                .methods
+                .iter()
+                .zip(&other.methods)
+                .all(|(a, b)| a.structural_eq(b))
+    }
+}
+
+impl StructNode {
+    pub fn structural_eq(&self, other: &StructNode) -> bool {
+        self.fields.len() == other.fields.len()
+            && self
+                .fields
+                .iter()
+                .zip(&other.fields)
+                .all(|(a, b)| a.structural_eq(b))
+    }
+}
+
+impl EnumNode {
+    pub fn structural_eq(&self, other: &EnumNode) -> bool {
+        self.variants.len() == other.variants.len()
+            && self
+                .variants
+                .iter()
+                .zip(&other.variants)
+                .all(|(a, b)| a.structural_eq(b))
+    }
+}
+
+impl ImplNode {
+    /// The implemented trait and self type are compared as types, since an
+    /// impl block has no name of its own.
+    pub fn structural_eq(&self, other: &ImplNode) -> bool {
+        let traits_eq = match (&self.trait_type, &other.trait_type) {
+            (Some(a), Some(b)) => a.structural_eq(b),
+            (None, None) => true,
+            _ => false,
+        };
+        traits_eq
+            && self.is_unsafe == other.is_unsafe
+            && self.is_negative == other.is_negative
+            && self.self_type.structural_eq(&other.self_type)
+            && self.items.len() == other.items.len()
+            && self
+                .items
+                .iter()
+                .zip(&other.items)
+                .all(|(a, b)| match (a, b) {
+                    (ImplItem::Method(a), ImplItem::Method(b)) => a.structural_eq(b),
+                    (ImplItem::Const(a), ImplItem::Const(b)) => {
+                        a.const_type.structural_eq(&b.const_type)
+                    }
+                    (ImplItem::Type(a), ImplItem::Type(b)) => {
+                        a.aliased_type.structural_eq(&b.aliased_type)
+                    }
+                    _ => false,
+                })
+    }
+}
+
+impl MethodNode {
+    pub fn structural_eq(&self, other: &MethodNode) -> bool {
+        let return_types_eq = match (&self.return_type, &other.return_type) {
+            (Some(a), Some(b)) => a.structural_eq(b),
+            (None, None) => true,
+            _ => false,
+        };
+        return_types_eq
+            && self.is_async == other.is_async
+            && self.params.len() == other.params.len()
+            && self
+                .params
+                .iter()
+                .zip(&other.params)
+                .all(|(a, b)| a.structural_eq(b))
+    }
+}
+
+impl ParamNode {
+    pub fn structural_eq(&self, other: &ParamNode) -> bool {
+        self.param_type.structural_eq(&other.param_type)
+    }
+}
+
+impl FieldNode {
+    pub fn structural_eq(&self, other: &FieldNode) -> bool {
+        self.field_type.structural_eq(&other.field_type)
+    }
+}
+
+impl VariantNode {
+    pub fn structural_eq(&self, other: &VariantNode) -> bool {
+        match (&self.associated_data, &other.associated_data) {
+            (Some(a), Some(b)) => a.structural_eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl TypeNode {
+    /// Types carry no item names, so two types are structurally equal only if
+    /// they are the same type.
+    pub fn structural_eq(&self, other: &TypeNode) -> bool {
+        match (self, other) {
+            (TypeNode::Simple(a), TypeNode::Simple(b)) => a == b,
+            (
+                TypeNode::Reference {
+                    lifetime: a_lifetime,
+                    mutable: a_mutable,
+                    inner: a,
+                },
+                TypeNode::Reference {
+                    lifetime: b_lifetime,
+                    mutable: b_mutable,
+                    inner: b,
+                },
+            ) => a_lifetime == b_lifetime && a_mutable == b_mutable && a.structural_eq(b),
+            (
+                TypeNode::RawPointer {
+                    mutable: a_mutable,
+                    inner: a,
+                },
+                TypeNode::RawPointer {
+                    mutable: b_mutable,
+                    inner: b,
+                },
+            ) => a_mutable == b_mutable && a.structural_eq(b),
+            (
+                TypeNode::Generic {
+                    name: a,
+                    args: a_args,
+                },
+                TypeNode::Generic {
+                    name: b,
+                    args: b_args,
+                },
+            ) => {
+                a == b
+                    && a_args.len() == b_args.len()
+                    && a_args.iter().zip(b_args).all(|(a, b)| a.structural_eq(b))
+            }
+            (TypeNode::Tuple(a), TypeNode::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.structural_eq(b))
+            }
+            (TypeNode::ImplTrait(a), TypeNode::ImplTrait(b))
+            | (TypeNode::DynTrait(a), TypeNode::DynTrait(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+// Generic Parameter Validation
+impl AstNode {
+    /// Reports generic parameters declared out of the order Rust requires:
+    /// lifetimes first, then type parameters, then const parameters.
+    pub fn check_generic_order(&self) -> Vec<OrderWarning> {
+        let (item, generics) = match self {
+            AstNode::Trait(node) => (node.name.clone(), &node.generics),
+            AstNode::Struct(node) => (node.name.clone(), &node.generics),
+            AstNode::Enum(node) => (node.name.clone(), &node.generics),
+            AstNode::TypeAlias(node) => (node.name.clone(), &node.generics),
+            AstNode::Impl(node) => (node.self_type.display(), &node.generics),
+            AstNode::Function(node) => (node.signature.name.clone(), &node.signature.generics),
+            // Foreign functions cannot declare type or const parameters
+            AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Use(_)
+            | AstNode::Extern(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => return Vec::new(),
+            AstNode::Module(node) => {
+                return node
+                    .items
+                    .iter()
+                    .flat_map(|item| item.check_generic_order())
+                    .collect();
+            }
+        };
+
+        let mut warnings = Vec::new();
+        for (i, param) in generics.iter().enumerate() {
+            if let Some(earlier) = generics[..i].iter().find(|g| g.kind() > param.kind()) {
+                warnings.push(OrderWarning {
+                    item: item.clone(),
+                    param: param.name().to_string(),
+                    kind: param.kind(),
+                    should_precede: earlier.name().to_string(),
+                    should_precede_kind: earlier.kind(),
+                });
+            }
+        }
+        warnings
+    }
+
+    /// Returns the names of the generic parameters the item declares, in
+    /// declaration order. Lifetimes keep their leading `'`. Items of a
+    /// module are not included.
+    pub fn generic_param_names(&self) -> Vec<String> {
+        let generics = match self {
+            AstNode::Trait(node) => &node.generics,
+            AstNode::Struct(node) => &node.generics,
+            AstNode::Enum(node) => &node.generics,
+            AstNode::Impl(node) => &node.generics,
+            AstNode::TypeAlias(node) => &node.generics,
+            AstNode::Function(node) => &node.signature.generics,
+            AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Module(_)
+            | AstNode::Use(_)
+            | AstNode::Extern(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => return Vec::new(),
+        };
+        generics.iter().map(|g| g.name().to_string()).collect()
+    }
+}
+
+impl GenericParam {
+    pub fn name(&self) -> &str {
+        match self {
+            GenericParam::Lifetime { name, .. }
+            | GenericParam::Type { name, .. }
+            | GenericParam::Const { name, .. } => name,
+        }
+    }
+
+    pub fn kind(&self) -> GenericParamKind {
+        match self {
+            GenericParam::Lifetime { .. } => GenericParamKind::Lifetime,
+            GenericParam::Type { .. } => GenericParamKind::Type,
+            GenericParam::Const { .. } => GenericParamKind::Const,
+        }
+    }
+}
+
+impl std::fmt::Display for GenericParamKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GenericParamKind::Lifetime => write!(f, "lifetime"),
+            GenericParamKind::Type => write!(f, "type parameter"),
+            GenericParamKind::Const => write!(f, "const parameter"),
+        }
+    }
+}
+
+impl std::fmt::Display for OrderWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} `{}` should precede {} `{}` in `{}`",
+            self.kind, self.param, self.should_precede_kind, self.should_precede, self.item
+        )
+    }
+}
+
+// Const Argument Resolution
+impl AstNode {
+    /// Reports the names used by const arguments that do not refer to a
+    /// const generic parameter in scope, each once in order of appearance.
+    /// Const arguments are array lengths, such as the `N` of `[T; N]`, and
+    /// generic arguments written as literals or blocks, such as `{ N + 1 }`.
+    /// Names resolve against the `const` parameters of the enclosing item
+    /// and, inside a method, of the method.
+    ///
+    /// A bare name passed as a generic argument, as in `Buffer<N>`, parses
+    /// like a type and is not checked. Const items are not tracked, so a
+    /// length naming a module-level `const` is reported as unresolved.
+    pub fn unresolved_const_args(&self) -> Vec<String> {
+        let mut unresolved = Vec::new();
+        self.collect_unresolved_const_args(&[], &mut unresolved);
+        unresolved
+    }
+
+    fn collect_unresolved_const_args(&self, outer: &[&str], unresolved: &mut Vec<String>) {
+        let mut scope = outer.to_vec();
+        match self {
+            AstNode::Trait(node) => {
+                scope.extend(const_param_names(&node.generics));
+                for method in &node.methods {
+                    method.collect_unresolved_const_args(&scope, unresolved);
+                }
+            }
+            AstNode::Struct(node) => {
+                scope.extend(const_param_names(&node.generics));
+                for field in &node.fields {
+                    field
+                        .field_type
+                        .collect_unresolved_const_args(&scope, unresolved);
+                }
+                for method in &node.inherent_methods {
+                    method.collect_unresolved_const_args(&scope, unresolved);
+                }
+            }
+            AstNode::Enum(node) => {
+                scope.extend(const_param_names(&node.generics));
+                for data in node
+                    .variants
+                    .iter()
+                    .filter_map(|v| v.associated_data.as_ref())
+                {
+                    data.collect_unresolved_const_args(&scope, unresolved);
+                }
+                for method in &node.inherent_methods {
+                    method.collect_unresolved_const_args(&scope, unresolved);
+                }
+            }
+            AstNode::TypeAlias(node) => {
+                scope.extend(const_param_names(&node.generics));
+                node.aliased_type
+                    .collect_unresolved_const_args(&scope, unresolved);
+            }
+            AstNode::Const(node) => node
+                .const_type
+                .collect_unresolved_const_args(&[], unresolved),
+            AstNode::Static(node) => node
+                .static_type
+                .collect_unresolved_const_args(&[], unresolved),
+            AstNode::Impl(node) => {
+                scope.extend(const_param_names(&node.generics));
+                let types = node.trait_type.iter().chain([&node.self_type]);
+                for ty in types {
+                    ty.collect_unresolved_const_args(&scope, unresolved);
+                }
+                for item in &node.items {
+                    match item {
+                        ImplItem::Method(m) => m.collect_unresolved_const_args(&scope, unresolved),
+                        ImplItem::Const(c) => c
+                            .const_type
+                            .collect_unresolved_const_args(&scope, unresolved),
+                        ImplItem::Type(t) => t
+                            .aliased_type
+                            .collect_unresolved_const_args(&scope, unresolved),
+                    }
+                }
+            }
+            AstNode::Module(node) => {
+                for item in &node.items {
+                    item.collect_unresolved_const_args(&[], unresolved);
+                }
+            }
+            AstNode::Extern(node) => {
+                for function in &node.functions {
+                    function
+                        .signature
+                        .collect_unresolved_const_args(&[], unresolved);
+                }
+            }
+            AstNode::Function(node) => node
+                .signature
+                .collect_unresolved_const_args(&[], unresolved),
+            AstNode::Use(_) | AstNode::MacroDef(_) | AstNode::Opaque(_) => {}
+        }
+    }
+}
+
+impl MethodNode {
+    fn collect_unresolved_const_args(&self, outer: &[&str], unresolved: &mut Vec<String>) {
+        let mut scope = outer.to_vec();
+        scope.extend(const_param_names(&self.generics));
+        let types = self.params.iter().map(|p| &*p.param_type);
+        for ty in types.chain(self.return_type.as_deref()) {
+            ty.collect_unresolved_const_args(&scope, unresolved);
+        }
+    }
+}
+
+impl TypeNode {
+    fn collect_unresolved_const_args(&self, scope: &[&str], unresolved: &mut Vec<String>) {
+        let check_expr = |expr: &str, unresolved: &mut Vec<String>| {
+            for name in const_expr_names(expr) {
+                if !scope.contains(&name) && !unresolved.iter().any(|n| n == name) {
+                    unresolved.push(name.to_string());
+                }
+            }
+        };
+        match self {
+            TypeNode::Simple(_) => {}
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                inner.collect_unresolved_const_args(scope, unresolved)
+            }
+            TypeNode::Generic { name, args } => {
+                for (i, arg) in args.iter().enumerate() {
+                    match arg {
+                        TypeNode::Simple(len) if name == "[]" && i == 1 => {
+                            check_expr(len, unresolved)
+                        }
+                        TypeNode::Simple(expr) if is_const_expr(expr) => {
+                            check_expr(expr, unresolved)
+                        }
+                        _ => arg.collect_unresolved_const_args(scope, unresolved),
+                    }
+                }
+            }
+            TypeNode::Tuple(elements) => {
+                for element in elements {
+                    element.collect_unresolved_const_args(scope, unresolved);
+                }
+            }
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                for bound in bounds {
+                    match bound {
+                        Bound::Lifetime(_) => {}
+                        Bound::Trait(ty) | Bound::Negative(ty) => {
+                            ty.collect_unresolved_const_args(scope, unresolved)
+                        }
+                        Bound::Closure {
+                            params,
+                            return_type,
+                            ..
+                        } => {
+                            for ty in params.iter().chain(return_type.as_deref()) {
+                                ty.collect_unresolved_const_args(scope, unresolved);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn const_param_names(generics: &[GenericParam]) -> impl Iterator<Item = &str> {
+    generics.iter().filter_map(|g| match g {
+        GenericParam::Const { name, .. } => Some(name.as_str()),
+        GenericParam::Lifetime { .. } | GenericParam::Type { .. } => None,
+    })
+}
+
+/// Whether a generic argument, parsed as a simple type, is written as a
+/// literal or block and so must be a const argument.
+fn is_const_expr(arg: &str) -> bool {
+    arg.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '{' | '-' | '\'' | '"'))
+        || arg == "true"
+        || arg == "false"
+}
+
+/// Returns the names and paths, such as `N` or `Self::LEN`, that a const
+/// expression uses. Literals, including suffixed numbers like `4usize`, and
+/// the `true` and `false` keywords are skipped.
+fn const_expr_names(expr: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut i = 0;
+    while i < expr.len() {
+        let rest = &expr[i..];
+        if let Some(len) = literal_len(rest) {
+            i += len;
+            continue;
+        }
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            i += rest.chars().next().map_or(1, char::len_utf8);
+            continue;
+        }
+        let token = rest[..len].trim_matches(':');
+        if token.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && !matches!(token, "true" | "false")
+        {
+            names.push(token);
+        }
+        i += len;
+    }
+    names
+}
+
+// Type Renaming
+impl AstNode {
+    /// Renames every occurrence of the type `from` to `to` in fields, params,
+    /// return types, const generic types, type parameter defaults,
+    /// where-clause subjects and
+    /// enum-variant data, returning the number of replacements. Only whole
+    /// type names match, so renaming `Id` leaves `UserId` untouched.
+    pub fn rename_type(&mut self, from: &str, to: &str) -> usize {
+        let generics_count = |generics: &mut Vec<GenericParam>| -> usize {
+            generics
+                .iter_mut()
+                .map(|g| match g {
+                    GenericParam::Const { ty, .. } => ty.rename(from, to),
+                    GenericParam::Type {
+                        default: Some(ty), ..
+                    } => ty.rename(from, to),
+                    _ => 0,
+                })
+                .sum()
+        };
+        let where_count = |predicates: &mut Vec<WherePredicate>| -> usize {
+            predicates
+                .iter_mut()
+                .map(|p| p.subject.rename(from, to))
+                .sum()
+        };
+
+        match self {
+            AstNode::Trait(node) => {
+                generics_count(&mut node.generics)
+                    + where_count(&mut node.where_clause)
+                    + node
+                        .methods
+                        .iter_mut()
+                        .map(|m| m.rename_type(from, to))
+                        .sum::<usize>()
+            }
+            AstNode::Struct(node) => {
+                generics_count(&mut node.generics)
+                    + where_count(&mut node.where_clause)
+                    + node
+                        .fields
+                        .iter_mut()
+                        .map(|f| f.field_type.rename(from, to))
+                        .sum::<usize>()
+                    + node
+                        .inherent_methods
+                        .iter_mut()
+                        .map(|m| m.rename_type(from, to))
+                        .sum::<usize>()
+            }
+            AstNode::Enum(node) => {
+                generics_count(&mut node.generics)
+                    + where_count(&mut node.where_clause)
+                    + node
+                        .variants
+                        .iter_mut()
+                        .filter_map(|v| v.associated_data.as_mut())
+                        .map(|data| data.rename_type(from, to))
+                        .sum::<usize>()
+                    + node
+                        .inherent_methods
+                        .iter_mut()
+                        .map(|m| m.rename_type(from, to))
+                        .sum::<usize>()
+            }
+            AstNode::TypeAlias(node) => {
+                generics_count(&mut node.generics) + node.aliased_type.rename(from, to)
+            }
+            AstNode::Const(node) => node.const_type.rename(from, to),
+            AstNode::Static(node) => node.static_type.rename(from, to),
+            AstNode::Use(_) | AstNode::MacroDef(_) | AstNode::Opaque(_) => 0,
+            AstNode::Extern(node) => node
+                .functions
+                .iter_mut()
+                .map(|f| f.signature.rename_type(from, to))
+                .sum(),
+            AstNode::Function(node) => node.signature.rename_type(from, to),
+            AstNode::Impl(node) => {
+                generics_count(&mut node.generics)
+                    + where_count(&mut node.where_clause)
+                    + node.trait_type.as_mut().map_or(0, |ty| ty.rename(from, to))
+                    + node.self_type.rename(from, to)
+                    + node
+                        .items
+                        .iter_mut()
+                        .map(|item| match item {
+                            ImplItem::Method(m) => m.rename_type(from, to),
+                            ImplItem::Const(c) => c.const_type.rename(from, to),
+                            ImplItem::Type(t) => t.aliased_type.rename(from, to),
+                        })
+                        .sum::<usize>()
+            }
+            AstNode::Module(node) => node
+                .items
+                .iter_mut()
+                .map(|item| item.rename_type(from, to))
+                .sum(),
+        }
+    }
+}
+
+impl MethodNode {
+    fn rename_type(&mut self, from: &str, to: &str) -> usize {
+        let params: usize = self
+            .params
+            .iter_mut()
+            .map(|p| p.param_type.rename(from, to))
+            .sum();
+        let return_type = self
+            .return_type
+            .as_mut()
+            .map_or(0, |ty| ty.rename(from, to));
+        let where_clause: usize = self
+            .where_clause
+            .iter_mut()
+            .map(|p| p.subject.rename(from, to))
+            .sum();
+        params + return_type + where_clause
+    }
+}
+
+impl TypeNode {
+    fn rename(&mut self, from: &str, to: &str) -> usize {
+        match self {
+            TypeNode::Simple(name) => {
+                if name == from {
+                    *name = to.to_string();
+                    1
+                } else {
+                    0
+                }
+            }
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                inner.rename(from, to)
+            }
+            TypeNode::Generic { name, args } => {
+                let mut count = 0;
+                if name == from {
+                    *name = to.to_string();
+                    count += 1;
+                }
+                count
+                    + args
+                        .iter_mut()
+                        .map(|arg| arg.rename(from, to))
+                        .sum::<usize>()
+            }
+            TypeNode::Tuple(elements) => elements.iter_mut().map(|e| e.rename(from, to)).sum(),
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                bounds.iter_mut().map(|b| b.rename(from, to)).sum()
+            }
+        }
+    }
+}
+
+impl Bound {
+    fn rename(&mut self, from: &str, to: &str) -> usize {
+        match self {
+            Bound::Lifetime(_) => 0,
+            Bound::Trait(ty) | Bound::Negative(ty) => ty.rename(from, to),
+            Bound::Closure {
+                params,
+                return_type,
+                ..
+            } => {
+                params.iter_mut().map(|p| p.rename(from, to)).sum::<usize>()
+                    + return_type.as_mut().map_or(0, |ty| ty.rename(from, to))
+            }
+        }
+    }
+}
+
+// Monomorphization
+type Bindings = HashMap<String, TypeNode>;
+
+impl AstNode {
+    /// Returns a copy of the item with every type named by a key of
+    /// `bindings`, such as a generic parameter `T`, replaced by the bound
+    /// type. The bound type parameters are dropped from the item's generics,
+    /// so `struct Wrapper<T> { inner: T }` with `T = u32` becomes
+    /// `struct Wrapper { inner: u32 }`.
+    pub fn monomorphize(&self, bindings: &HashMap<String, TypeNode>) -> AstNode {
+        let mut node = self.clone();
+        node.substitute(bindings);
+        node
+    }
+
+    fn substitute(&mut self, bindings: &Bindings) {
+        match self {
+            AstNode::Trait(node) => {
+                substitute_generics(&mut node.generics, &mut node.where_clause, bindings);
+                node.methods.iter_mut().for_each(|m| m.substitute(bindings));
+            }
+            AstNode::Struct(node) => {
+                substitute_generics(&mut node.generics, &mut node.where_clause, bindings);
+                for field in &mut node.fields {
+                    field.field_type.substitute(bindings);
+                }
+                node.inherent_methods
+                    .iter_mut()
+                    .for_each(|m| m.substitute(bindings));
+            }
+            AstNode::Enum(node) => {
+                substitute_generics(&mut node.generics, &mut node.where_clause, bindings);
+                node.variants
+                    .iter_mut()
+                    .filter_map(|v| v.associated_data.as_mut())
+                    .for_each(|data| data.substitute(bindings));
+                node.inherent_methods
+                    .iter_mut()
+                    .for_each(|m| m.substitute(bindings));
+            }
+            AstNode::TypeAlias(node) => {
+                substitute_generics(&mut node.generics, &mut [], bindings);
+                node.aliased_type.substitute(bindings);
+            }
+            AstNode::Const(node) => node.const_type.substitute(bindings),
+            AstNode::Static(node) => node.static_type.substitute(bindings),
+            AstNode::Use(_) | AstNode::MacroDef(_) | AstNode::Opaque(_) => {}
+            AstNode::Extern(node) => node
+                .functions
+                .iter_mut()
+                .for_each(|f| f.signature.substitute(bindings)),
+            AstNode::Function(node) => node.signature.substitute(bindings),
+            AstNode::Impl(node) => {
+                substitute_generics(&mut node.generics, &mut node.where_clause, bindings);
+                if let Some(trait_type) = &mut node.trait_type {
+                    trait_type.substitute(bindings);
+                }
+                node.self_type.substitute(bindings);
+                for item in &mut node.items {
+                    match item {
+                        ImplItem::Method(m) => m.substitute(bindings),
+                        ImplItem::Const(c) => c.const_type.substitute(bindings),
+                        ImplItem::Type(t) => t.aliased_type.substitute(bindings),
+                    }
+                }
+            }
+            AstNode::Module(node) => node.items.iter_mut().for_each(|i| i.substitute(bindings)),
+        }
+    }
+}
+
+/// Drops the bound type parameters and substitutes the types in the bounds
+/// of the others and in the where clause.
+fn substitute_generics(
+    generics: &mut Vec<GenericParam>,
+    where_clause: &mut [WherePredicate],
+    bindings: &Bindings,
+) {
+    generics.retain(|g| !(g.kind() == GenericParamKind::Type && bindings.contains_key(g.name())));
+    for generic in generics.iter_mut() {
+        match generic {
+            GenericParam::Lifetime { .. } => {}
+            GenericParam::Type {
+                bounds, default, ..
+            } => {
+                bounds.iter_mut().for_each(|b| b.substitute(bindings));
+                if let Some(default) = default {
+                    default.substitute(bindings);
+                }
+            }
+            GenericParam::Const { ty, .. } => ty.substitute(bindings),
+        }
+    }
+    for predicate in where_clause {
+        predicate.subject.substitute(bindings);
+        predicate
+            .bounds
+            .iter_mut()
+            .for_each(|b| b.substitute(bindings));
+    }
+}
+
+impl MethodNode {
+    fn substitute(&mut self, bindings: &Bindings) {
+        substitute_generics(&mut self.generics, &mut self.where_clause, bindings);
+        for param in &mut self.params {
+            param.param_type.substitute(bindings);
+        }
+        if let Some(return_type) = &mut self.return_type {
+            return_type.substitute(bindings);
+        }
+    }
+}
+
+impl TypeNode {
+    fn substitute(&mut self, bindings: &Bindings) {
+        match self {
+            TypeNode::Simple(name) => {
+                if let Some(bound) = bindings.get(name) {
+                    *self = bound.clone();
+                }
+            }
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                inner.substitute(bindings)
+            }
+            TypeNode::Generic { args, .. } => args.iter_mut().for_each(|a| a.substitute(bindings)),
+            TypeNode::Tuple(elements) => elements.iter_mut().for_each(|e| e.substitute(bindings)),
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                bounds.iter_mut().for_each(|b| b.substitute(bindings));
+            }
+        }
+    }
+}
+
+impl Bound {
+    fn substitute(&mut self, bindings: &Bindings) {
+        match self {
+            Bound::Lifetime(_) => {}
+            Bound::Trait(ty) | Bound::Negative(ty) => ty.substitute(bindings),
+            Bound::Closure {
+                params,
+                return_type,
+                ..
+            } => {
+                params.iter_mut().for_each(|p| p.substitute(bindings));
+                if let Some(return_type) = return_type {
+                    return_type.substitute(bindings);
+                }
+            }
+        }
+    }
+}
+
+// Type Depth Metrics
+impl TypeNode {
+    /// Returns the nesting depth of the type: `u8` has depth 1, `Vec<u8>`
+    /// depth 2 and `Vec<Vec<u8>>` depth 3. References and tuples count as a
+    /// level like generics do.
+    pub fn depth(&self) -> usize {
+        match self {
+            TypeNode::Simple(_) => 1,
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                1 + inner.depth()
+            }
+            TypeNode::Generic { args, .. } => 1 + args.iter().map(|a| a.depth()).max().unwrap_or(0),
+            TypeNode::Tuple(elements) => 1 + elements.iter().map(|e| e.depth()).max().unwrap_or(0),
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                1 + bounds.iter().map(|b| b.depth()).max().unwrap_or(0)
+            }
+        }
+    }
+}
+
+impl Bound {
+    /// Lifetimes have depth 0; a closure bound counts as a level above its
+    /// parameter and return types.
+    fn depth(&self) -> usize {
+        match self {
+            Bound::Lifetime(_) => 0,
+            Bound::Trait(ty) | Bound::Negative(ty) => ty.depth(),
+            Bound::Closure {
+                params,
+                return_type,
+                ..
+            } => {
+                1 + params
+                    .iter()
+                    .chain(return_type.as_deref())
+                    .map(|ty| ty.depth())
+                    .max()
+                    .unwrap_or(0)
+            }
+        }
+    }
+}
+
+impl AstNode {
+    /// Returns the depth of the most deeply nested type the item contains,
+    /// or 0 if it contains no types.
+    pub fn max_type_depth(&self) -> usize {
+        let generics_depth = |generics: &[GenericParam]| -> usize {
+            generics
+                .iter()
+                .map(|g| match g {
+                    GenericParam::Const { ty, .. } => ty.depth(),
+                    GenericParam::Type {
+                        default: Some(ty), ..
+                    } => ty.depth(),
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0)
+        };
+
+        match self {
+            AstNode::Trait(node) => node
+                .methods
+                .iter()
+                .map(|m| m.max_type_depth())
+                .chain([generics_depth(&node.generics)])
+                .max()
+                .unwrap_or(0),
+            AstNode::Struct(node) => node
+                .fields
+                .iter()
+                .map(|f| f.field_type.depth())
+                .chain(node.inherent_methods.iter().map(|m| m.max_type_depth()))
+                .chain([generics_depth(&node.generics)])
+                .max()
+                .unwrap_or(0),
+            AstNode::Enum(node) => node
+                .variants
+                .iter()
+                .filter_map(|v| v.associated_data.as_ref())
+                .map(|data| data.max_type_depth())
+                .chain(node.inherent_methods.iter().map(|m| m.max_type_depth()))
+                .chain([generics_depth(&node.generics)])
+                .max()
+                .unwrap_or(0),
+            AstNode::TypeAlias(node) => node.aliased_type.depth(),
+            AstNode::Const(node) => node.const_type.depth(),
+            AstNode::Static(node) => node.static_type.depth(),
+            AstNode::Use(_) | AstNode::MacroDef(_) | AstNode::Opaque(_) => 0,
+            AstNode::Extern(node) => node
+                .functions
+                .iter()
+                .map(|f| f.signature.max_type_depth())
+                .max()
+                .unwrap_or(0),
+            AstNode::Function(node) => node.signature.max_type_depth(),
+            AstNode::Impl(node) => node
+                .items
+                .iter()
+                .map(|item| match item {
+                    ImplItem::Method(m) => m.max_type_depth(),
+                    ImplItem::Const(c) => c.const_type.depth(),
+                    ImplItem::Type(t) => t.aliased_type.depth(),
+                })
+                .chain(node.trait_type.iter().map(|t| t.depth()))
+                .chain([node.self_type.depth(), generics_depth(&node.generics)])
+                .max()
+                .unwrap_or(0),
+            AstNode::Module(node) => node
+                .items
+                .iter()
+                .map(|item| item.max_type_depth())
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl MethodNode {
+    fn max_type_depth(&self) -> usize {
+        self.params
+            .iter()
+            .map(|p| p.param_type.depth())
+            .chain(self.return_type.iter().map(|ty| ty.depth()))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// Public API Extraction
+impl AstNode {
+    /// Returns the item if it is declared `pub`, keeping only its public
+    /// members. Structs keep their `pub` fields and modules their public
+    /// items. Traits and enum variants are kept whole, since their methods
+    /// and variants are as visible as they are, while merged inherent methods
+    /// are kept only if `pub`. Impl and extern blocks have no visibility of
+    /// their own: trait impls are kept whole, while inherent impls and extern
+    /// blocks keep their `pub` items and are dropped if none remain.
+    pub fn public_api(&self) -> Option<AstNode> {
+        let is_public = |visibility: &Visibility| *visibility == Visibility::Public;
+        let public_methods = |methods: &[MethodNode]| -> Vec<MethodNode> {
+            methods
+                .iter()
+                .filter(|m| is_public(&m.visibility))
+                .cloned()
+                .collect()
+        };
+        match self {
+            AstNode::Trait(node) => is_public(&node.visibility).then(|| self.clone()),
+            AstNode::Struct(node) => is_public(&node.visibility).then(|| {
+                AstNode::Struct(StructNode {
+                    fields: node
+                        .fields
+                        .iter()
+                        .filter(|f| is_public(&f.visibility))
+                        .cloned()
+                        .collect(),
+                    inherent_methods: public_methods(&node.inherent_methods),
+                    ..node.clone()
+                })
+            }),
+            AstNode::Enum(node) => is_public(&node.visibility).then(|| {
+                AstNode::Enum(EnumNode {
+                    inherent_methods: public_methods(&node.inherent_methods),
+                    ..node.clone()
+                })
+            }),
+            AstNode::TypeAlias(node) => is_public(&node.visibility).then(|| self.clone()),
+            AstNode::Const(node) => is_public(&node.visibility).then(|| self.clone()),
+            AstNode::Static(node) => is_public(&node.visibility).then(|| self.clone()),
+            AstNode::Use(node) => is_public(&node.visibility).then(|| self.clone()),
+            AstNode::Extern(node) => {
+                let functions: Vec<FunctionNode> = node
+                    .functions
+                    .iter()
+                    .filter(|f| is_public(&f.signature.visibility))
+                    .cloned()
+                    .collect();
+                (!functions.is_empty()).then(|| {
+                    AstNode::Extern(ExternBlock {
+                        functions,
+                        ..node.clone()
+                    })
+                })
+            }
+            AstNode::Function(node) => is_public(&node.signature.visibility).then(|| self.clone()),
+            // Macros are exported by attribute rather than `pub`, and the
+            // visibility of an unparsed item is unknown
+            AstNode::MacroDef(_) | AstNode::Opaque(_) => None,
+            AstNode::Module(node) => is_public(&node.visibility).then(|| {
+                AstNode::Module(ModuleNode {
+                    items: AstNode::public_api_of(&node.items),
+                    ..node.clone()
+                })
+            }),
+            AstNode::Impl(node) if node.trait_type.is_some() => Some(self.clone()),
+            AstNode::Impl(node) => {
+                let items: Vec<ImplItem> = node
+                    .items
+                    .iter()
+                    .filter(|item| match item {
+                        ImplItem::Method(m) => is_public(&m.visibility),
+                        ImplItem::Const(c) => is_public(&c.visibility),
+                        ImplItem::Type(t) => is_public(&t.visibility),
+                    })
+                    .cloned()
+                    .collect();
+                (!items.is_empty()).then(|| {
+                    AstNode::Impl(ImplNode {
+                        items,
+                        ..node.clone()
+                    })
+                })
+            }
+        }
+    }
+
+    /// Applies [`AstNode::public_api`] to each item, such as the output of
+    /// [`Parser::parse_many`], dropping the items that are not public.
+    pub fn public_api_of(items: &[AstNode]) -> Vec<AstNode> {
+        items.iter().filter_map(|item| item.public_api()).collect()
+    }
+}
+
+// Child Iteration
+impl<'a> IntoIterator for &'a TraitNode {
+    type Item = &'a MethodNode;
+    type IntoIter = std::slice::Iter<'a, MethodNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.methods.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a StructNode {
+    type Item = &'a FieldNode;
+    type IntoIter = std::slice::Iter<'a, FieldNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.fields.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a EnumNode {
+    type Item = &'a VariantNode;
+    type IntoIter = std::slice::Iter<'a, VariantNode>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.variants.iter()
+    }
+}
+
+impl AstNode {
+    /// Returns the item and every item nested in it, in depth-first
+    /// pre-order: the items of modules and the associated data of enum
+    /// variants, with their own nested items.
+    pub fn all_nodes(&self) -> Vec<&AstNode> {
+        let mut nodes = Vec::new();
+        self.collect_nodes(&mut nodes);
+        nodes
+    }
+
+    fn collect_nodes<'a>(&'a self, nodes: &mut Vec<&'a AstNode>) {
+        nodes.push(self);
+        for child in self.children() {
+            child.collect_nodes(nodes);
+        }
+    }
+
+    /// Returns the items nested directly in this one: the items of a module
+    /// or the associated data of an enum's variants. Other items have no
+    /// children.
+    pub fn children(&self) -> Vec<&AstNode> {
+        match self {
+            AstNode::Module(node) => node.items.iter().collect(),
+            AstNode::Enum(node) => node
+                .variants
+                .iter()
+                .filter_map(|v| v.associated_data.as_deref())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the nesting depth of the item over the same nested items as
+    /// [`AstNode::all_nodes`]: a struct has depth 1, and an enum with a
+    /// struct variant depth 2.
+    pub fn depth(&self) -> usize {
+        1 + self
+            .children()
+            .into_iter()
+            .map(|child| child.depth())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// Mutable Traversal
+/// Hooks that [`walk_mut`] calls on the parts of an AST as it reaches them.
+/// Each hook does nothing by default and runs before the parts inside what
+/// it is given are walked, so those changes are walked in turn.
+pub trait VisitorMut {
+    /// Called on every item, including module items and the anonymous
+    /// structs holding enum variant data.
+    fn visit_node_mut(&mut self, _node: &mut AstNode) {}
+
+    /// Called on trait methods, inherent methods, impl methods and extern
+    /// functions.
+    fn visit_method_mut(&mut self, _method: &mut MethodNode) {}
+
+    fn visit_param_mut(&mut self, _param: &mut ParamNode) {}
+
+    fn visit_field_mut(&mut self, _field: &mut FieldNode) {}
+
+    fn visit_variant_mut(&mut self, _variant: &mut VariantNode) {}
+
+    /// Called on the type of every field, parameter, return value, alias,
+    /// const, static and impl header, then on the types nested in it.
+    /// Generic bounds and where clauses are not walked.
+    fn visit_type_mut(&mut self, _ty: &mut TypeNode) {}
+}
+
+/// Walks `node` depth-first in source order, calling the hooks of `visitor`
+/// on each part so it can be changed in place.
+pub fn walk_mut<V: VisitorMut + ?Sized>(node: &mut AstNode, visitor: &mut V) {
+    visitor.visit_node_mut(node);
+    match node {
+        AstNode::Trait(node) => walk_methods_mut(&mut node.methods, visitor),
+        AstNode::Struct(node) => {
+            walk_fields_mut(&mut node.fields, visitor);
+            walk_methods_mut(&mut node.inherent_methods, visitor);
+        }
+        AstNode::Enum(node) => {
+            for variant in &mut node.variants {
+                visitor.visit_variant_mut(variant);
+                if let Some(data) = variant.associated_data.as_deref_mut() {
+                    walk_mut(data, visitor);
+                }
+            }
+            walk_methods_mut(&mut node.inherent_methods, visitor);
+        }
+        AstNode::TypeAlias(node) => walk_type_mut(&mut node.aliased_type, visitor),
+        AstNode::Const(node) => walk_type_mut(&mut node.const_type, visitor),
+        AstNode::Static(node) => walk_type_mut(&mut node.static_type, visitor),
+        AstNode::Module(node) => {
+            for item in &mut node.items {
+                walk_mut(item, visitor);
+            }
+        }
+        AstNode::Impl(node) => {
+            if let Some(trait_type) = &mut node.trait_type {
+                walk_type_mut(trait_type, visitor);
+            }
+            walk_type_mut(&mut node.self_type, visitor);
+            for item in &mut node.items {
+                match item {
+                    ImplItem::Method(method) => walk_method_mut(method, visitor),
+                    ImplItem::Const(c) => walk_type_mut(&mut c.const_type, visitor),
+                    ImplItem::Type(t) => walk_type_mut(&mut t.aliased_type, visitor),
+                }
+            }
+        }
+        AstNode::Extern(node) => node
+            .functions
+            .iter_mut()
+            .for_each(|f| walk_method_mut(&mut f.signature, visitor)),
+        AstNode::Function(node) => walk_method_mut(&mut node.signature, visitor),
+        AstNode::Use(_) | AstNode::MacroDef(_) | AstNode::Opaque(_) => {}
+    }
+}
+
+fn walk_fields_mut<V: VisitorMut + ?Sized>(fields: &mut [FieldNode], visitor: &mut V) {
+    for field in fields {
+        visitor.visit_field_mut(field);
+        walk_type_mut(&mut field.field_type, visitor);
+    }
+}
+
+fn walk_methods_mut<V: VisitorMut + ?Sized>(methods: &mut [MethodNode], visitor: &mut V) {
+    for method in methods {
+        walk_method_mut(method, visitor);
+    }
+}
+
+fn walk_method_mut<V: VisitorMut + ?Sized>(method: &mut MethodNode, visitor: &mut V) {
+    visitor.visit_method_mut(method);
+    for param in &mut method.params {
+        visitor.visit_param_mut(param);
+        walk_type_mut(&mut param.param_type, visitor);
+    }
+    if let Some(return_type) = &mut method.return_type {
+        walk_type_mut(return_type, visitor);
+    }
+}
+
+fn walk_type_mut<V: VisitorMut + ?Sized>(ty: &mut TypeNode, visitor: &mut V) {
+    visitor.visit_type_mut(ty);
+    match ty {
+        TypeNode::Simple(_) => {}
+        TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+            walk_type_mut(inner, visitor)
+        }
+        TypeNode::Generic { args: types, .. } | TypeNode::Tuple(types) => {
+            for ty in types {
+                walk_type_mut(ty, visitor);
+            }
+        }
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+            for bound in bounds {
+                match bound {
+                    Bound::Lifetime(_) => {}
+                    Bound::Trait(ty) | Bound::Negative(ty) => walk_type_mut(ty, visitor),
+                    Bound::Closure {
+                        params,
+                        return_type,
+                        ..
+                    } => {
+                        for param in params {
+                            walk_type_mut(param, visitor);
+                        }
+                        if let Some(return_type) = return_type {
+                            walk_type_mut(return_type, visitor);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AstNode {
+    /// Walks the node with [`walk_mut`].
+    pub fn walk_mut<V: VisitorMut + ?Sized>(&mut self, visitor: &mut V) {
+        walk_mut(self, visitor);
+    }
+}
+
+// Member Accessors
+impl StructNode {
+    /// Returns the field at `index`, in declaration order.
+    pub fn field(&self, index: usize) -> Option<&FieldNode> {
+        self.fields.get(index)
+    }
+
+    /// Returns the field called `name`. Tuple struct fields are named by
+    /// their position, such as `"0"`.
+    pub fn field_named(&self, name: &str) -> Option<&FieldNode> {
+        self.fields.iter().find(|f| f.name == name)
+    }
+
+    /// Returns the number of fields.
+    pub fn field_count(&self) -> usize {
+        self.fields.len()
+    }
+}
+
+impl EnumNode {
+    /// Returns the variant at `index`, in declaration order.
+    pub fn variant(&self, index: usize) -> Option<&VariantNode> {
+        self.variants.get(index)
+    }
+
+    /// Returns the variant called `name`.
+    pub fn variant_named(&self, name: &str) -> Option<&VariantNode> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+}
+
+impl TraitNode {
+    /// Returns the method at `index`, in declaration order.
+    pub fn method(&self, index: usize) -> Option<&MethodNode> {
+        self.methods.get(index)
+    }
+
+    /// Returns the method called `name`.
+    pub fn method_named(&self, name: &str) -> Option<&MethodNode> {
+        self.methods.iter().find(|m| m.name == name)
+    }
+
+    /// Returns the number of methods.
+    pub fn method_count(&self) -> usize {
+        self.methods.len()
+    }
+}
+
+// Impl Merging
+impl AstNode {
+    /// Attaches the methods of an inherent impl block to this struct or enum
+    /// as its `inherent_methods`. The impl block must be for the item's own
+    /// type, matched by name; its associated consts and types are not kept.
+    pub fn merge_impl(&mut self, impl_node: &ImplNode) -> Result<(), MergeError> {
+        let (name, methods) = match self {
+            AstNode::Struct(node) => (&node.name, &mut node.inherent_methods),
+            AstNode::Enum(node) => (&node.name, &mut node.inherent_methods),
+            _ => return Err(MergeError::UnsupportedTarget),
+        };
+        if impl_node.trait_type.is_some() {
+            return Err(MergeError::TraitImpl);
+        }
+
+        let self_name = match &impl_node.self_type {
+            TypeNode::Simple(path) | TypeNode::Generic { name: path, .. } => {
+                path.rsplit("::").next().unwrap_or(path)
+            }
+            _ => "",
+        };
+        if self_name != name {
+            return Err(MergeError::TypeMismatch {
+                expected: name.clone(),
+                found: impl_node.self_type.display(),
+            });
+        }
+
+        methods.extend(impl_node.items.iter().filter_map(|item| match item {
+            ImplItem::Method(method) => Some(method.clone()),
+            ImplItem::Const(_) | ImplItem::Type(_) => None,
+        }));
+        Ok(())
+    }
+}
+
+// Item Merging
+impl AstNode {
+    /// Combines two definitions of the same struct, enum or trait into one
+    /// with the members of both, `self`'s first. The header, such as the
+    /// visibility and generics, is taken from `self`.
+    pub fn merge(&self, other: &AstNode) -> Result<AstNode, MergeError> {
+        let (name, other_name) = match (self, other) {
+            (AstNode::Struct(a), AstNode::Struct(b)) => (&a.name, &b.name),
+            (AstNode::Enum(a), AstNode::Enum(b)) => (&a.name, &b.name),
+            (AstNode::Trait(a), AstNode::Trait(b)) => (&a.name, &b.name),
+            _ => return Err(MergeError::IncompatibleKinds),
+        };
+        if name != other_name {
+            return Err(MergeError::NameMismatch {
+                expected: name.clone(),
+                found: other_name.clone(),
+            });
+        }
+
+        let mut merged = self.clone();
+        match (&mut merged, other) {
+            (AstNode::Struct(a), AstNode::Struct(b)) => {
+                merge_members(&mut a.fields, &b.fields, |f| &f.name)?;
+                merge_members(&mut a.inherent_methods, &b.inherent_methods, |m| &m.name)?;
+            }
+            (AstNode::Enum(a), AstNode::Enum(b)) => {
+                merge_members(&mut a.variants, &b.variants, |v| &v.name)?;
+                merge_members(&mut a.inherent_methods, &b.inherent_methods, |m| &m.name)?;
+            }
+            (AstNode::Trait(a), AstNode::Trait(b)) => {
+                merge_members(&mut a.methods, &b.methods, |m| &m.name)?;
+            }
+            _ => unreachable!("kinds were checked above"),
+        }
+        Ok(merged)
+    }
+}
+
+fn merge_members<T: Clone>(
+    members: &mut Vec<T>,
+    other: &[T],
+    name: impl Fn(&T) -> &String,
+) -> Result<(), MergeError> {
+    for member in other {
+        if members.iter().any(|m| name(m) == name(member)) {
+            return Err(MergeError::DuplicateMember(name(member).clone()));
+        }
+        members.push(member.clone());
+    }
+    Ok(())
+}
+
+// Item Subtraction
+impl AstNode {
+    /// The inverse of [`AstNode::merge`]: removes the members of `other`
+    /// from this struct, enum or trait, matched by name. Every member of
+    /// `other` must be present in `self`.
+    pub fn subtract(&self, other: &AstNode) -> Result<AstNode, SubtractError> {
+        let (name, other_name) = match (self, other) {
+            (AstNode::Struct(a), AstNode::Struct(b)) => (&a.name, &b.name),
+            (AstNode::Enum(a), AstNode::Enum(b)) => (&a.name, &b.name),
+            (AstNode::Trait(a), AstNode::Trait(b)) => (&a.name, &b.name),
+            _ => return Err(SubtractError::IncompatibleKinds),
+        };
+        if name != other_name {
+            return Err(SubtractError::NameMismatch {
+                expected: name.clone(),
+                found: other_name.clone(),
+            });
+        }
+
+        let mut remaining = self.clone();
+        match (&mut remaining, other) {
+            (AstNode::Struct(a), AstNode::Struct(b)) => {
+                subtract_members(&mut a.fields, &b.fields, |f| &f.name)?;
+                subtract_members(&mut a.inherent_methods, &b.inherent_methods, |m| &m.name)?;
+            }
+            (AstNode::Enum(a), AstNode::Enum(b)) => {
+                subtract_members(&mut a.variants, &b.variants, |v| &v.name)?;
+                subtract_members(&mut a.inherent_methods, &b.inherent_methods, |m| &m.name)?;
+            }
+            (AstNode::Trait(a), AstNode::Trait(b)) => {
+                subtract_members(&mut a.methods, &b.methods, |m| &m.name)?;
+            }
+            _ => unreachable!("kinds were checked above"),
+        }
+        Ok(remaining)
+    }
+}
+
+fn subtract_members<T>(
+    members: &mut Vec<T>,
+    other: &[T],
+    name: impl Fn(&T) -> &String,
+) -> Result<(), SubtractError> {
+    for member in other {
+        let index = members
+            .iter()
+            .position(|m| name(m) == name(member))
+            .ok_or_else(|| SubtractError::MissingMember(name(member).clone()))?;
+        members.remove(index);
+    }
+    Ok(())
+}
+
+// Item Intersection
+impl AstNode {
+    /// Returns a copy of this struct, enum or trait keeping only the members
+    /// that `other` also has, matched by name. Nothing is shared with an item
+    /// of another kind; items without members are returned unchanged.
+    pub fn intersection(&self, other: &AstNode) -> AstNode {
+        let mut common = self.clone();
+        match (&mut common, other) {
+            (AstNode::Struct(a), AstNode::Struct(b)) => {
+                retain_common(&mut a.fields, &b.fields, |f| &f.name);
+                retain_common(&mut a.inherent_methods, &b.inherent_methods, |m| &m.name);
+            }
+            (AstNode::Enum(a), AstNode::Enum(b)) => {
+                retain_common(&mut a.variants, &b.variants, |v| &v.name);
+                retain_common(&mut a.inherent_methods, &b.inherent_methods, |m| &m.name);
+            }
+            (AstNode::Trait(a), AstNode::Trait(b)) => {
+                retain_common(&mut a.methods, &b.methods, |m| &m.name);
+            }
+            (AstNode::Struct(a), _) => {
+                a.fields.clear();
+                a.inherent_methods.clear();
+            }
+            (AstNode::Enum(a), _) => {
+                a.variants.clear();
+                a.inherent_methods.clear();
+            }
+            (AstNode::Trait(a), _) => a.methods.clear(),
+            _ => {}
+        }
+        common
+    }
+}
+
+fn retain_common<T>(members: &mut Vec<T>, other: &[T], name: impl Fn(&T) -> &String) {
+    members.retain(|m| other.iter().any(|o| name(o) == name(m)));
+}
+
+// Method Name Collection
+impl AstNode {
+    /// Returns the names of all methods in the item, in order: trait
+    /// methods, inherent methods merged into structs and enums, including
+    /// those of variant data, `impl` block methods and, for modules, the
+    /// methods of every contained item.
+    pub fn collect_all_method_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.collect_method_names(&mut names);
+        names
+    }
+
+    fn collect_method_names(&self, names: &mut Vec<String>) {
+        let method_names = |methods: &[MethodNode], names: &mut Vec<String>| {
+            names.extend(methods.iter().map(|m| m.name.clone()));
+        };
+        match self {
+            AstNode::Trait(node) => method_names(&node.methods, names),
+            AstNode::Struct(node) => method_names(&node.inherent_methods, names),
+            AstNode::Enum(node) => {
+                method_names(&node.inherent_methods, names);
+                for data in node
+                    .variants
+                    .iter()
+                    .filter_map(|v| v.associated_data.as_ref())
+                {
+                    data.collect_method_names(names);
+                }
+            }
+            AstNode::Impl(node) => {
+                names.extend(node.items.iter().filter_map(|item| match item {
+                    ImplItem::Method(m) => Some(m.name.clone()),
+                    ImplItem::Const(_) | ImplItem::Type(_) => None,
+                }));
+            }
+            AstNode::Module(node) => {
+                for item in &node.items {
+                    item.collect_method_names(names);
+                }
+            }
+            AstNode::TypeAlias(_)
+            | AstNode::Const(_)
+            | AstNode::Static(_)
+            | AstNode::Use(_)
+            | AstNode::Extern(_)
+            | AstNode::Function(_)
+            | AstNode::MacroDef(_)
+            | AstNode::Opaque(_) => {}
+        }
+    }
+}
+
+// Method Queries
+impl MethodNode {
+    /// Returns the `self` parameter the method takes, if it has one.
+    pub fn receiver(&self) -> Option<Receiver> {
+        let first = self.params.first()?;
+        let name = first.name.strip_prefix("mut ").unwrap_or(&first.name);
+        if is_shorthand_receiver(first) {
+            Some(match name.strip_prefix('&') {
+                Some(rest) if rest.split_whitespace().any(|word| word == "mut") => {
+                    Receiver::RefMutSelf
+                }
+                Some(_) => Receiver::RefSelf,
+                None => Receiver::SelfValue,
+            })
+        } else if name == "self" {
+            Some(Receiver::Typed((*first.param_type).clone()))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of parameters, not counting the receiver.
+    pub fn arity(&self) -> usize {
+        self.params_without_self().len()
+    }
+
+    /// Returns the name of the outermost named type the method returns, such
+    /// as `Option` for `Option<String>`. References give the name of the
+    /// type they borrow. Tuples, slices, arrays, `impl` and `dyn` types have
+    /// no name, and neither does a method without a return type.
+    pub fn return_type_name(&self) -> Option<&str> {
+        let mut ty = self.return_type.as_deref()?;
+        while let TypeNode::Reference { inner, .. } = ty {
+            ty = inner;
+        }
+        match ty {
+            TypeNode::Simple(name) => Some(name),
+            TypeNode::Generic { name, .. } if name != "[]" => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Returns the parameters after the receiver, or all of them if the
+    /// method has none.
+    pub fn params_without_self(&self) -> &[ParamNode] {
+        &self.params[usize::from(self.receiver().is_some())..]
+    }
+}
+
+/// Whether the parameter is a receiver written without a type, such as
+/// `&self`, which is parsed with a placeholder `self` type.
+fn is_shorthand_receiver(param: &ParamNode) -> bool {
+    let ty = match &*param.param_type {
+        TypeNode::Reference { inner, .. } => inner,
+        ty => ty,
+    };
+    *ty == TypeNode::Simple("self".to_string())
+}
+
+impl TraitNode {
+    /// Returns the methods whose return type is exactly `type_name`, such as
+    /// `"String"` or `"Option<Self>"`, compared as parsed types.
+    pub fn methods_returning_type(&self, type_name: &str) -> Vec<&MethodNode> {
+        let Ok(ty) = Parser::parse_type(type_name.trim(), ParseOptions::default().max_depth) else {
+            return Vec::new();
+        };
+        self.methods
+            .iter()
+            .filter(|m| m.return_type.as_deref() == Some(&ty))
+            .collect()
+    }
+
+    /// Returns the methods with a parameter of exactly type `type_name`, such
+    /// as `"&str"`, compared as parsed types.
+    pub fn methods_taking_type(&self, type_name: &str) -> Vec<&MethodNode> {
+        let Ok(ty) = Parser::parse_type(type_name.trim(), ParseOptions::default().max_depth) else {
+            return Vec::new();
+        };
+        self.methods
+            .iter()
+            .filter(|m| m.params.iter().any(|p| *p.param_type == ty))
+            .collect()
+    }
+
+    /// Returns the methods an implementation must define: those declared
+    /// without a default body.
+    pub fn required_methods(&self) -> Vec<&MethodNode> {
+        self.methods.iter().filter(|m| !m.has_body).collect()
+    }
+
+    /// Returns the methods with a default body, which implementations may
+    /// leave out.
+    pub fn provided_methods(&self) -> Vec<&MethodNode> {
+        self.methods.iter().filter(|m| m.has_body).collect()
+    }
+
+    /// Approximates whether the trait can be used as `dyn Trait`: every method
+    /// must take a `self` receiver other than `self` by value and may not
+    /// return `Self`, take type parameters (including `impl Trait`
+    /// arguments) or require `Self: Sized` in their where clause, and the
+    /// trait must not require `Self: Sized`, either as a supertrait or in its
+    /// where clause.
+    ///
+    /// This is a heuristic, and stricter than Rust in places. Rust allows
+    /// methods taking `self` by value or bounded by `where Self: Sized`,
+    /// which merely cannot be called on a trait object, so a trait with one
+    /// is reported as unsafe. Other rules, such as receivers like `Rc<Self>` and the object safety
+    /// of supertraits, are ignored.
+    pub fn is_object_safe(&self) -> bool {
+        let sized = Bound::Trait(TypeNode::Simple("Sized".to_string()));
+        let requires_sized = |where_clause: &[WherePredicate]| {
+            where_clause.iter().any(|p| {
+                p.subject == TypeNode::Simple("Self".to_string()) && p.bounds.contains(&sized)
+            })
+        };
+        !self.supertraits.contains(&sized)
+            && !requires_sized(&self.where_clause)
+            && self.methods.iter().all(|m| {
+                let returns_self = m.return_type.as_deref().is_some_and(mentions_self);
+                let is_generic = m
+                    .generics
+                    .iter()
+                    .any(|g| g.kind() == GenericParamKind::Type)
+                    || m.params
+                        .iter()
+                        .any(|p| matches!(*p.param_type, TypeNode::ImplTrait(_)));
+                let by_reference = m
+                    .receiver()
+                    .is_some_and(|receiver| receiver != Receiver::SelfValue);
+                by_reference && !returns_self && !is_generic && !requires_sized(&m.where_clause)
+            })
+    }
+}
+
+// Impl Stubs
+impl TraitNode {
+    /// Emits an `impl` block of the trait for `for_type`, with a `todo!()`
+    /// body for each of the [`TraitNode::required_methods`]. The trait's
+    /// generic parameters and `where` clause are carried over to the impl.
+    pub fn impl_stub(&self, for_type: &str) -> String {
+        let trait_args = if self.generics.is_empty() {
+            String::new()
+        } else {
+            let names: Vec<&str> = self.generics.iter().map(|g| g.name()).collect();
+            format!("<{}>", names.join(", "))
+        };
+        // Impl generics cannot have defaults
+        let mut impl_generics = self.generics.clone();
+        for generic in &mut impl_generics {
+            if let GenericParam::Type { default, .. } = generic {
+                *default = None;
+            }
+        }
+        let header = format!(
+            "impl{} {}{} for {}{}",
+            display_generics(&impl_generics),
+            self.name,
+            trait_args,
+            for_type,
+            display_where_clause(&self.where_clause)
+        );
+
+        let methods: Vec<String> = self
+            .required_methods()
+            .into_iter()
+            .map(|m| format!("    {} {{\n        todo!()\n    }}", m.signature()))
+            .collect();
+        if methods.is_empty() {
+            format!("{} {{}}\n", header)
+        } else {
+            format!("{} {{\n{}\n}}\n", header, methods.join("\n\n"))
+        }
+    }
+}
+
+// Type Mapping
+impl TypeNode {
+    /// Rebuilds the type bottom-up, replacing every node, from the leaves to
+    /// the root, with the result of `f` applied to it once its children have
+    /// been mapped. Types in `impl` and `dyn` bounds are mapped too.
+    pub fn map(&self, f: &impl Fn(&TypeNode) -> TypeNode) -> TypeNode {
+        let mapped = match self {
+            TypeNode::Simple(_) => self.clone(),
+            TypeNode::Reference {
+                lifetime,
+                mutable,
+                inner,
+            } => TypeNode::Reference {
+                lifetime: lifetime.clone(),
+                mutable: *mutable,
+                inner: Box::new(inner.map(f)),
+            },
+            TypeNode::RawPointer { mutable, inner } => TypeNode::RawPointer {
+                mutable: *mutable,
+                inner: Box::new(inner.map(f)),
+            },
+            TypeNode::Generic { name, args } => TypeNode::Generic {
+                name: name.clone(),
+                args: args.iter().map(|a| a.map(f)).collect(),
+            },
+            TypeNode::Tuple(elements) => {
+                TypeNode::Tuple(elements.iter().map(|e| e.map(f)).collect())
+            }
+            TypeNode::ImplTrait(bounds) => TypeNode::ImplTrait(map_bounds(bounds, f)),
+            TypeNode::DynTrait(bounds) => TypeNode::DynTrait(map_bounds(bounds, f)),
+        };
+        f(&mapped)
+    }
+}
+
+fn map_bounds(bounds: &[Bound], f: &impl Fn(&TypeNode) -> TypeNode) -> Vec<Bound> {
+    bounds
+        .iter()
+        .map(|bound| match bound {
+            Bound::Lifetime(_) => bound.clone(),
+            Bound::Trait(ty) => Bound::Trait(ty.map(f)),
+            Bound::Negative(ty) => Bound::Negative(ty.map(f)),
+            Bound::Closure {
+                name,
+                params,
+                return_type,
+            } => Bound::Closure {
+                name: name.clone(),
+                params: params.iter().map(|p| p.map(f)).collect(),
+                return_type: return_type.as_ref().map(|ty| Box::new(ty.map(f))),
+            },
+        })
+        .collect()
+}
+
+// Repr Attributes
+impl AstNode {
+    /// Returns the representation given by the item's `#[repr(...)]`
+    /// attributes, or `None` when it has none or one this parser does not
+    /// recognize.
+    pub fn repr(&self) -> Option<Repr> {
+        match self {
+            AstNode::Trait(node) => parse_reprs(&node.attributes),
+            AstNode::Struct(node) => parse_reprs(&node.attributes),
+            AstNode::Enum(node) => parse_reprs(&node.attributes),
+            _ => None,
+        }
+    }
+}
+
+impl AstNode {
+    /// Returns the traits named by the item's `#[derive(...)]` attributes,
+    /// in source order, with paths kept as written. Attributes spanning
+    /// several lines are read the same as those on one.
+    pub fn derives(&self) -> Vec<String> {
+        let attributes = match self {
+            AstNode::Struct(node) => &node.attributes,
+            AstNode::Enum(node) => &node.attributes,
+            _ => return Vec::new(),
+        };
+        attributes
+            .iter()
+            .filter(|a| a.path == "derive")
+            .filter_map(|a| a.args.as_deref())
+            .flat_map(|args| split_top_level(args, ','))
+            .map(|name| name.split_whitespace().collect::<String>())
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+}
+
+impl StructNode {
+    /// Returns whether the struct is `#[repr(C)]` and every field is a
+    /// primitive with a C counterpart, a raw pointer, a `c_` type alias
+    /// such as `c_int`, or an array of those. This is a heuristic: type
+    /// aliases and other `#[repr(C)]` structs are not resolved, so fields of
+    /// those types make it return `false`.
+    pub fn is_repr_c_compatible(&self) -> bool {
+        let is_repr_c = match parse_reprs(&self.attributes) {
+            Some(Repr::C) => true,
+            Some(Repr::Combined(reprs)) => reprs.contains(&Repr::C),
+            _ => false,
+        };
+        is_repr_c && self.fields.iter().all(|f| is_c_compatible(&f.field_type))
+    }
+}
+
+fn is_c_compatible(ty: &TypeNode) -> bool {
+    match ty {
+        TypeNode::RawPointer { .. } => true,
+        TypeNode::Simple(name) => {
+            let name = name.rsplit("::").next().unwrap_or(name);
+            name.starts_with("c_")
+                || (PRIMITIVES.contains(&name) && !matches!(name, "i128" | "u128" | "char" | "str"))
+        }
+        // Arrays have their length as a second argument, slices do not
+        TypeNode::Generic { name, args } if name == "[]" && args.len() == 2 => {
+            is_c_compatible(&args[0])
+        }
+        _ => false,
+    }
+}
+
+impl TypeNode {
+    /// Returns the C spelling of the type for FFI headers: fixed-width
+    /// integers such as `int32_t`, `float` and `double`, `bool`, the
+    /// `core::ffi` aliases such as `c_int`, raw pointers such as `const
+    /// uint8_t*` and arrays such as `uint8_t[4]`. `()` is `void`, for return
+    /// types. Types without a C counterpart, like `String`, `Vec<T>` or
+    /// references, whose mutability is not kept, give `None`.
+    pub fn to_c_type(&self) -> Option<String> {
+        match self {
+            TypeNode::RawPointer { mutable, inner } => {
+                let pointee = inner.to_c_type()?;
+                Some(if *mutable {
+                    format!("{}*", pointee)
+                } else if pointee.ends_with('*') {
+                    // A pointer to a pointer has the `const` after the inner `*`
+                    format!("{} const*", pointee)
+                } else {
+                    format!("const {}*", pointee)
+                })
+            }
+            TypeNode::Simple(name) => {
+                let c_type = match name.rsplit("::").next().unwrap_or(name) {
+                    "i8" => "int8_t",
+                    "i16" => "int16_t",
+                    "i32" => "int32_t",
+                    "i64" => "int64_t",
+                    "isize" => "intptr_t",
+                    "u8" => "uint8_t",
+                    "u16" => "uint16_t",
+                    "u32" => "uint32_t",
+                    "u64" => "uint64_t",
+                    "usize" => "size_t",
+                    "f32" | "c_float" => "float",
+                    "f64" | "c_double" => "double",
+                    "bool" => "bool",
+                    "c_void" => "void",
+                    "c_char" => "char",
+                    "c_schar" => "signed char",
+                    "c_uchar" => "unsigned char",
+                    "c_short" => "short",
+                    "c_ushort" => "unsigned short",
+                    "c_int" => "int",
+                    "c_uint" => "unsigned int",
+                    "c_long" => "long",
+                    "c_ulong" => "unsigned long",
+                    "c_longlong" => "long long",
+                    "c_ulonglong" => "unsigned long long",
+                    _ => return None,
+                };
+                Some(c_type.to_string())
+            }
+            TypeNode::Tuple(elements) if elements.is_empty() => Some("void".to_string()),
+            TypeNode::Generic { name, args } if name == "[]" && args.len() == 2 => {
+                let TypeNode::Simple(len) = &args[1] else {
+                    return None;
+                };
+                let mut element = args[0].to_c_type()?;
+                // The outer length of a nested array is written first
+                let at = element.find('[').unwrap_or(element.len());
+                element.insert_str(at, &format!("[{}]", len));
+                Some(element)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn parse_reprs(attributes: &[Attribute]) -> Option<Repr> {
+    let mut reprs = Vec::new();
+    for attribute in attributes.iter().filter(|a| a.path == "repr") {
+        for arg in split_top_level(attribute.args.as_deref()?, ',') {
+            let arg = arg.trim();
+            if !arg.is_empty() {
+                reprs.push(parse_repr(arg)?);
+            }
+        }
+    }
+    match reprs.len() {
+        0 => None,
+        1 => reprs.pop(),
+        _ => Some(Repr::Combined(reprs)),
+    }
+}
+
+fn parse_repr(arg: &str) -> Option<Repr> {
+    let (name, value) = match arg.split_once('(') {
+        Some((name, value)) => (name.trim(), Some(value.strip_suffix(')')?.trim())),
+        None => (arg, None),
+    };
+    match (name, value) {
+        ("C", None) => Some(Repr::C),
+        ("transparent", None) => Some(Repr::Transparent),
+        ("packed", None) => Some(Repr::Packed(None)),
+        ("packed", Some(n)) => n.parse().ok().map(|n| Repr::Packed(Some(n))),
+        ("align", Some(n)) => n.parse().ok().map(Repr::Align),
+        (int, None) if int.starts_with(['i', 'u']) && PRIMITIVES.contains(&int) => {
+            Some(Repr::Int(int.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns whether `Self` itself, rather than a path like `Self::Item`,
+/// appears anywhere in the type.
+fn mentions_self(ty: &TypeNode) -> bool {
+    match ty {
+        TypeNode::Simple(name) => name == "Self",
+        TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+            mentions_self(inner)
+        }
+        TypeNode::Generic { args, .. } => args.iter().any(mentions_self),
+        TypeNode::Tuple(elements) => elements.iter().any(mentions_self),
+        TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => bounds
+            .iter()
+            .any(|b| matches!(b, Bound::Trait(ty) if mentions_self(ty))),
+    }
+}
+
+// Bincode Layout
+impl AstNode {
+    /// Returns the fields of a struct or enum as `(name, type)` pairs, in the
+    /// order bincode's default encoding writes them. Struct fields come in
+    /// declaration order, tuple fields named by index. An enum starts with
+    /// its `u32` variant index, named `variant`, followed by the fields of
+    /// every variant named `Variant.field`; only those of the encoded variant
+    /// are written. Other items have no layout.
+    pub fn to_bincode_layout(&self) -> Vec<(String, String)> {
+        let fields = |fields: &[FieldNode], prefix: &str| -> Vec<(String, String)> {
+            fields
+                .iter()
+                .map(|f| (format!("{}{}", prefix, f.name), f.field_type.display()))
+                .collect()
+        };
+        match self {
+            AstNode::Struct(node) => fields(&node.fields, ""),
+            AstNode::Enum(node) => {
+                let mut layout = vec![("variant".to_string(), "u32".to_string())];
+                for variant in &node.variants {
+                    if let Some(AstNode::Struct(data)) = variant.associated_data.as_deref() {
+                        layout.extend(fields(&data.fields, &format!("{}.", variant.name)));
+                    }
+                }
+                layout
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+// Type Queries
+const PRIMITIVES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize", "f32",
+    "f64", "bool", "char", "str",
+];
+
+impl TypeNode {
+    /// Returns whether the type is one of Rust's primitive scalar types or
+    /// `str`. References, generics and user types are not primitives.
+    pub fn is_primitive(&self) -> bool {
+        let TypeNode::Simple(name) = self else {
+            return false;
+        };
+        PRIMITIVES.contains(&name.as_str())
+    }
+
+    /// Returns the names of the types the type is built from, in the order
+    /// they are written and without repeats: `HashMap<String, Vec<i32>>`
+    /// gives `HashMap`, `String`, `Vec` and `i32`. References, slices,
+    /// arrays and tuples contribute only their element types, array lengths
+    /// and lifetimes are left out, and `impl` and `dyn` types contribute the
+    /// traits they name.
+    pub fn referenced_type_names(&self) -> Vec<&str> {
+        let mut names = Vec::new();
+        self.collect_type_names(&mut names);
+        names
+    }
+
+    fn collect_type_names<'a>(&'a self, names: &mut Vec<&'a str>) {
+        let push = |names: &mut Vec<&'a str>, name: &'a str| {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        };
+        match self {
+            TypeNode::Simple(name) => push(names, name),
+            TypeNode::Reference { inner, .. } | TypeNode::RawPointer { inner, .. } => {
+                inner.collect_type_names(names)
+            }
+            // The second argument of an array is its length
+            TypeNode::Generic { name, args } if name == "[]" => args
+                .iter()
+                .take(1)
+                .for_each(|a| a.collect_type_names(names)),
+            TypeNode::Generic { name, args } => {
+                push(names, name);
+                args.iter().for_each(|a| a.collect_type_names(names));
+            }
+            TypeNode::Tuple(elements) => elements.iter().for_each(|e| e.collect_type_names(names)),
+            TypeNode::ImplTrait(bounds) | TypeNode::DynTrait(bounds) => {
+                for bound in bounds {
+                    match bound {
+                        Bound::Lifetime(_) => {}
+                        Bound::Trait(ty) | Bound::Negative(ty) => ty.collect_type_names(names),
+                        Bound::Closure {
+                            name,
+                            params,
+                            return_type,
+                        } => {
+                            push(names, name);
+                            for ty in params.iter().chain(return_type.as_deref()) {
+                                ty.collect_type_names(names);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Use Resolution
+impl UseNode {
+    /// Returns the names the declaration brings into scope, each with the
+    /// full path it refers to, expanding `{...}` groups: `use std::{fmt,
+    /// io::{self, Read as R}}` gives `fmt`, `io` and `R`. A glob import is
+    /// returned under the name `*` with the path of the module it imports
+    /// from. Imports renamed to `_` bring no name into scope and are left
+    /// out.
+    pub fn imports(&self) -> Vec<(String, String)> {
+        let mut imports = Vec::new();
+        collect_imports("", &self.path, &mut imports);
+        imports
+    }
+}
+
+fn collect_imports(prefix: &str, tree: &str, imports: &mut Vec<(String, String)>) {
+    let tree: String = tree.split_whitespace().collect::<Vec<_>>().join(" ");
+    let join = |path: &str| match (prefix, path) {
+        ("", path) => path.to_string(),
+        (prefix, "") => prefix.to_string(),
+        (prefix, path) => format!("{}::{}", prefix, path),
+    };
+
+    if let Some(open) = tree.find('{') {
+        if tree.ends_with('}') {
+            let path = join(tree[..open].trim().trim_end_matches("::"));
+            for subtree in split_top_level(&tree[open + 1..tree.len() - 1], ',') {
+                if !subtree.trim().is_empty() {
+                    collect_imports(&path, subtree.trim(), imports);
+                }
+            }
+        }
+        return;
+    }
+
+    let (path, alias) = match tree.split_once(" as ") {
+        Some((path, alias)) => (path.trim(), Some(alias.trim())),
+        None => (tree.as_str(), None),
+    };
+    if let Some(module) = path.strip_suffix('*') {
+        imports.push(("*".to_string(), join(module.trim_end_matches("::"))));
+        return;
+    }
+    // `self` in a group imports the module the group is in
+    let full_path = match path {
+        "self" => prefix.to_string(),
+        path => join(path),
+    };
+    let name = alias.unwrap_or_else(|| full_path.rsplit("::").next().unwrap_or(&full_path));
+    if name != "_" {
+        imports.push((name.to_string(), full_path.clone()));
+    }
+}
+
+/// Resolves a type name, as written somewhere the `uses` are in scope, to
+/// the full path it was imported from, following renames: with `use
+/// std::collections::HashMap as Map;`, `Map` resolves to
+/// `std::collections::HashMap`. Paths like `io::Error` are resolved by
+/// their first segment. Explicit imports take precedence over globs, and
+/// a name only a glob could provide resolves through it if there is just
+/// one, since which module defines it is not known. Paths keep their
+/// `crate::`, `self::` or `super::` prefix as written.
+pub fn resolve_type_alias(uses: &[UseNode], name: &str) -> Option<String> {
+    let (first, rest) = match name.split_once("::") {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (name, None),
+    };
+    let imports: Vec<(String, String)> = uses.iter().flat_map(UseNode::imports).collect();
+    let path = match imports.iter().find(|(imported, _)| imported == first) {
+        Some((_, path)) => path.clone(),
+        None => match imports
+            .iter()
+            .filter(|(imported, _)| imported == "*")
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            [(_, module)] => format!("{}::{}", module, first),
+            _ => return None,
+        },
+    };
+    Some(match rest {
+        Some(rest) => format!("{}::{}", path, rest),
+        None => path,
+    })
+}
+
+// Difference Explanation
+impl AstNode {
+    /// Describes how `other` differs from `self`, one difference per line, as
+    /// a readable alternative to comparing `Debug` output in failing tests.
+    /// Members are matched by name, with `self` as the left side. Returns an
+    /// empty string when the nodes are equal.
+    pub fn explain_diff(&self, other: &AstNode) -> String {
+        let mut diffs = Vec::new();
+        explain_node("", self, other, &mut diffs);
+        diffs.join("\n")
+    }
+}
+
+fn explain_node(parent: &str, left: &AstNode, right: &AstNode, diffs: &mut Vec<String>) {
+    if left == right {
+        return;
+    }
+    let path = qualify(parent, &item_name(left));
+    let (left_kind, right_kind) = (item_kind(left), item_kind(right));
+    if left_kind != right_kind {
+        diffs.push(format!(
+            "{}: {} on the left, {} on the right",
+            path, left_kind, right_kind
+        ));
+        return;
+    }
+
+    let before = diffs.len();
+    let (left_name, right_name) = (item_name(left), item_name(right));
+    if left_name != right_name {
+        mismatch(diffs, &path, "name", &left_name, &right_name);
+    }
+    match (left, right) {
+        (AstNode::Trait(a), AstNode::Trait(b)) => {
+            explain_generics(&path, &a.generics, &b.generics, diffs);
+            explain_methods(&path, &a.methods, &b.methods, diffs);
+        }
+        (AstNode::Struct(a), AstNode::Struct(b)) => {
+            explain_generics(&path, &a.generics, &b.generics, diffs);
+            explain_fields(&path, &a.fields, &b.fields, diffs);
+            explain_methods(&path, &a.inherent_methods, &b.inherent_methods, diffs);
+        }
+        (AstNode::Enum(a), AstNode::Enum(b)) => {
+            explain_generics(&path, &a.generics, &b.generics, diffs);
+            explain_members(
+                &path,
+                "::",
+                &a.variants,
+                &b.variants,
+                |v| v.name.clone(),
+                |path, a, b, diffs| match (
+                    a.associated_data.as_deref(),
+                    b.associated_data.as_deref(),
+                ) {
+                    (Some(AstNode::Struct(a)), Some(AstNode::Struct(b))) => {
+                        explain_fields(path, &a.fields, &b.fields, diffs)
+                    }
+                    _ if a != b => mismatch(
+                        diffs,
+                        path,
+                        "variant",
+                        &a.to_source_string(),
+                        &b.to_source_string(),
+                    ),
+                    _ => {}
+                },
+                diffs,
+            );
+            explain_methods(&path, &a.inherent_methods, &b.inherent_methods, diffs);
+        }
+        (AstNode::Module(a), AstNode::Module(b)) => explain_members(
+            &path,
+            "::",
+            &a.items,
+            &b.items,
+            item_name,
+            |_, a, b, diffs| explain_node(&path, a, b, diffs),
+            diffs,
+        ),
+        _ => {}
+    }
+    if diffs.len() == before {
+        // Nothing more specific differs, such as a doc comment or value
+        diffs.push(format!("{}: differs", path));
+    }
+}
+
+fn explain_generics(
+    path: &str,
+    left: &[GenericParam],
+    right: &[GenericParam],
+    diffs: &mut Vec<String>,
+) {
+    if left != right {
+        mismatch(
+            diffs,
+            path,
+            "generics",
+            &display_generics(left),
+            &display_generics(right),
+        );
+    }
+}
+
+fn explain_fields(path: &str, left: &[FieldNode], right: &[FieldNode], diffs: &mut Vec<String>) {
+    explain_members(
+        path,
+        ".",
+        left,
+        right,
+        |f| f.name.clone(),
+        |path, a, b, diffs| {
+            if a.field_type != b.field_type {
+                let (a_type, b_type) = (a.field_type.display(), b.field_type.display());
+                mismatch(diffs, path, "type", &a_type, &b_type);
+            }
+            if a.visibility != b.visibility {
+                let (a_vis, b_vis) = (format!("{:?}", a.visibility), format!("{:?}", b.visibility));
+                mismatch(diffs, path, "visibility", &a_vis, &b_vis);
+            }
+        },
+        diffs,
+    );
+}
+
+fn explain_methods(path: &str, left: &[MethodNode], right: &[MethodNode], diffs: &mut Vec<String>) {
+    explain_members(
+        path,
+        ".",
+        left,
+        right,
+        |m| m.name.clone(),
+        |path, a, b, diffs| {
+            if a != b {
+                let (a_sig, b_sig) = (a.signature(), b.signature());
+                mismatch(diffs, path, "signature", &a_sig, &b_sig);
+            }
+        },
+        diffs,
+    );
+}
+
+/// Pairs up the members of two items by name, reporting members found on only
+/// one side and passing the pairs to `explain`.
+fn explain_members<T>(
+    path: &str,
+    separator: &str,
+    left: &[T],
+    right: &[T],
+    name: impl Fn(&T) -> String,
+    mut explain: impl FnMut(&str, &T, &T, &mut Vec<String>),
+    diffs: &mut Vec<String>,
+) {
+    for a in left {
+        let member_path = format!("{}{}{}", path, separator, name(a));
+        match right.iter().find(|b| name(b) == name(a)) {
+            Some(b) => explain(&member_path, a, b, diffs),
+            None => diffs.push(format!("{}: only on the left", member_path)),
+        }
+    }
+    for b in right {
+        if !left.iter().any(|a| name(a) == name(b)) {
+            diffs.push(format!(
+                "{}{}{}: only on the right",
+                path,
+                separator,
+                name(b)
+            ));
+        }
+    }
+}
+
+fn mismatch(diffs: &mut Vec<String>, path: &str, what: &str, left: &str, right: &str) {
+    diffs.push(format!(
+        "{}: {} `{}` on the left, `{}` on the right",
+        path, what, left, right
+    ));
+}
+
+fn qualify(parent: &str, name: &str) -> String {
+    if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", parent, name)
+    }
+}
+
+fn item_kind(node: &AstNode) -> &'static str {
+    match node {
+        AstNode::Trait(_) => "trait",
+        AstNode::Struct(_) => "struct",
+        AstNode::Enum(_) => "enum",
+        AstNode::TypeAlias(_) => "type alias",
+        AstNode::Const(_) => "const",
+        AstNode::Static(_) => "static",
+        AstNode::Module(_) => "module",
+        AstNode::Use(_) => "use",
+        AstNode::Impl(_) => "impl",
+        AstNode::Extern(_) => "extern block",
+        AstNode::Function(_) => "function",
+        AstNode::MacroDef(_) => "macro",
+        AstNode::Opaque(_) => "opaque item",
+    }
+}
+
+/// Returns the name an item is matched by: its own name, the path of a `use`,
+/// the implemented types of an `impl` or the first line of an opaque item.
+fn item_name(node: &AstNode) -> String {
+    match node {
+        AstNode::Trait(node) => node.name.clone(),
+        AstNode::Struct(node) => node.name.clone(),
+        AstNode::Enum(node) => node.name.clone(),
+        AstNode::TypeAlias(node) => node.name.clone(),
+        AstNode::Const(node) => node.name.clone(),
+        AstNode::Static(node) => node.name.clone(),
+        AstNode::Module(node) => node.name.clone(),
+        AstNode::Use(node) => node.path.clone(),
+        AstNode::Impl(node) => format!(
+            "impl {}{}",
+            display_impl_trait(node),
+            node.self_type.display()
+        ),
+        AstNode::Extern(node) => format!("extern \"{}\"", node.abi),
+        AstNode::Function(node) => node.signature.name.clone(),
+        AstNode::MacroDef(node) => node.name.clone(),
+        AstNode::Opaque(source) => opaque_label(source).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_visualizer_trait() {
+        let inputs = vec![
+            r#"pub trait Visualizer {
+                fn visualize(&self, data: &[u8]);
+                fn process(&self, input: &str) -> String;
+            }"#,
+            r#"
+            pub trait Visualizer {
+                fn visualize(&self, data: &[u8]);
+                fn process(&self, input: &str) -> String;
+            }
+            "#,
+            "pub trait Visualizer { fn visualize(&self, data: &[u8]); fn process(&self, input: &str) -> String; }",
+        ];
+
+        for input in inputs {
+            let expected = AstNode::Trait(TraitNode {
+                visibility: Visibility::Public,
+                name: "Visualizer".to_string(),
+                methods: vec![
+                    MethodNode {
+                        name: "visualize".to_string(),
+                        params: vec![
+                            ParamNode {
+                                name: "&self".to_string(),
+                                param_type: Box::new(TypeNode::Reference {
+                                    lifetime: None,
+                                    mutable: false,
+                                    inner: Box::new(TypeNode::Simple("self".to_string())),
+                                }),
+                            },
+                            ParamNode {
+                                name: "data".to_string(),
+                                param_type: Box::new(TypeNode::Reference {
+                                    lifetime: None,
+                                    mutable: false,
+                                    inner: Box::new(TypeNode::Generic {
+                                        name: "[]".to_string(),
+                                        args: vec![TypeNode::Simple("u8".to_string())],
+                                    }),
+                                }),
+                            },
+                        ],
+                        return_type: None,
+                        ..Default::default()
+                    },
+                    MethodNode {
+                        name: "process".to_string(),
+                        params: vec![
+                            ParamNode {
+                                name: "&self".to_string(),
+                                param_type: Box::new(TypeNode::Reference {
+                                    lifetime: None,
+                                    mutable: false,
+                                    inner: Box::new(TypeNode::Simple("self".to_string())),
+                                }),
+                            },
+                            ParamNode {
+                                name: "input".to_string(),
+                                param_type: Box::new(TypeNode::Reference {
+                                    lifetime: None,
+                                    mutable: false,
+                                    inner: Box::new(TypeNode::Simple("str".to_string())),
+                                }),
+                            },
+                        ],
+                        return_type: Some(Box::new(TypeNode::Simple("String".to_string()))),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            });
+
+            assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_struct() {
+        let input = r#"
+            pub struct Point {
+                x: f64,
+                y: f64,
+                label: String,
+            }
+        "#;
+
+        let expected = AstNode::Struct(StructNode {
+            visibility: Visibility::Public,
+            name: "Point".to_string(),
+            fields: vec![
+                FieldNode {
+                    visibility: Visibility::Private,
+                    name: "x".to_string(),
+                    field_type: Box::new(TypeNode::Simple("f64".to_string())),
+                    attributes: Vec::new(),
+                },
+                FieldNode {
+                    visibility: Visibility::Private,
+                    name: "y".to_string(),
+                    field_type: Box::new(TypeNode::Simple("f64".to_string())),
+                    attributes: Vec::new(),
+                },
+                FieldNode {
+                    visibility: Visibility::Private,
+                    name: "label".to_string(),
+                    field_type: Box::new(TypeNode::Simple("String".to_string())),
+                    attributes: Vec::new(),
+                },
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_enum() {
+        let input = r#"
+            pub enum Color {
+                Red,
+                Green,
+                Blue,
+            }
+        "#;
+
+        let expected = AstNode::Enum(EnumNode {
+            visibility: Visibility::Public,
+            name: "Color".to_string(),
+            variants: vec![
+                VariantNode {
+                    name: "Red".to_string(),
+                    associated_data: None,
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+                VariantNode {
+                    name: "Green".to_string(),
+                    associated_data: None,
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+                VariantNode {
+                    name: "Blue".to_string(),
+                    associated_data: None,
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_enum_with_associated_data() {
+        let input = r#"
+            pub enum Message {
+                Quit,
+                Move { x: i32, y: i32 },
+                Write(String),
+                ChangeColor(i32, i32, i32),
+            }
+        "#;
+
+        let expected = AstNode::Enum(EnumNode {
+            visibility: Visibility::Public,
+            name: "Message".to_string(),
+            variants: vec![
+                VariantNode {
+                    name: "Quit".to_string(),
+                    associated_data: None,
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+                VariantNode {
+                    name: "Move".to_string(),
+                    associated_data: Some(Box::new(AstNode::Struct(StructNode {
+                        name: "".to_string(), // Anonymous struct
+                        fields: vec![
+                            FieldNode {
+                                visibility: Visibility::Private,
+                                name: "x".to_string(),
+                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
+                                attributes: Vec::new(),
+                            },
+                            FieldNode {
+                                visibility: Visibility::Private,
+                                name: "y".to_string(),
+                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
+                                attributes: Vec::new(),
+                            },
+                        ],
+                        ..Default::default()
+                    }))),
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+                VariantNode {
+                    name: "Write".to_string(),
+                    associated_data: Some(Box::new(AstNode::Struct(StructNode {
+                        name: "".to_string(), // Tuple struct equivalent
+                        fields: vec![FieldNode {
+                            visibility: Visibility::Private,
+                            name: "0".to_string(),
+                            field_type: Box::new(TypeNode::Simple("String".to_string())),
+                            attributes: Vec::new(),
+                        }],
+                        ..Default::default()
+                    }))),
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+                VariantNode {
+                    name: "ChangeColor".to_string(),
+                    associated_data: Some(Box::new(AstNode::Struct(StructNode {
+                        name: "".to_string(), // Tuple struct equivalent
+                        fields: vec![
+                            FieldNode {
+                                visibility: Visibility::Private,
+                                name: "0".to_string(),
+                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
+                                attributes: Vec::new(),
+                            },
+                            FieldNode {
+                                visibility: Visibility::Private,
+                                name: "1".to_string(),
+                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
+                                attributes: Vec::new(),
+                            },
+                            FieldNode {
+                                visibility: Visibility::Private,
+                                name: "2".to_string(),
+                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
+                                attributes: Vec::new(),
+                            },
+                        ],
+                        ..Default::default()
+                    }))),
+                    discriminant: None,
+                    attributes: Vec::new(),
+                },
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_tree_string_inlines_variant_data() {
+        let input = r#"
+            pub enum Message {
+                Quit,
+                Move { x: i32, y: i32 },
+                Write(String),
+                ChangeColor(i32, i32, i32),
+            }
+        "#;
+
+        let expected = "\
+- Enum: Message
+├── Variant: Quit
+├── Variant: Move { x: i32, y: i32 }
+├── Variant: Write(String)
+└── Variant: ChangeColor(i32, i32, i32)
+";
+        assert_eq!(input.parse::<AstNode>().unwrap().tree_string(), expected);
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let input = "fn standalone_function() {}";
+        assert!(input.parse::<AstNode>().is_err());
+    }
+
+    #[test]
+    fn test_parse_struct_with_invalid_field() {
+        let input = r#"
+            pub struct InvalidStruct {
+                x f64, // Missing colon
+                y: f64,
+            }
+        "#;
+
+        assert!(input.parse::<AstNode>().is_err());
+    }
+
+    #[test]
+    fn test_parse_trait_with_invalid_method() {
+        let input = r#"
+            pub trait InvalidTrait {
+                fn invalid_method(&self data: &[u8]); // Missing comma
+            }
+        "#;
+
+        assert!(input.parse::<AstNode>().is_err());
+    }
+
+    #[test]
+    fn test_structural_eq_ignores_names() {
+        let point: AstNode = "pub struct Point { x: f64, y: f64 }".parse().unwrap();
+        let coord: AstNode = "pub struct Coord { lat: f64, lon: f64 }".parse().unwrap();
+        let mixed: AstNode = "pub struct Mixed { a: f64, b: i32 }".parse().unwrap();
+        let shape: AstNode = "pub enum Shape { Circle(f64), Square(f64) }"
+            .parse()
+            .unwrap();
+        let figure: AstNode = "pub enum Figure { Round(f64), Boxy(f64) }".parse().unwrap();
+
+        assert!(point.structural_eq(&coord));
+        assert!(!point.structural_eq(&mixed));
+        assert!(!point.structural_eq(&shape));
+        assert!(shape.structural_eq(&figure));
+    }
+
+    #[test]
+    fn test_check_generic_order() {
+        let input = "pub struct S<T, 'a> { value: &'a T }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let warnings = ast.check_generic_order();
+        assert_eq!(
+            warnings,
+            vec![OrderWarning {
+                item: "S".to_string(),
+                param: "'a".to_string(),
+                kind: GenericParamKind::Lifetime,
+                should_precede: "T".to_string(),
+                should_precede_kind: GenericParamKind::Type,
+            }]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "lifetime `'a` should precede type parameter `T` in `S`"
+        );
+
+        let ordered = "pub struct S<'a, T: Clone, const N: usize> { value: &'a T }";
+        assert!(ordered
+            .parse::<AstNode>()
+            .unwrap()
+            .check_generic_order()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_rename_type() {
+        let input = r#"
+            pub struct Sample {
+                a: i32,
+                b: Vec<i32>,
+                c: &i32,
+                d: i32x4,
+            }
+        "#;
+        let mut ast = input.parse::<AstNode>().unwrap();
+
+        assert_eq!(ast.rename_type("i32", "i64"), 3);
+
+        let expected = r#"
+            pub struct Sample {
+                a: i64,
+                b: Vec<i64>,
+                c: &i64,
+                d: i32x4,
+            }
+        "#;
+        assert_eq!(ast, expected.parse::<AstNode>().unwrap());
+    }
+
+    #[test]
+    fn test_parse_type_alias() {
+        let input = "pub type Result<T> = std::result::Result<T, Error>;";
+
+        let expected = AstNode::TypeAlias(TypeAliasNode {
+            visibility: Visibility::Public,
+            name: "Result".to_string(),
+            generics: vec![GenericParam::Type {
+                name: "T".to_string(),
+                bounds: vec![],
+                default: None,
+            }],
+            aliased_type: TypeNode::Generic {
+                name: "std::result::Result".to_string(),
+                args: vec![
+                    TypeNode::Simple("T".to_string()),
+                    TypeNode::Simple("Error".to_string()),
+                ],
+            },
+            attributes: Vec::new(),
+            doc: None,
+        });
+        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+
+        let bare = "type Bytes = Vec<u8>;";
+        assert_eq!(
+            bare.parse::<AstNode>().unwrap(),
+            AstNode::TypeAlias(TypeAliasNode {
+                visibility: Visibility::Private,
+                name: "Bytes".to_string(),
+                generics: vec![],
+                aliased_type: TypeNode::Generic {
+                    name: "Vec".to_string(),
+                    args: vec![TypeNode::Simple("u8".to_string())],
+                },
+                attributes: Vec::new(),
+                doc: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_file_with_inner_attributes() {
+        let input = r#"
+            #![allow(dead_code)]
+            #![no_std]
+
+            pub struct Point {
+                x: f64,
+                y: f64,
+            }
+        "#;
+
+        let file = Parser::parse_file(input).unwrap();
+        assert_eq!(
+            file.header.inner_attributes,
+            vec![
+                Attribute {
+                    path: "allow".to_string(),
+                    args: Some("dead_code".to_string()),
+                },
+                Attribute {
+                    path: "no_std".to_string(),
+                    args: None,
+                },
+            ]
+        );
+        assert_eq!(file.items.len(), 1);
+        assert!(matches!(&file.items[0], AstNode::Struct(s) if s.name == "Point"));
+
+        assert_eq!(Parser::parse_many(input).unwrap(), file.items);
+    }
+
+    #[test]
+    fn test_parse_many() {
+        let input = r#"
+            pub struct Point { x: f64, y: f64 }
+            // A comment between items
+            pub type Points = Vec<Point>;
+            pub enum Color { Red, Green }
+        "#;
+
+        let items = Parser::parse_many(input).unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[1], AstNode::TypeAlias(_)));
+        assert!(matches!(items[2], AstNode::Enum(_)));
+    }
+
+    #[test]
+    fn test_parse_const() {
+        let input = "pub const MAX: usize = 1024;";
+        assert_eq!(
+            input.parse::<AstNode>().unwrap(),
+            AstNode::Const(ConstNode {
+                visibility: Visibility::Public,
+                name: "MAX".to_string(),
+                const_type: TypeNode::Simple("usize".to_string()),
+                value: "1024".to_string(),
+                attributes: Vec::new(),
+                doc: None,
+            })
+        );
+
+        let bare = "const ORIGIN: (i32, i32) = Point::new(0, 0);";
+        match bare.parse::<AstNode>().unwrap() {
+            AstNode::Const(const_node) => {
+                assert_eq!(const_node.name, "ORIGIN");
+                assert_eq!(const_node.value, "Point::new(0, 0)");
+            }
+            other => panic!("expected a const, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_static() {
+        let input = "pub static FOO: Mutex<i32> = Mutex::new(0);";
+        assert_eq!(
+            input.parse::<AstNode>().unwrap(),
+            AstNode::Static(StaticNode {
+                visibility: Visibility::Public,
+                name: "FOO".to_string(),
+                static_type: TypeNode::Generic {
+                    name: "Mutex".to_string(),
+                    args: vec![TypeNode::Simple("i32".to_string())],
+                },
+                value: "Mutex::new(0)".to_string(),
+                is_mut: false,
+                attributes: Vec::new(),
+                doc: None,
+            })
+        );
+
+        let mutable = "static mut COUNTER: u32 = 0;";
+        assert_eq!(
+            mutable.parse::<AstNode>().unwrap(),
+            AstNode::Static(StaticNode {
+                visibility: Visibility::Private,
+                name: "COUNTER".to_string(),
+                static_type: TypeNode::Simple("u32".to_string()),
+                value: "0".to_string(),
+                is_mut: true,
+                attributes: Vec::new(),
+                doc: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_reference_and_tuple_return_types() {
+        let input = r#"
+            pub trait Pairs {
+                fn pair(&self) -> &(i32, i32);
+                fn labelled(&self) -> (&str, u8);
+            }
+        "#;
+
+        let methods = match input.parse::<AstNode>().unwrap() {
+            AstNode::Trait(trait_node) => trait_node.methods,
+            other => panic!("expected a trait, got {:?}", other),
+        };
+        let return_types: Vec<&TypeNode> = methods
+            .iter()
+            .map(|m| m.return_type.as_deref().unwrap())
+            .collect();
+
+        assert_eq!(
+            return_types[0],
+            &TypeNode::Reference {
+                lifetime: None,
+                mutable: false,
+                inner: Box::new(TypeNode::Tuple(vec![
+                    TypeNode::Simple("i32".to_string()),
+                    TypeNode::Simple("i32".to_string()),
+                ]))
+            }
+        );
+        assert_eq!(
+            return_types[1],
+            &TypeNode::Tuple(vec![
+                TypeNode::Reference {
+                    lifetime: None,
+                    mutable: false,
+                    inner: Box::new(TypeNode::Simple("str".to_string()))
+                },
+                TypeNode::Simple("u8".to_string()),
+            ])
+        );
+
+        for ty in return_types {
+            assert_eq!(
+                &Parser::parse_type(&ty.display(), ParseOptions::default().max_depth).unwrap(),
+                ty
+            );
+        }
+        assert_eq!(
+            methods[0].return_type.as_ref().unwrap().display(),
+            "&(i32, i32)"
+        );
+        assert_eq!(
+            methods[1].return_type.as_ref().unwrap().display(),
+            "(&str, u8)"
+        );
+    }
+
+    #[test]
+    fn test_parse_reference_lifetimes_and_mutability() {
+        let depth = ParseOptions::default().max_depth;
+        let simple = |name: &str| Box::new(TypeNode::Simple(name.to_string()));
+
+        assert_eq!(
+            Parser::parse_type("&mut Vec<u8>", depth).unwrap(),
+            TypeNode::Reference {
+                lifetime: None,
+                mutable: true,
+                inner: Box::new(TypeNode::Generic {
+                    name: "Vec".to_string(),
+                    args: vec![TypeNode::Simple("u8".to_string())],
+                }),
+            }
+        );
+        assert_eq!(
+            Parser::parse_type("&'a str", depth).unwrap(),
+            TypeNode::Reference {
+                lifetime: Some("'a".to_string()),
+                mutable: false,
+                inner: simple("str"),
+            }
+        );
+        assert_eq!(
+            Parser::parse_type("&&T", depth).unwrap(),
+            TypeNode::Reference {
+                lifetime: None,
+                mutable: false,
+                inner: Box::new(TypeNode::Reference {
+                    lifetime: None,
+                    mutable: false,
+                    inner: simple("T"),
+                }),
+            }
+        );
+        assert_eq!(
+            Parser::parse_type("&'static mut mutex::Guard", depth).unwrap(),
+            TypeNode::Reference {
+                lifetime: Some("'static".to_string()),
+                mutable: true,
+                inner: simple("mutex::Guard"),
+            }
+        );
+
+        for input in [
+            "&mut T",
+            "&'a T",
+            "&&T",
+            "&'a mut [u8]",
+            "&mut &'b dyn Read",
+        ] {
+            let ty = Parser::parse_type(input, depth).unwrap();
+            assert_eq!(ty.display(), input);
+            assert_eq!(Parser::parse_type(&ty.display(), depth).unwrap(), ty);
+        }
+        assert!(Parser::parse_type("&' T", depth).is_err());
+
+        let mut ast: AstNode = "pub struct Cache<'a> { items: &mut Vec<Old>, name: &'a Old }"
+            .parse()
+            .unwrap();
+        assert_eq!(ast.rename_type("Old", "New"), 2);
+        assert_eq!(
+            ast.to_compact_string(),
+            "struct Cache<'a> { items: &mut Vec<New>, name: &'a New }"
+        );
+        let wrapper: AstNode = "pub struct Slot<'a, T> { value: &'a mut T }"
+            .parse()
+            .unwrap();
+        let bindings = HashMap::from([("T".to_string(), TypeNode::Simple("u32".to_string()))]);
+        assert_eq!(
+            wrapper.monomorphize(&bindings).to_compact_string(),
+            "struct Slot<'a> { value: &'a mut u32 }"
+        );
+        let field_type = Parser::parse_type("&mut Vec<u8>", depth).unwrap();
+        assert_eq!(field_type.referenced_type_names(), vec!["Vec", "u8"]);
+    }
+
+    #[test]
+    fn test_parse_module() {
+        let input = r#"
+            pub mod geometry {
+                pub struct Point { x: f64, y: f64 }
+
+                mod shapes {
+                    pub enum Shape { Circle, Square }
+                }
+            }
+        "#;
+
+        let module = match input.parse::<AstNode>().unwrap() {
+            AstNode::Module(module) => module,
+            other => panic!("expected a module, got {:?}", other),
+        };
+        assert_eq!(module.name, "geometry");
+        assert_eq!(module.items.len(), 2);
+        assert!(matches!(&module.items[0], AstNode::Struct(s) if s.name == "Point"));
+        match &module.items[1] {
+            AstNode::Module(inner) => {
+                assert_eq!(inner.name, "shapes");
+                assert!(matches!(inner.items.as_slice(), [AstNode::Enum(_)]));
+            }
+            other => panic!("expected a nested module, got {:?}", other),
+        }
+
+        assert_eq!(
+            "mod tests;".parse::<AstNode>().unwrap(),
+            AstNode::Module(ModuleNode {
+                visibility: Visibility::Private,
+                name: "tests".to_string(),
+                items: vec![],
+                attributes: Vec::new(),
+                doc: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_use() {
+        let cases = [
+            (
+                "pub use crate::foo::Bar;",
+                Visibility::Public,
+                "crate::foo::Bar",
+            ),
+            (
+                "use std::collections::HashMap;",
+                Visibility::Private,
+                "std::collections::HashMap",
+            ),
+            (
+                "pub(crate) use super::{Alpha, Beta as B};",
+                Visibility::Crate,
+                "super::{Alpha, Beta as B}",
+            ),
+            (
+                "pub(in crate::net) use super::Socket;",
+                Visibility::PubIn("crate::net".to_string()),
+                "super::Socket",
+            ),
+        ];
+
+        for (input, visibility, path) in cases {
+            assert_eq!(
+                input.parse::<AstNode>().unwrap(),
+                AstNode::Use(UseNode {
+                    visibility,
+                    path: path.to_string(),
+                    attributes: Vec::new(),
+                    doc: None,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_pub_in_path_containing_keyword() {
+        let visibility = |module: &str| Visibility::PubIn(format!("crate::{}", module));
+
+        match "pub(in crate::typed) type Alias = u8;"
+            .parse::<AstNode>()
+            .unwrap()
+        {
+            AstNode::TypeAlias(node) => {
+                assert_eq!(node.visibility, visibility("typed"));
+                assert_eq!(node.name, "Alias");
+            }
+            other => panic!("expected a type alias, got {:?}", other),
+        }
+        match "pub(in crate::constants) const MAX: u8 = 1;"
+            .parse::<AstNode>()
+            .unwrap()
+        {
+            AstNode::Const(node) => {
+                assert_eq!(node.visibility, visibility("constants"));
+                assert_eq!(node.name, "MAX");
+                assert_eq!(node.value, "1");
+            }
+            other => panic!("expected a const, got {:?}", other),
+        }
+        match "pub(in crate::modules) mod inner {}"
+            .parse::<AstNode>()
+            .unwrap()
+        {
+            AstNode::Module(node) => {
+                assert_eq!(node.visibility, visibility("modules"));
+                assert_eq!(node.name, "inner");
+            }
+            other => panic!("expected a module, got {:?}", other),
+        }
+        match "pub(in crate::structs) struct Point { x: i32 }"
+            .parse::<AstNode>()
+            .unwrap()
+        {
+            AstNode::Struct(node) => assert_eq!(node.name, "Point"),
+            other => panic!("expected a struct, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_grouped_trait_bounds() {
+        let input = "pub trait Runner { fn f<T: (Fn() -> u8) + Send>(&self, t: T); }";
+
+        let method = match input.parse::<AstNode>().unwrap() {
+            AstNode::Trait(mut trait_node) => trait_node.methods.remove(0),
+            other => panic!("expected a trait, got {:?}", other),
+        };
+        assert_eq!(method.name, "f");
+        assert_eq!(
+            method.generics,
+            vec![GenericParam::Type {
+                name: "T".to_string(),
+                bounds: vec![
+                    Bound::Closure {
+                        name: "Fn".to_string(),
+                        params: vec![],
+                        return_type: Some(Box::new(TypeNode::Simple("u8".to_string()))),
+                    },
+                    Bound::Trait(TypeNode::Simple("Send".to_string())),
+                ],
+                default: None,
+            }]
+        );
+        assert_eq!(method.params.len(), 2);
+        assert_eq!(method.params[1].param_type.display(), "T");
+        assert_eq!(method.return_type, None);
+    }
+
+    #[test]
+    fn test_parse_impl_with_associated_items() {
+        let input = r#"
+            impl Add for Meters {
+                type Output = Meters;
+                const ZERO: f64 = 0.0;
+
+                fn add(self, other: Meters) -> Meters {
+                    Meters(self.0 + other.0)
+                }
+            }
+        "#;
+
+        let expected = AstNode::Impl(ImplNode {
+            generics: vec![],
+            where_clause: vec![],
+            trait_type: Some(TypeNode::Simple("Add".to_string())),
+            self_type: TypeNode::Simple("Meters".to_string()),
+            is_unsafe: false,
+            is_negative: false,
+            items: vec![
+                ImplItem::Type(TypeAliasNode {
+                    visibility: Visibility::Private,
+                    name: "Output".to_string(),
+                    generics: vec![],
+                    aliased_type: TypeNode::Simple("Meters".to_string()),
+                    attributes: Vec::new(),
+                    doc: None,
+                }),
+                ImplItem::Const(ConstNode {
+                    visibility: Visibility::Private,
+                    name: "ZERO".to_string(),
+                    const_type: TypeNode::Simple("f64".to_string()),
+                    value: "0.0".to_string(),
+                    attributes: Vec::new(),
+                    doc: None,
+                }),
+                ImplItem::Method(MethodNode {
+                    name: "add".to_string(),
+                    params: vec![
+                        ParamNode {
+                            name: "self".to_string(),
+                            param_type: Box::new(TypeNode::Simple("self".to_string())),
+                        },
+                        ParamNode {
+                            name: "other".to_string(),
+                            param_type: Box::new(TypeNode::Simple("Meters".to_string())),
+                        },
+                    ],
+                    return_type: Some(Box::new(TypeNode::Simple("Meters".to_string()))),
+                    has_body: true,
+                    ..Default::default()
+                }),
+            ],
+            attributes: Vec::new(),
+            doc: None,
+        });
+
+        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_any_detects_each_kind() {
+        let cases = [
+            ("trait", "pub(crate) trait Shape { fn area(&self) -> f64; }"),
+            (
+                "struct",
+                "#[derive(Debug)]\nstruct Point { x: i32, y: i32 }",
+            ),
+            ("enum", "/// A direction\npub enum Direction { Up, Down }"),
+            ("type", "type Id = u64;"),
+            ("const", "const MAX: usize = 8;"),
+            ("static", "pub static NAME: &str = \"x\";"),
+            ("mod", "mod config;"),
+            ("use", "use std::fmt;"),
+            (
+                "impl",
+                "impl Point { fn new() -> Point { Point { x: 0, y: 0 } } }",
+            ),
+            ("extern", "extern \"C\" { fn abs(x: i32) -> i32; }"),
+            (
+                "macro_rules",
+                "#[macro_export]\nmacro_rules! square { ($x:expr) => { $x * $x }; }",
+            ),
+        ];
+        for (kind, input) in cases {
+            let ast = Parser::parse_any(input).unwrap();
+            let detected = match ast {
+                AstNode::Trait(_) => "trait",
+                AstNode::Struct(_) => "struct",
+                AstNode::Enum(_) => "enum",
+                AstNode::TypeAlias(_) => "type",
+                AstNode::Const(_) => "const",
+                AstNode::Static(_) => "static",
+                AstNode::Module(_) => "mod",
+                AstNode::Use(_) => "use",
+                AstNode::Impl(_) => "impl",
+                AstNode::Extern(_) | AstNode::Function(_) => "extern",
+                AstNode::MacroDef(_) => "macro_rules",
+                AstNode::Opaque(_) => "opaque",
+            };
+            assert_eq!(detected, kind, "input: {}", input);
+        }
+
+        let err = Parser::parse_any("pub fn main() {}").unwrap_err();
+        assert_eq!(err, ParseError::UnsupportedConstruct("fn".to_string()));
+        assert!(err.to_string().contains("trait, struct, enum"));
+    }
+
+    #[test]
+    fn test_type_depth() {
+        let depth = |ty: &str| {
+            Parser::parse_type(ty, ParseOptions::default().max_depth)
+                .unwrap()
+                .depth()
+        };
+        assert_eq!(depth("u8"), 1);
+        assert_eq!(depth("Vec<u8>"), 2);
+        assert_eq!(depth("HashMap<String, Vec<Option<u8>>>"), 4);
+
+        let input =
+            "pub struct Index { names: Vec<String>, lookup: HashMap<String, Vec<Option<u8>>> }";
+        assert_eq!(input.parse::<AstNode>().unwrap().max_type_depth(), 4);
+    }
+
+    #[test]
+    fn test_parse_block_doc_comments() {
+        let input = "/** multi\nline */\npub struct Point { x: i32 }";
+        match input.parse::<AstNode>().unwrap() {
+            AstNode::Struct(node) => assert_eq!(node.doc.as_deref(), Some("multi\nline")),
+            other => panic!("expected a struct, got {:?}", other),
+        }
+
+        let input = r#"
+            /*!
+             * Geometry types.
+             */
+
+            /// A point.
+            /**
+             * With a gutter:
+             *     indented
+             */
+            pub struct Point { x: i32 }
+        "#;
+        let file = Parser::parse_file(input).unwrap();
+        assert_eq!(file.header.doc.as_deref(), Some("Geometry types."));
+        match &file.items[0] {
+            AstNode::Struct(node) => assert_eq!(
+                node.doc.as_deref(),
+                Some("A point.\nWith a gutter:\n    indented")
+            ),
+            other => panic!("expected a struct, got {:?}", other),
+        }
+
+        let input = "/**\n\u{3000}wide\n  narrow\n*/ pub struct S { x: i32 }";
+        match Parser::parse(input).unwrap() {
+            AstNode::Struct(node) => assert_eq!(node.doc.as_deref(), Some("wide\n narrow")),
+            other => panic!("expected a struct, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_multibyte_input_does_not_panic() {
+        let seeds = [
+            "//! Header\n#![allow(dead_code)]\n/**\n * Doc\n */\npub struct Point<T = f64> { pub x: T, y: [u8; 4] }",
+            "#[repr(u8)] pub(in crate::net) enum E { A = 1, B(u8, (u8, u16)), C { x: i32 } }",
+            "pub trait Shape<'a>: Send { fn area(&self, k: &[f64]) -> impl Fn(u8) -> u8 { todo!() } }",
+            "extern \"C\" { fn abs(x: i32) -> i32; } pub extern \"C\" fn tick() {}",
+        ];
+        for seed in seeds {
+            for (i, _) in seed.char_indices() {
+                for c in ['\u{3000}', 'é', '😀'] {
+                    let mut input = seed.to_string();
+                    input.insert(i, c);
+                    let _ = Parser::parse_file(&input);
+                    let _ = Parser::parse_borrowed(&input);
+                }
+            }
+        }
+
+        match Parser::parse("/**\n\u{3000}a\n b\n*/ pub struct S { x: i32 }").unwrap() {
+            AstNode::Struct(node) => assert_eq!(node.doc.as_deref(), Some("a\nb")),
+            other => panic!("expected a struct, got {:?}", other),
+        }
+        assert!(Parser::parse_file("#!é[allow(dead_code)]").is_err());
+    }
+
+    #[test]
+    fn test_public_api_keeps_public_fields() {
+        let input = r#"
+            pub struct Account {
+                pub id: u64,
+                balance: i64,
+            }
+            struct Internal { pub cache: Vec<u8> }
+        "#;
+        let items = Parser::parse_many(input).unwrap();
+
+        let expected = vec![AstNode::Struct(StructNode {
+            visibility: Visibility::Public,
+            name: "Account".to_string(),
+            fields: vec![FieldNode {
+                visibility: Visibility::Public,
+                name: "id".to_string(),
+                field_type: Box::new(TypeNode::Simple("u64".to_string())),
+                attributes: Vec::new(),
+            }],
+            ..Default::default()
+        })];
+        assert_eq!(AstNode::public_api_of(&items), expected);
+        assert_eq!(items[1].public_api(), None);
+    }
+
+    #[test]
+    fn test_deeply_nested_type_hits_recursion_limit() {
+        let depth = 10_000;
+        let ty = format!("{}i32{}", "Vec<".repeat(depth), ">".repeat(depth));
+        let input = format!("pub struct Deep {{ value: {} }}", ty);
+
+        assert_eq!(Parser::parse_any(&input), Err(ParseError::RecursionLimit));
+        let modules = format!("{}{}", "mod m { ".repeat(depth), "}".repeat(depth));
+        assert_eq!(
+            Parser::parse_many(&modules),
+            Err(ParseError::RecursionLimit)
+        );
+
+        let options = ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        let shallow = "pub struct Shallow { value: Vec<i32> }";
+        assert!(Parser::parse_with_options(shallow, &options).is_ok());
+        let nested = "pub struct Nested { value: Vec<Vec<i32>> }";
+        assert_eq!(
+            Parser::parse_with_options(nested, &options),
+            Err(ParseError::RecursionLimit)
+        );
+    }
+
+    #[test]
+    fn test_unknown_items_as_opaque() {
+        let input = r#"
+            pub struct First { a: i32 }
+            union Bits {
+                int: u32,
+                float: f32,
+            }
+            pub struct Unit;
+            pub struct Second { b: i32 }
+        "#;
+        let parse = |on_unknown| {
+            let options = ParseOptions {
+                on_unknown,
+                ..ParseOptions::default()
+            };
+            Parser::parse_file_with_options(input, &options).map(|file| file.items)
+        };
+
+        let items = parse(OnUnknown::OpaqueItem).unwrap();
+        assert_eq!(items.len(), 4);
+        assert!(matches!(&items[0], AstNode::Struct(node) if node.name == "First"));
+        assert!(matches!(&items[1], AstNode::Opaque(source) if source.starts_with("union Bits {")));
+        assert_eq!(items[2], AstNode::Opaque("pub struct Unit;".to_string()));
+        assert!(matches!(&items[3], AstNode::Struct(node) if node.name == "Second"));
+
+        let skipped = parse(OnUnknown::Skip).unwrap();
+        assert_eq!(skipped, [items[0].clone(), items[3].clone()]);
+        assert_eq!(
+            parse(OnUnknown::Error),
+            Err(ParseError::UnsupportedConstruct("union".to_string()))
+        );
+
+        let nested = "pub struct Deep { value: Vec<Vec<u8>> }";
+        let options = ParseOptions {
+            max_depth: 2,
+            on_unknown: OnUnknown::OpaqueItem,
+        };
+        assert_eq!(
+            Parser::parse_file_with_options(nested, &options),
+            Err(ParseError::RecursionLimit)
+        );
+    }
+
+    #[test]
+    fn test_iterate_over_children() {
+        let input = "pub trait Shape { fn area(&self) -> f64; fn name(&self) -> String; }";
+        match input.parse::<AstNode>().unwrap() {
+            AstNode::Trait(node) => {
+                let names: Vec<&str> = node.into_iter().map(|m| m.name.as_str()).collect();
+                assert_eq!(names, ["area", "name"]);
+            }
+            other => panic!("expected a trait, got {:?}", other),
+        }
+
+        match "pub struct Point { x: i32, y: i32 }"
+            .parse::<AstNode>()
+            .unwrap()
+        {
+            AstNode::Struct(node) => {
+                let mut fields = Vec::new();
+                for field in &node {
+                    fields.push(field.name.as_str());
+                }
+                assert_eq!(fields, ["x", "y"]);
+            }
+            other => panic!("expected a struct, got {:?}", other),
+        }
+
+        match "pub enum Color { Red, Green, Blue }"
+            .parse::<AstNode>()
+            .unwrap()
+        {
+            AstNode::Enum(node) => assert_eq!((&node).into_iter().count(), 3),
+            other => panic!("expected an enum, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_where_clause_on_associated_types() {
+        let input = "pub trait Collect<T>: Iterator where Self::Item: Clone + Send, <T as IntoIterator>::Item: Debug { fn first(&self) -> Option<Self::Item>; }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let expected = vec![
+            WherePredicate {
+                subject: TypeNode::Simple("Self::Item".to_string()),
+                bounds: vec![
+                    Bound::Trait(TypeNode::Simple("Clone".to_string())),
+                    Bound::Trait(TypeNode::Simple("Send".to_string())),
+                ],
+            },
+            WherePredicate {
+                subject: TypeNode::Simple("<T as IntoIterator>::Item".to_string()),
+                bounds: vec![Bound::Trait(TypeNode::Simple("Debug".to_string()))],
+            },
+        ];
+        match &ast {
+            AstNode::Trait(node) => assert_eq!(node.where_clause, expected),
+            other => panic!("expected a trait, got {:?}", other),
+        }
+        assert!(ast.tree_string().starts_with(
+            "- Trait: Collect<T>: Iterator where Self::Item: Clone + Send, <T as IntoIterator>::Item: Debug\n"
+        ));
+    }
+
+    #[test]
+    fn test_methods_returning_type() {
+        let input = "pub trait Codec { fn encode(&self) -> String; fn name(&self) -> String; fn len(&self) -> usize; fn decode(s: &str) -> Option<Self>; }";
+        let AstNode::Trait(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a trait");
+        };
+
+        let names = |ty: &str| -> Vec<String> {
+            node.methods_returning_type(ty)
+                .iter()
+                .map(|m| m.name.clone())
+                .collect()
+        };
+        assert_eq!(names("String"), ["encode", "name"]);
+        assert_eq!(names("Option<Self>"), ["decode"]);
+        assert!(names("u8").is_empty());
+    }
+
+    #[test]
+    fn test_merge_impl_attaches_inherent_methods() {
+        let mut ast = "pub struct Counter { count: u32 }"
+            .parse::<AstNode>()
+            .unwrap();
+        let impl_block = r#"
+            impl Counter {
+                pub fn new() -> Self { Counter { count: 0 } }
+                fn count(&self) -> u32 { self.count }
+            }
+        "#;
+        let AstNode::Impl(impl_node) = impl_block.parse::<AstNode>().unwrap() else {
+            panic!("expected an impl block");
+        };
+
+        ast.merge_impl(&impl_node).unwrap();
+        match &ast {
+            AstNode::Struct(node) => {
+                let names: Vec<&str> = node
+                    .inherent_methods
+                    .iter()
+                    .map(|m| m.name.as_str())
+                    .collect();
+                assert_eq!(names, ["new", "count"]);
+            }
+            other => panic!("expected a struct, got {:?}", other),
+        }
+
+        let AstNode::Impl(other) = "impl Timer { fn start(&self) {} }"
+            .parse::<AstNode>()
+            .unwrap()
+        else {
+            panic!("expected an impl block");
+        };
+        assert_eq!(
+            ast.merge_impl(&other),
+            Err(MergeError::TypeMismatch {
+                expected: "Counter".to_string(),
+                found: "Timer".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_methods_taking_type() {
+        let input = r#"
+            pub trait Ledger {
+                fn credit(&mut self, account: &Account, amount: u64);
+                fn debit(&mut self, account: &mut Account, amount: u64) -> Result<u64, Error>;
+                fn merge(&mut self, totals: HashMap<String,u64>);
+                fn balance(&self, account: &Account) -> u64;
+            }
+        "#;
+        let AstNode::Trait(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a trait");
+        };
+
+        let names = |ty: &str| -> Vec<String> {
+            node.methods_taking_type(ty)
+                .iter()
+                .map(|m| m.name.clone())
+                .collect()
+        };
+        assert_eq!(names("&Account"), ["credit", "balance"]);
+        assert_eq!(names("&mut Account"), ["debit"]);
+        assert_eq!(names("u64"), ["credit", "debit"]);
+        assert_eq!(names("HashMap<String, u64>"), ["merge"]);
+        assert!(names("Account").is_empty());
+        assert!(names("Result<u64, Error>").is_empty());
+    }
+
+    #[test]
+    fn test_parse_closure_bound_with_params_and_return_type() {
+        let input = "pub trait Runner { fn run<F: Fn(i32, &str) -> bool>(&self, f: F); }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let method = match &ast {
+            AstNode::Trait(node) => &node.methods[0],
+            other => panic!("expected a trait, got {:?}", other),
+        };
+        assert_eq!(
+            method.generics,
+            vec![GenericParam::Type {
+                name: "F".to_string(),
+                bounds: vec![Bound::Closure {
+                    name: "Fn".to_string(),
+                    params: vec![
+                        TypeNode::Simple("i32".to_string()),
+                        TypeNode::Reference {
+                            lifetime: None,
+                            mutable: false,
+                            inner: Box::new(TypeNode::Simple("str".to_string()))
+                        },
+                    ],
+                    return_type: Some(Box::new(TypeNode::Simple("bool".to_string()))),
+                }],
+                default: None,
+            }]
+        );
+        assert!(ast
+            .tree_string()
+            .contains("Method: run<F: Fn(i32, &str) -> bool>"));
+
+        let grouped = "pub trait Runner { fn f<T: (FnMut(u8) -> u8) + Send>(&self, t: T); }";
+        assert!(grouped
+            .parse::<AstNode>()
+            .unwrap()
+            .tree_string()
+            .contains("Method: f<T: (FnMut(u8) -> u8) + Send>"));
+    }
+
+    #[test]
+    fn test_collect_all_method_names() {
+        let input = r#"
+            pub mod shapes {
+                pub trait Shape { fn area(&self) -> f64; fn name(&self) -> String; }
+                impl Circle { pub fn new(r: f64) -> Self { Circle { r } } }
+                pub struct Circle { r: f64 }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+        assert_eq!(ast.collect_all_method_names(), ["area", "name", "new"]);
+    }
+
+    #[test]
+    fn test_parse_multi_line_trait_method() {
+        let input = "pub trait Processor {
+            fn process(
+                &self,
+                input: &str,
+            ) -> Result<String, Box<dyn
+                Error>>;
+        }";
+        let method = match input.parse::<AstNode>().unwrap() {
+            AstNode::Trait(mut node) => node.methods.remove(0),
+            other => panic!("expected a trait, got {:?}", other),
+        };
+
+        assert_eq!(method.name, "process");
+        assert_eq!(method.params.len(), 2);
+        assert_eq!(method.params[1].param_type.display(), "&str");
+        assert_eq!(
+            method.return_type.unwrap().display(),
+            "Result<String, Box<dyn Error>>"
+        );
+    }
+
+    #[test]
+    fn test_parse_impl_trait_with_lifetime_bound() {
+        let input = "pub trait Numbers { fn iter(&self) -> impl Iterator<Item = u8> + '_; }";
+        let return_type = match input.parse::<AstNode>().unwrap() {
+            AstNode::Trait(mut node) => node.methods.remove(0).return_type.unwrap(),
+            other => panic!("expected a trait, got {:?}", other),
+        };
+
+        assert_eq!(
+            *return_type,
+            TypeNode::ImplTrait(vec![
+                Bound::Trait(TypeNode::Generic {
+                    name: "Iterator".to_string(),
+                    args: vec![TypeNode::Simple("Item = u8".to_string())],
+                }),
+                Bound::Lifetime("'_".to_string()),
+            ])
+        );
+        assert_eq!(return_type.display(), "impl Iterator<Item = u8> + '_");
+    }
+
+    #[test]
+    fn test_parse_trait_body() {
+        let body = "
+            fn area(&self) -> f64;
+            fn scale(&self, factor: f64);
+        ";
+        let methods = Parser::parse_trait_body(body).unwrap();
+
+        let names: Vec<&str> = methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["area", "scale"]);
+        assert_eq!(methods[1].params[1].param_type.display(), "f64");
+        assert!(Parser::parse_trait_body("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_method_signature() {
+        let method = Parser::parse_method_signature("fn contains(&self, x: i32) -> bool;").unwrap();
+        assert_eq!(method.name, "contains");
+        assert_eq!(method.params.len(), 2);
+        assert_eq!(method.params[1].param_type.display(), "i32");
+        assert_eq!(method.return_type.unwrap().display(), "bool");
+
+        let method = Parser::parse_method_signature("fn clear(&self)").unwrap();
+        assert_eq!(method.name, "clear");
+        assert!(method.return_type.is_none());
+    }
+
+    #[test]
+    fn test_parse_generic_method_signature() {
+        let method =
+            Parser::parse_method_signature("fn insert<T: Into<String>>(&self, value: T)").unwrap();
+        assert_eq!(method.name, "insert");
+        assert_eq!(display_generics(&method.generics), "<T: Into<String>>");
+        assert_eq!(method.params[1].param_type.display(), "T");
+    }
+
+    #[test]
+    fn test_parse_struct_body() {
+        let fields = Parser::parse_struct_body("x: i32, pub y: Vec<f64>,").unwrap();
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "x");
+        assert_eq!(fields[0].field_type.display(), "i32");
+        assert_eq!(fields[1].visibility, Visibility::Public);
+        assert_eq!(fields[1].field_type.display(), "Vec<f64>");
+    }
+
+    #[test]
+    fn test_parse_grouped_reference_to_trait_object() {
+        let read_send = TypeNode::DynTrait(vec![
+            Bound::Trait(TypeNode::Simple("Read".to_string())),
+            Bound::Trait(TypeNode::Simple("Send".to_string())),
+        ]);
+        let expected = TypeNode::Reference {
+            lifetime: None,
+            mutable: false,
+            inner: Box::new(read_send),
+        };
+
+        let max_depth = ParseOptions::default().max_depth;
+        let grouped = Parser::parse_type("&(dyn Read + Send)", max_depth).unwrap();
+        assert_eq!(grouped, expected);
+        assert_eq!(grouped.display(), "&(dyn Read + Send)");
+        assert_eq!(
+            Parser::parse_type("&dyn Read + Send", max_depth).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_body() {
+        let colors = Parser::parse_enum_body("Red, Green, Blue").unwrap();
+        let names: Vec<&str> = colors.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["Red", "Green", "Blue"]);
+        assert!(colors.iter().all(|v| v.associated_data.is_none()));
+
+        let options = Parser::parse_enum_body("Some(T), None").unwrap();
+        assert_eq!(options.len(), 2);
+        match options[0].associated_data.as_deref() {
+            Some(AstNode::Struct(data)) => assert_eq!(data.fields[0].field_type.display(), "T"),
+            other => panic!("expected tuple data, got {:?}", other),
+        }
+        assert!(options[1].associated_data.is_none());
+    }
+
+    #[test]
+    fn test_is_primitive() {
+        let max_depth = ParseOptions::default().max_depth;
+        let is_primitive = |ty: &str| Parser::parse_type(ty, max_depth).unwrap().is_primitive();
+
+        assert!(is_primitive("i32"));
+        assert!(is_primitive("bool"));
+        assert!(!is_primitive("String"));
+        assert!(!is_primitive("Vec<u8>"));
+        assert!(!is_primitive("&str"));
+    }
+
+    #[test]
+    fn test_method_signature() {
+        let signatures = [
+            "fn process(&self, input: &str) -> String",
+            "pub fn new<T: Into<String>>(name: T) -> Self",
+            "fn clear(self)",
+            "fn boxed(self: Box<Self>) -> usize",
+            "async fn flush(&self)",
+        ];
+        for signature in signatures {
+            let method = Parser::parse_method_signature(signature).unwrap();
+            assert_eq!(method.signature(), signature);
+        }
+        let input = "pub trait Visualizer { fn visualize(&self, data: &[u8]); fn process(&self, input: &str) -> String; }";
+        let AstNode::Trait(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a trait");
+        };
+        let signatures: Vec<String> = node.methods.iter().map(|m| m.signature()).collect();
+        assert_eq!(
+            signatures,
+            [
+                "fn visualize(&self, data: &[u8])",
+                "fn process(&self, input: &str) -> String"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repr_of_struct_and_enum() {
+        let point = "#[derive(Debug)]\n#[repr(C)]\npub struct Point { x: f64, y: f64 }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(point.repr(), Some(Repr::C));
+
+        let opcode = "#[repr(u8)] pub enum Opcode { Nop, Halt }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(opcode.repr(), Some(Repr::Int("u8".to_string())));
+    }
+
+    #[test]
+    fn test_combined_repr() {
+        let header = "#[repr(C, packed)] #[repr(align(8))] pub struct Header { len: u32 }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            header.repr(),
+            Some(Repr::Combined(vec![
+                Repr::C,
+                Repr::Packed(None),
+                Repr::Align(8)
+            ]))
+        );
+        let plain = "pub struct Plain { len: u32 }".parse::<AstNode>().unwrap();
+        assert_eq!(plain.repr(), None);
+    }
+
+    #[test]
+    fn test_field_to_source_string() {
+        let field = FieldNode {
+            visibility: Visibility::Private,
+            name: "x".to_string(),
+            field_type: Box::new(TypeNode::Simple("f64".to_string())),
+            attributes: Vec::new(),
+        };
+        assert_eq!(field.to_source_string(), "x: f64");
+
+        let body = "pub name: String, tags: Vec<&str>";
+        let fields = Parser::parse_struct_body(body).unwrap();
+        let source: Vec<String> = fields.iter().map(|f| f.to_source_string()).collect();
+        assert_eq!(source.join(", "), body);
+    }
+
+    #[test]
+    fn test_parse_raw_string_const() {
+        let input = r##"
+            const MSG: &str = r#"a "quoted"; string"#;
+            const PATH: &str = r"C:\";
+        "##;
+        let items = Parser::parse_many(input).unwrap();
+
+        assert_eq!(items.len(), 2);
+        match &items[0] {
+            AstNode::Const(node) => assert_eq!(node.value, r##"r#"a "quoted"; string"#"##),
+            other => panic!("expected a const, got {:?}", other),
+        }
+        match &items[1] {
+            AstNode::Const(node) => assert_eq!(node.value, r#"r"C:\""#),
+            other => panic!("expected a const, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_byte_string_const() {
+        let input = r##"
+            const B: &[u8] = b"a;\"b";
+            const RAW: &[u8] = br#"}"#;
+        "##;
+        let items = Parser::parse_many(input).unwrap();
+
+        assert_eq!(items.len(), 2);
+        match &items[0] {
+            AstNode::Const(node) => {
+                assert_eq!(node.name, "B");
+                assert_eq!(node.value, r#"b"a;\"b""#);
+            }
+            other => panic!("expected a const, got {:?}", other),
+        }
+        match &items[1] {
+            AstNode::Const(node) => assert_eq!(node.value, r##"br#"}"#"##),
+            other => panic!("expected a const, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_variant_to_source_string() {
+        let variants =
+            Parser::parse_enum_body("Red, Write(String), Move { x: i32, y: i32 }").unwrap();
+        let source: Vec<String> = variants.iter().map(|v| v.to_source_string()).collect();
+        assert_eq!(source, ["Red", "Write(String)", "Move { x: i32, y: i32 }"]);
+    }
+
+    #[test]
+    fn test_explain_diff_names_field_with_different_type() {
+        let left = "pub struct Point { x: f64, y: f64 }"
+            .parse::<AstNode>()
+            .unwrap();
+        let right = "pub struct Point { x: f64, y: i32, z: f64 }"
+            .parse::<AstNode>()
+            .unwrap();
+
+        assert_eq!(
+            left.explain_diff(&right),
+            "Point.y: type `f64` on the left, `i32` on the right\nPoint.z: only on the right"
+        );
+        assert_eq!(left.explain_diff(&left), "");
+    }
+
+    #[test]
+    fn test_explain_diff_in_module() {
+        let left = "mod shapes { pub trait Shape { fn area(&self) -> f64; } pub enum Kind { Circle(f64) } }"
+            .parse::<AstNode>()
+            .unwrap();
+        let right = "mod shapes { pub trait Shape { fn area(&self) -> f32; } pub struct Kind {} }"
+            .parse::<AstNode>()
+            .unwrap();
+
+        let expected = "\
+shapes::Shape.area: signature `fn area(&self) -> f64` on the left, `fn area(&self) -> f32` on the right
+shapes::Kind: enum on the left, struct on the right";
+        assert_eq!(left.explain_diff(&right), expected);
+    }
+
+    #[test]
+    fn test_merge_structs() {
+        let position = "pub struct Point { x: f64, y: f64 }"
+            .parse::<AstNode>()
+            .unwrap();
+        let height = "pub struct Point { z: f64 }".parse::<AstNode>().unwrap();
+
+        let merged = position.merge(&height).unwrap();
+        let AstNode::Struct(node) = &merged else {
+            panic!("expected a struct, got {:?}", merged);
+        };
+        let names: Vec<&str> = node.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["x", "y", "z"]);
+
+        assert_eq!(
+            position.merge(&position),
+            Err(MergeError::DuplicateMember("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_merge_traits_and_incompatible_kinds() {
+        let reader = "pub trait Io { fn read(&self) -> u8; }"
+            .parse::<AstNode>()
+            .unwrap();
+        let writer = "pub trait Io { fn write(&self, b: u8); }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(
+            reader.merge(&writer).unwrap().collect_all_method_names(),
+            ["read", "write"]
+        );
+
+        let point = "pub struct Io { x: f64 }".parse::<AstNode>().unwrap();
+        assert_eq!(reader.merge(&point), Err(MergeError::IncompatibleKinds));
+    }
+
+    #[test]
+    fn test_parse_async_trait_method() {
+        let input =
+            "pub trait Client { async fn fetch(&self, url: &str) -> String; fn close(&self); }";
+        let AstNode::Trait(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a trait");
+        };
+
+        assert_eq!(node.methods.len(), 2);
+        assert_eq!(node.methods[0].name, "fetch");
+        assert!(node.methods[0].is_async);
+        assert_eq!(
+            node.methods[0].return_type.as_ref().unwrap().display(),
+            "String"
+        );
+        assert!(!node.methods[1].is_async);
+        assert_eq!(
+            node.methods[0].signature(),
+            "async fn fetch(&self, url: &str) -> String"
+        );
+    }
+
+    #[test]
+    fn test_subtract_struct_fields() {
+        let point = "pub struct Point { x: f64, y: f64, z: f64 }"
+            .parse::<AstNode>()
+            .unwrap();
+        let height = "pub struct Point { z: f64 }".parse::<AstNode>().unwrap();
+
+        let flat = point.subtract(&height).unwrap();
+        assert_eq!(
+            flat,
+            "pub struct Point { x: f64, y: f64 }"
+                .parse::<AstNode>()
+                .unwrap()
+        );
+        assert_eq!(
+            flat.subtract(&height),
+            Err(SubtractError::MissingMember("z".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_subtract_undoes_merge() {
+        let reader = "pub trait Io { fn read(&self) -> u8; }"
+            .parse::<AstNode>()
+            .unwrap();
+        let writer = "pub trait Io { fn write(&self, b: u8); }"
+            .parse::<AstNode>()
+            .unwrap();
+
+        let merged = reader.merge(&writer).unwrap();
+        assert_eq!(merged.subtract(&writer), Ok(reader));
+    }
+
+    #[test]
+    fn test_member_accessors() {
+        let input = r#"
+            pub struct Point { x: f64, y: f64 }
+            pub enum Shape { Circle(f64), Square(f64) }
+            pub trait Area { fn area(&self) -> f64; fn unit(&self) -> String; }
+        "#;
+        let items = Parser::parse_many(input).unwrap();
+        let (AstNode::Struct(point), AstNode::Enum(shape), AstNode::Trait(area)) =
+            (&items[0], &items[1], &items[2])
+        else {
+            panic!("unexpected items: {:?}", items);
+        };
+
+        assert_eq!(point.field(1).map(|f| f.name.as_str()), Some("y"));
+        assert_eq!(shape.variant(1).map(|v| v.name.as_str()), Some("Square"));
+        assert_eq!(area.method(0).map(|m| m.name.as_str()), Some("area"));
+        assert!(point.field(2).is_none());
+        assert!(area.method(2).is_none());
+
+        assert_eq!(point.field_named("x"), point.field(0));
+        assert_eq!(shape.variant_named("Circle"), shape.variant(0));
+        assert_eq!(area.method_named("unit"), area.method(1));
+        assert!(shape.variant_named("Triangle").is_none());
+
+        assert_eq!(point.field_count(), 2);
+        assert_eq!(area.method_count(), 2);
+    }
+
+    #[test]
+    fn test_intersection_keeps_common_members() {
+        let old = "pub struct Config { name: String, retries: u8, verbose: bool }"
+            .parse::<AstNode>()
+            .unwrap();
+        let new = "pub struct Config { verbose: bool, name: String, timeout: u64 }"
+            .parse::<AstNode>()
+            .unwrap();
+
+        assert_eq!(
+            old.intersection(&new),
+            "pub struct Config { name: String, verbose: bool }"
+                .parse::<AstNode>()
+                .unwrap()
+        );
+
+        let v1 = "pub trait Api { fn get(&self); fn put(&self); }"
+            .parse::<AstNode>()
+            .unwrap();
+        let v2 = "pub trait Api { fn get(&self); fn delete(&self); }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(v1.intersection(&v2).collect_all_method_names(), ["get"]);
+        assert_eq!(
+            v1.intersection(&old).collect_all_method_names(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_parse_field_attributes() {
+        let input = r#"
+            pub struct User {
+                #[serde(skip)]
+                #[cfg(all(unix, feature = "cache"))] cache: Vec<u8>,
+                pub name: String,
+            }
+        "#;
+        let AstNode::Struct(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a struct");
+        };
+
+        assert_eq!(node.fields.len(), 2);
+        assert_eq!(node.fields[0].name, "cache");
+        assert_eq!(
+            node.fields[0].attributes,
+            [
+                Attribute {
+                    path: "serde".to_string(),
+                    args: Some("skip".to_string()),
+                },
+                Attribute {
+                    path: "cfg".to_string(),
+                    args: Some(r#"all(unix, feature = "cache")"#.to_string()),
+                },
+            ]
+        );
+        assert_eq!(node.fields[1].visibility, Visibility::Public);
+        assert!(node.fields[1].attributes.is_empty());
+    }
+
+    #[test]
+    fn test_parse_variant_attributes() {
+        let variants =
+            Parser::parse_enum_body("#[deprecated] Old, #[serde(rename = \"n\")] New(u8)").unwrap();
+
+        assert_eq!(variants[0].name, "Old");
+        assert_eq!(variants[0].attributes[0].path, "deprecated");
+        assert_eq!(variants[1].name, "New");
+        assert_eq!(variants[1].to_source_string(), "New(u8)");
+        assert_eq!(
+            variants[1].attributes[0].args.as_deref(),
+            Some("rename = \"n\"")
+        );
+    }
+
+    #[test]
+    fn test_monomorphize_struct() {
+        let input = "pub struct Pair<T, U> where T: Clone { first: T, second: Option<&U> }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let max_depth = ParseOptions::default().max_depth;
+        let bindings = HashMap::from([
+            ("T".to_string(), TypeNode::Simple("u32".to_string())),
+            (
+                "U".to_string(),
+                Parser::parse_type("Vec<u8>", max_depth).unwrap(),
+            ),
+        ]);
+        let expected = "pub struct Pair where u32: Clone { first: u32, second: Option<&Vec<u8>> }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(ast.monomorphize(&bindings), expected);
+    }
+
+    #[test]
+    fn test_monomorphize_keeps_unbound_params() {
+        let input = "pub trait Store<K, V> { fn get(&self, key: K) -> Option<V>; }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let bindings = HashMap::from([("K".to_string(), TypeNode::Simple("String".to_string()))]);
+        let expected = "pub trait Store<V> { fn get(&self, key: String) -> Option<V>; }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(ast.monomorphize(&bindings), expected);
+    }
+
+    #[test]
+    fn test_type_map() {
+        let max_depth = ParseOptions::default().max_depth;
+        let ty = Parser::parse_type("HashMap<String, Vec<i32>>", max_depth).unwrap();
+
+        let widened = ty.map(&|t| match t {
+            TypeNode::Simple(name) if name == "i32" => TypeNode::Simple("i64".to_string()),
+            other => other.clone(),
+        });
+        assert_eq!(
+            widened,
+            Parser::parse_type("HashMap<String, Vec<i64>>", max_depth).unwrap()
+        );
+
+        let optional = ty.map(&|t| match t {
+            TypeNode::Simple(_) => TypeNode::Generic {
+                name: "Option".to_string(),
+                args: vec![t.clone()],
+            },
+            other => other.clone(),
+        });
+        assert_eq!(
+            optional.display(),
+            "HashMap<Option<String>, Vec<Option<i32>>>"
+        );
+    }
+
+    #[test]
+    fn test_is_object_safe() {
+        let is_object_safe = |input: &str| match input.parse::<AstNode>().unwrap() {
+            AstNode::Trait(node) => node.is_object_safe(),
+            other => panic!("expected a trait, got {:?}", other),
+        };
+
+        assert!(is_object_safe(
+            "pub trait Draw { fn draw(&self, scale: f64) -> Vec<u8>; fn item(&self) -> Self::Item; }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Dup { fn dup(&self) -> Option<Self>; }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Visit { fn visit<V>(&self, v: V); }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Feed { fn feed(&self, f: impl Fn(u8)); }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Build where Self: Sized { fn build(&self); }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Make: Clone + Sized { fn make(&self); }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Close { fn name(&self) -> String; fn close(self); }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Consume { fn consume(mut self); }"
+        ));
+        assert!(is_object_safe(
+            "pub trait Release { fn release(self: Box<Self>); fn reset(&mut self); }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Named { fn name(&self) -> String; fn default_name() -> String; }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Count { fn count(&self) -> u8 where Self: Sized; }"
+        ));
+        assert!(!is_object_safe(
+            "pub trait Flush { fn flush(&self) where Self: Sized + Send; }"
+        ));
+        assert!(is_object_safe(
+            "pub trait Emit { fn emit(&self) -> u8 where u8: Copy; }"
+        ));
+
+        let method =
+            Parser::parse_method_signature("fn count(&self) -> u8 where Self: Sized").unwrap();
+        assert_eq!(
+            method.return_type.as_deref(),
+            Some(&TypeNode::Simple("u8".to_string()))
+        );
+        assert_eq!(method.where_clause.len(), 1);
+        assert_eq!(
+            method.signature(),
+            "fn count(&self) -> u8 where Self: Sized"
+        );
+        let method = Parser::parse_method_signature("fn flush(&self) where Self: Sized;").unwrap();
+        assert_eq!(method.return_type, None);
+        assert_eq!(method.where_clause[0].bounds.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_lifetime_and_trait_supertraits() {
+        let input = "pub trait Foo<'a>: 'a + Clone { fn get(&self) -> &str; }";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let AstNode::Trait(node) = &ast else {
+            panic!("expected a trait, got {:?}", ast);
+        };
+        assert_eq!(
+            node.supertraits,
+            [
+                Bound::Lifetime("'a".to_string()),
+                Bound::Trait(TypeNode::Simple("Clone".to_string())),
+            ]
+        );
+        assert!(ast
+            .tree_string()
+            .starts_with("- Trait: Foo<'a>: 'a + Clone\n"));
+    }
+
+    #[test]
+    fn test_unresolved_const_args() {
+        let input = r#"
+            pub struct Matrix<T, const N: usize> {
+                data: [T; N],
+                scratch: [u8; M],
+                rows: Vec<[T; N]>,
+                padded: Buffer<{ N + 1 }, 4>,
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+        let AstNode::Struct(node) = &ast else {
+            panic!("expected a struct, got {:?}", ast);
+        };
+        assert_eq!(
+            *node.fields[0].field_type,
+            TypeNode::Generic {
+                name: "[]".to_string(),
+                args: vec![
+                    TypeNode::Simple("T".to_string()),
+                    TypeNode::Simple("N".to_string())
+                ],
+            }
+        );
+
+        assert_eq!(ast.unresolved_const_args(), ["M"]);
+
+        let method =
+            "pub trait Fill { fn fill<const K: usize>(&self, out: &mut [u8; K]) -> [u8; LEN]; }";
+        let ast = method.parse::<AstNode>().unwrap();
+        assert_eq!(ast.unresolved_const_args(), ["LEN"]);
+    }
+
+    #[test]
+    fn test_to_compact_string() {
+        let cases = [
+            (
+                "pub struct Point {\n    x: f64,\n    y: f64,\n}",
+                "struct Point { x: f64, y: f64 }",
+            ),
+            (
+                "pub trait Shape: Debug { fn area(&self) -> f64; }",
+                "trait Shape: Debug { fn area(&self) -> f64 }",
+            ),
+            (
+                "enum Message { Quit, Write(String) }",
+                "enum Message { Quit, Write(String) }",
+            ),
+            ("pub use std::fmt;", "use std::fmt"),
+            ("pub struct Empty {}", "struct Empty"),
+        ];
+        for (input, expected) in cases {
+            let ast = input.parse::<AstNode>().unwrap();
+            assert_eq!(ast.to_compact_string(), expected, "input: {}", input);
+        }
+
+        let ast =
+            "pub trait Store { fn get(&self, key: &str) -> Option<String>; fn clear(&self); }"
+                .parse::<AstNode>()
+                .unwrap();
+        let summary = ast.to_compact_string_with_width(30);
+        assert_eq!(summary, "trait Store { fn get(&self,...");
+        assert_eq!(summary.chars().count(), 30);
+    }
+
+    #[test]
+    fn test_write_tree_to() {
+        let ast = "pub trait Shape { fn area(&self) -> f64; }"
+            .parse::<AstNode>()
+            .unwrap();
+        let mut out = Vec::new();
+        ast.write_tree_to(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        if cfg!(feature = "ansi") {
+            assert!(out.starts_with("- \x1b[36mTrait:\x1b[0m Shape\n"));
+            assert!(out.contains("└── \x1b[32mMethod:\x1b[0m area\n"));
+        } else {
+            assert_eq!(out, ast.tree_string());
+        }
+    }
+
+    #[test]
+    fn test_parse_extern_block() {
+        let input = r#"
+            extern "C" {
+                fn malloc(size: usize) -> *mut u8;
+                #[link_name = "free"]
+                pub fn release(ptr: *mut u8);
+            }
+        "#;
+        let AstNode::Extern(block) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected an extern block");
+        };
+        assert_eq!(block.abi, "C");
+        let signatures: Vec<String> = block
+            .functions
+            .iter()
+            .map(|f| f.to_signature_string())
+            .collect();
+        assert_eq!(
+            signatures,
+            [
+                "extern \"C\" fn malloc(size: usize) -> *mut u8",
+                "pub extern \"C\" fn release(ptr: *mut u8)"
+            ]
+        );
+
+        for (input, abi) in [
+            ("extern \"system\" { fn GetLastError() -> u32; }", "system"),
+            ("extern { fn abort(); }", "C"),
+        ] {
+            match input.parse::<AstNode>().unwrap() {
+                AstNode::Extern(block) => {
+                    assert_eq!(block.abi, abi);
+                    assert!(block.functions.iter().all(|f| f.abi == abi));
+                }
+                other => panic!("expected an extern block, got {:?}", other),
+            }
+        }
+        assert_eq!(
+            Parser::parse_any("extern crate alloc;"),
+            Err(ParseError::UnsupportedConstruct("extern".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_extern_function() {
+        let input = r#"
+            #[no_mangle]
+            pub extern "C" fn on_event(code: i32, data: *const u8) -> bool {
+                code > 0
+            }
+        "#;
+        let AstNode::Function(function) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a function");
+        };
+        assert_eq!(function.abi, "C");
+        assert!(function.signature.has_body);
+        assert_eq!(
+            function.to_signature_string(),
+            "pub extern \"C\" fn on_event(code: i32, data: *const u8) -> bool"
+        );
+
+        let input = r#"
+            extern fn tick() {}
+            unsafe extern "system" fn hook(id: u32) -> u32 { id }
+            unsafe extern "C" { fn abs(x: i32) -> i32; }
+        "#;
+        let items = Parser::parse_many(input).unwrap();
+        let summaries: Vec<String> = items.iter().map(|item| item.compact_summary()).collect();
+        assert_eq!(
+            summaries,
+            [
+                "extern \"C\" fn tick()",
+                "extern \"system\" fn hook(id: u32) -> u32",
+                "extern \"C\" { fn abs(x: i32) -> i32 }"
+            ]
+        );
+        assert_eq!(
+            Parser::parse_any("pub fn main() {}"),
+            Err(ParseError::UnsupportedConstruct("fn".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_method_receiver_and_arity() {
+        let cases = [
+            (
+                "fn process(&self, input: &str) -> String",
+                Some(Receiver::RefSelf),
+                1,
+            ),
+            (
+                "fn push(&'a mut self, value: u8)",
+                Some(Receiver::RefMutSelf),
+                1,
+            ),
+            ("fn finish(mut self)", Some(Receiver::SelfValue), 0),
+            (
+                "fn boxed(self: Box<Self>, depth: usize)",
+                Some(Receiver::Typed(TypeNode::Generic {
+                    name: "Box".to_string(),
+                    args: vec![TypeNode::Simple("Self".to_string())],
+                })),
+                1,
+            ),
+            ("fn new(name: String, id: u32) -> Self", None, 2),
+        ];
+        for (signature, receiver, arity) in cases {
+            let method = Parser::parse_method_signature(signature).unwrap();
+            assert_eq!(method.receiver(), receiver, "signature: {}", signature);
+            assert_eq!(method.arity(), arity, "signature: {}", signature);
+            assert_eq!(method.signature(), signature);
+        }
+    }
+
+    #[test]
+    fn test_all_nodes() {
+        let input = r#"
+            pub mod shapes {
+                pub enum Shape { Circle(f64), Rect { w: f64, h: f64 }, Empty }
+                pub mod util {
+                    pub struct Point { x: f64, y: f64 }
+                }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+
+        let kinds: Vec<&str> = ast.all_nodes().into_iter().map(item_kind).collect();
+        assert_eq!(
+            kinds,
+            ["module", "enum", "struct", "struct", "module", "struct"]
+        );
+        assert_eq!(ast.all_nodes()[0], &ast);
+    }
+
+    #[test]
+    fn test_malformed_input_returns_errors() {
+        for input in [
+            "pub enum E { Foo( }",
+            "pub enum E { Bar) }",
+            "pub enum E { A(((((, B }",
+            "pub enum E { Point { x: i32 ) }",
+            "pub trait T { ( }",
+        ] {
+            assert!(Parser::parse(input).is_err(), "{}", input);
+            assert!(Parser::parse_borrowed(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_enum_variants_with_discriminants_and_raw_names() {
+        let input = "pub enum E { r#type, Code = 2, Pair((u8, u8)), Tagged(u8) = 1 << 3 }";
+        let AstNode::Enum(node) = Parser::parse(input).unwrap() else {
+            panic!("expected an enum");
+        };
+        let names: Vec<&str> = node.variants.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["r#type", "Code", "Pair", "Tagged"]);
+        let discriminants: Vec<Option<&str>> = node
+            .variants
+            .iter()
+            .map(|v| v.discriminant.as_deref())
+            .collect();
+        assert_eq!(discriminants, [None, Some("2"), None, Some("1 << 3")]);
+        assert_eq!(node.variants[3].to_source_string(), "Tagged(u8) = 1 << 3");
+        assert_eq!(
+            AstNode::Enum(node).compact_summary(),
+            "enum E { r#type, Code = 2, Pair((u8, u8)), Tagged(u8) = 1 << 3 }"
+        );
+        assert!(Parser::parse("enum E { A = }").is_err());
+    }
+
+    #[test]
+    fn test_ast_node_depth() {
+        let depth = |input: &str| input.parse::<AstNode>().unwrap().depth();
+        assert_eq!(depth("pub struct Point { x: f64, y: f64 }"), 1);
+        assert_eq!(depth("pub enum Status { Active, Suspended }"), 1);
+        assert_eq!(
+            depth("pub enum Shape { Empty, Rect { w: f64, h: f64 } }"),
+            2
+        );
+        assert_eq!(
+            depth("pub mod shapes { pub enum Shape { Circle(f64) } pub struct Unit {} }"),
+            3
+        );
+    }
+
+    #[test]
+    fn test_trait_default_method_with_nested_braces() {
+        let input = r#"
+            pub trait Run {
+                fn run(&self) { if true { foo() } else { bar() } }
+                fn code(&self) -> u8 {
+                    match self.kind() { Kind::A => { log("}"); 1 } _ => 2 }
+                }
+                fn stop(&self);
+            }
+        "#;
+        let AstNode::Trait(node) = Parser::parse(input).unwrap() else {
+            panic!("expected a trait");
+        };
+
+        let names: Vec<&str> = node.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["run", "code", "stop"]);
+        assert_eq!(node.methods[0].return_type, None);
+        assert_eq!(
+            node.methods[1].return_type.as_deref(),
+            Some(&TypeNode::Simple("u8".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_try_from_str_and_string() {
+        let input = "pub struct Point { x: f64, y: f64 }";
+        let ast = AstNode::try_from(input).unwrap();
+        assert_eq!(ast, input.parse::<AstNode>().unwrap());
+        assert_eq!(AstNode::try_from(input.to_string()).unwrap(), ast);
+        assert!(AstNode::try_from("pub struct Point { x f64 }").is_err());
+    }
+
+    #[test]
+    fn test_parse_unsafe_and_negative_impls() {
+        let AstNode::Impl(node) =
+            Parser::parse("unsafe impl<T: Send> Send for Handle<T> {}").unwrap()
+        else {
+            panic!("expected an impl");
+        };
+        assert!(node.is_unsafe && !node.is_negative);
+        assert_eq!(node.trait_type, Some(TypeNode::Simple("Send".to_string())));
+
+        let ast = Parser::parse("impl !Sync for Cell {}").unwrap();
+        let AstNode::Impl(node) = &ast else {
+            panic!("expected an impl");
+        };
+        assert!(node.is_negative && !node.is_unsafe);
+        assert_eq!(node.trait_type, Some(TypeNode::Simple("Sync".to_string())));
+        assert_eq!(ast.to_compact_string(), "impl !Sync for Cell { }");
+
+        assert!(Parser::parse("impl !Cell {}").is_err());
+    }
+
+    #[test]
+    fn test_parse_macro_rules_keeps_body() {
+        let input = r#"
+            macro_rules! hashmap {
+                ($($key:expr => $value:expr),* $(,)?) => {{
+                    let mut map = HashMap::new();
+                    $(map.insert($key, $value);)*
+                    map
+                }};
+            }
+            pub struct Point { x: i32, y: i32 }
+        "#;
+        let items = Parser::parse_many(input).unwrap();
+        assert_eq!(items.len(), 2);
+        let AstNode::MacroDef(node) = &items[0] else {
+            panic!("expected a macro definition");
+        };
+        assert_eq!(node.name, "hashmap");
+        assert!(node
+            .body
+            .starts_with("($($key:expr => $value:expr),* $(,)?) => {{"));
+        assert!(node.body.ends_with("}};"));
+        assert_eq!(items[0].tree_string(), "- Macro: hashmap\n");
+
+        assert!(Parser::parse("macro_rules! { () => {} }").is_err());
+    }
+
+    #[test]
+    fn test_attributes_and_docs_on_other_items() {
+        let input = r#"
+            /// Builds a map.
+            #[macro_export]
+            macro_rules! map { () => {} }
+
+            #[no_mangle]
+            pub extern "C" fn tick() {}
+
+            /// The largest value.
+            pub const MAX: u8 = 255;
+
+            /// Helpers.
+            #[cfg(test)]
+            mod helpers {}
+
+            #[link(name = "m")]
+            extern "C" {
+                /// Absolute value.
+                #[link_name = "abs"]
+                fn absolute(x: i32) -> i32;
+            }
+        "#;
+        let items = Parser::parse_many(input).unwrap();
+        let attribute = |path: &str, args: Option<&str>| Attribute {
+            path: path.to_string(),
+            args: args.map(str::to_string),
+        };
+
+        let AstNode::MacroDef(node) = &items[0] else {
+            panic!("expected a macro definition");
+        };
+        assert_eq!(node.attributes, [attribute("macro_export", None)]);
+        assert_eq!(node.doc.as_deref(), Some("Builds a map."));
+        let AstNode::Function(node) = &items[1] else {
+            panic!("expected a function");
+        };
+        assert_eq!(node.attributes, [attribute("no_mangle", None)]);
+        assert_eq!(node.doc, None);
+        let AstNode::Const(node) = &items[2] else {
+            panic!("expected a const");
+        };
+        assert!(node.attributes.is_empty());
+        assert_eq!(node.doc.as_deref(), Some("The largest value."));
+        let AstNode::Module(node) = &items[3] else {
+            panic!("expected a module");
+        };
+        assert_eq!(node.attributes, [attribute("cfg", Some("test"))]);
+        assert_eq!(node.doc.as_deref(), Some("Helpers."));
+        let AstNode::Extern(node) = &items[4] else {
+            panic!("expected an extern block");
+        };
+        assert_eq!(node.attributes, [attribute("link", Some("name = \"m\""))]);
+        assert_eq!(
+            node.functions[0].attributes,
+            [attribute("link_name", Some("\"abs\""))]
+        );
+        assert_eq!(node.functions[0].doc.as_deref(), Some("Absolute value."));
+    }
+
+    #[test]
+    fn test_parse_negative_bound() {
+        let input = "pub struct Guard<T: !Send + Clone> where T: !Sync { value: T }";
+        let AstNode::Struct(node) = Parser::parse(input).unwrap() else {
+            panic!("expected a struct");
+        };
+        assert_eq!(
+            node.generics[0],
+            GenericParam::Type {
+                name: "T".to_string(),
+                bounds: vec![
+                    Bound::Negative(TypeNode::Simple("Send".to_string())),
+                    Bound::Trait(TypeNode::Simple("Clone".to_string())),
+                ],
+                default: None,
+            }
+        );
+        assert_eq!(node.generics[0].display(), "T: !Send + Clone");
+        assert_eq!(display_where_clause(&node.where_clause), " where T: !Sync");
+
+        for input in [
+            "pub struct S<T: !> { value: T }",
+            "pub struct S<T: ?> { value: T }",
+            "pub struct S<T: Clone + ! > { value: T }",
+            "pub struct S<T> where T: ? { value: T }",
+        ] {
+            assert!(
+                matches!(Parser::parse(input), Err(ParseError::Invalid(_))),
+                "{}",
+                input
+            );
+            assert!(
+                matches!(Parser::parse_borrowed(input), Err(ParseError::Invalid(_))),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_children_are_direct_only() {
+        let input = r#"
+            pub mod outer {
+                pub mod inner { pub struct Deep {} }
+                pub enum Shape { Empty, Circle(f64), Rect { w: f64, h: f64 } }
+            }
+        "#;
+        let ast = input.parse::<AstNode>().unwrap();
+        let kinds: Vec<&str> = ast.children().into_iter().map(item_kind).collect();
+        assert_eq!(kinds, ["module", "enum"]);
+
+        let shape = ast.children()[1];
+        assert_eq!(shape.children().len(), 2);
+        assert!(shape.children()[0].children().is_empty());
+        assert_eq!(ast.all_nodes().len(), 6);
+    }
+
+    #[test]
+    fn test_generic_param_names() {
+        let ast = "pub struct S<'a, T, const N: usize> { value: &'a [T; N] }"
+            .parse::<AstNode>()
+            .unwrap();
+        assert_eq!(ast.generic_param_names(), ["'a", "T", "N"]);
+
+        let alias = "type Pair<A, B> = (A, B);".parse::<AstNode>().unwrap();
+        assert_eq!(alias.generic_param_names(), ["A", "B"]);
+        let constant = "const MAX: usize = 8;".parse::<AstNode>().unwrap();
+        assert!(constant.generic_param_names().is_empty());
+    }
+
+    #[test]
+    fn test_type_alias_keeps_generic_bounds() {
+        let ast = "pub type Shared<T: Send + 'static> = Arc<T>;"
+            .parse::<AstNode>()
+            .unwrap();
+        let AstNode::TypeAlias(node) = &ast else {
+            panic!("expected a type alias");
+        };
+        assert_eq!(
+            node.generics,
+            [GenericParam::Type {
+                name: "T".to_string(),
+                bounds: vec![
+                    Bound::Trait(TypeNode::Simple("Send".to_string())),
+                    Bound::Lifetime("'static".to_string()),
+                ],
+                default: None,
+            }]
+        );
+        assert_eq!(
+            ast.tree_string(),
+            "- Type Alias: Shared<T: Send + 'static> = Arc<T>\n"
+        );
+    }
+
+    #[test]
+    fn test_field_visibility_round_trips() {
+        let input =
+            "pub struct Point { pub x: i32, pub(crate) y: u8, pub(in crate::geo) z: u8, w: u8 }";
+        let ast = input.parse::<AstNode>().unwrap();
+        let AstNode::Struct(node) = &ast else {
+            panic!("expected a struct");
+        };
+        let fields: Vec<(&Visibility, &str)> = node
+            .fields
+            .iter()
+            .map(|f| (&f.visibility, f.name.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                (&Visibility::Public, "x"),
+                (&Visibility::Crate, "y"),
+                (&Visibility::PubIn("crate::geo".to_string()), "z"),
+                (&Visibility::Private, "w"),
+            ]
+        );
+
+        assert_eq!(
+            ast.tree_string(),
+            "\
+- Struct: Point
+├── Field: pub x: i32
+├── Field: pub(crate) y: u8
+├── Field: pub(in crate::geo) z: u8
+└── Field: w: u8
+"
+        );
+        assert_eq!(
+            ast.to_compact_string_with_width(100),
+            "struct Point { pub x: i32, pub(crate) y: u8, pub(in crate::geo) z: u8, w: u8 }"
+        );
+    }
+
+    #[test]
+    fn test_required_and_provided_methods() {
+        let input = r#"
+            pub trait Greeter {
+                fn name(&self) -> String;
+                fn greet(&self) -> String { format!("Hello, {}!", self.name()) }
+            }
+        "#;
+        let AstNode::Trait(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a trait");
+        };
+        let names = |methods: Vec<&MethodNode>| -> Vec<String> {
+            methods.into_iter().map(|m| m.name.clone()).collect()
+        };
+        assert_eq!(names(node.required_methods()), ["name"]);
+        assert_eq!(names(node.provided_methods()), ["greet"]);
+
+        let borrowed = Parser::parse_borrowed(input).unwrap().to_owned();
+        assert_eq!(borrowed, AstNode::Trait(node));
+    }
+
+    #[test]
+    fn test_parse_impl_and_dyn_trait_types() {
+        let depth = ParseOptions::default().max_depth;
+        let u8 = || TypeNode::Simple("u8".to_string());
+
+        let ty = Parser::parse_type("impl Iterator<Item = u8>", depth).unwrap();
+        assert_eq!(
+            ty,
+            TypeNode::ImplTrait(vec![Bound::Trait(TypeNode::Generic {
+                name: "Iterator".to_string(),
+                args: vec![TypeNode::Simple("Item = u8".to_string())],
+            })])
+        );
+        assert_eq!(ty.display(), "impl Iterator<Item = u8>");
+
+        let ty = Parser::parse_type("Box<dyn Fn(u8) -> u8 + Send>", depth).unwrap();
+        assert_eq!(
+            ty,
+            TypeNode::Generic {
+                name: "Box".to_string(),
+                args: vec![TypeNode::DynTrait(vec![
+                    Bound::Closure {
+                        name: "Fn".to_string(),
+                        params: vec![u8()],
+                        return_type: Some(Box::new(u8())),
+                    },
+                    Bound::Trait(TypeNode::Simple("Send".to_string())),
+                ])],
+            }
+        );
+        // The closure is parenthesized so that `+ Send` does not read as part
+        // of its return type
+        assert_eq!(ty.display(), "Box<dyn (Fn(u8) -> u8) + Send>");
+        assert_eq!(Parser::parse_type(&ty.display(), depth).unwrap(), ty);
+    }
+
+    #[test]
+    fn test_referenced_type_names() {
+        let names = |input: &str| -> Vec<String> {
+            let ty = Parser::parse_type(input, 16).unwrap();
+            ty.referenced_type_names()
+                .into_iter()
+                .map(String::from)
+                .collect()
+        };
+        assert_eq!(
+            names("HashMap<String, Vec<i32>>"),
+            ["HashMap", "String", "Vec", "i32"]
+        );
+        assert_eq!(names("&[Option<u8>; N]"), ["Option", "u8"]);
+        assert_eq!(names("(u8, u8, String)"), ["u8", "String"]);
+        assert_eq!(
+            names("Box<dyn Fn(Event) -> Option<Error> + Send>"),
+            ["Box", "Fn", "Event", "Option", "Error", "Send"]
+        );
+    }
+
+    #[test]
+    fn test_trait_impl_stub() {
+        let input = r#"
+            pub trait Store<T: Clone> where T: Send {
+                fn get(&self, key: &str) -> Option<T>;
+                fn put(&mut self, key: String, value: T);
+                fn load(&mut self, bytes: &[u8]) -> usize;
+                fn len(&self) -> usize { 0 }
+            }
+        "#;
+        let AstNode::Trait(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a trait");
+        };
+
+        let stub = node.impl_stub("Memory");
+        let expected = "\
+impl<T: Clone> Store<T> for Memory where T: Send {
+    fn get(&self, key: &str) -> Option<T> {
+        todo!()
+    }
+
+    fn put(&mut self, key: String, value: T) {
+        todo!()
+    }
+
+    fn load(&mut self, bytes: &[u8]) -> usize {
+        todo!()
+    }
+}
+";
+        assert_eq!(stub, expected);
+
+        let AstNode::Impl(impl_node) = stub.parse::<AstNode>().unwrap() else {
+            panic!("expected an impl");
+        };
+        assert_eq!(impl_node.self_type, TypeNode::Simple("Memory".to_string()));
+        assert_eq!(impl_node.generics, node.generics);
+        assert_eq!(impl_node.where_clause, node.where_clause);
+        let names: Vec<&str> = impl_node
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ImplItem::Method(m) => Some(m.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["get", "put", "load"]);
+    }
+
+    #[test]
+    fn test_parse_hashmap_nested_generic() {
+        let input = "pub struct Index { entries: HashMap<String, Vec<HashMap<u8, (i32, i64)>>> }";
+        let AstNode::Struct(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a struct");
+        };
+        let TypeNode::Generic { name, args } = &*node.fields[0].field_type else {
+            panic!("expected a generic type");
+        };
+        assert_eq!(name, "HashMap");
+        assert_eq!(
+            args,
+            &[
+                TypeNode::Simple("String".to_string()),
+                TypeNode::Generic {
+                    name: "Vec".to_string(),
+                    args: vec![TypeNode::Generic {
+                        name: "HashMap".to_string(),
+                        args: vec![
+                            TypeNode::Simple("u8".to_string()),
+                            TypeNode::Tuple(vec![
+                                TypeNode::Simple("i32".to_string()),
+                                TypeNode::Simple("i64".to_string()),
+                            ]),
+                        ],
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_generic_closing_brackets() {
+        let vec_of = |inner: TypeNode| TypeNode::Generic {
+            name: "Vec".to_string(),
+            args: vec![inner],
+        };
+        let u8 = TypeNode::Simple("u8".to_string());
+
+        let ty = Parser::parse_type("Vec<Vec<u8>>", 16).unwrap();
+        assert_eq!(ty, vec_of(vec_of(u8.clone())));
+        assert_eq!(ty.display(), "Vec<Vec<u8>>");
+
+        let ty = Parser::parse_type("Vec<Vec<Vec<u8>>>", 16).unwrap();
+        assert_eq!(ty, vec_of(vec_of(vec_of(u8))));
+        assert_eq!(ty.display(), "Vec<Vec<Vec<u8>>>");
+    }
+
+    #[test]
+    fn test_bincode_layout() {
+        let layout = |input: &str| input.parse::<AstNode>().unwrap().to_bincode_layout();
+        let pair = |name: &str, ty: &str| (name.to_string(), ty.to_string());
+
+        assert_eq!(
+            layout("pub struct Header { magic: [u8; 4], len: u32, tags: Vec<String> }"),
+            [
+                pair("magic", "[u8; 4]"),
+                pair("len", "u32"),
+                pair("tags", "Vec<String>")
+            ]
         );
+        assert_eq!(
+            layout("pub enum Message { Ping, Data(u16, Vec<u8>), Close { code: u16 } }"),
+            [
+                pair("variant", "u32"),
+                pair("Data.0", "u16"),
+                pair("Data.1", "Vec<u8>"),
+                pair("Close.code", "u16"),
+            ]
+        );
+        assert!(layout("pub trait Codec { fn encode(&self); }").is_empty());
     }
-}
 
-impl VariantNode {
-    fn display_tree_internal(&self, prefix: &str, _is_last: bool) {
-        println!("{}Variant: {}", prefix, self.name);
-        if let Some(associated_data) = &self.associated_data {
-            // Recursively display the associated AstNode
-            associated_data.display_tree_internal(&format!("{}    ", prefix));
-        }
-    }
-}
+    #[test]
+    fn test_struct_is_repr_c_compatible() {
+        let parse_struct = |input: &str| match input.parse::<AstNode>().unwrap() {
+            AstNode::Struct(node) => node,
+            other => panic!("expected a struct, got {:?}", other),
+        };
 
-impl TypeNode {
-    fn display(&self) -> String {
-        match self {
-            TypeNode::Simple(name) => name.clone(),
-            TypeNode::Reference(inner) => format!("&{}", inner.display()),
-            TypeNode::Generic { name, args } => {
-                let args_display: Vec<String> = args.iter().map(|arg| arg.display()).collect();
-                format!("{}<{}>", name, args_display.join(", "))
-            }
-        }
+        let header = parse_struct(
+            "#[repr(C, packed)] pub struct Header { len: u32, data: *const u8, tag: [c_char; 4] }",
+        );
+        assert!(header.is_repr_c_compatible());
+
+        let unmarked = parse_struct("pub struct Header { len: u32 }");
+        assert!(!unmarked.is_repr_c_compatible());
+        let owned = parse_struct("#[repr(C)] pub struct Header { name: String, data: &[u8] }");
+        assert!(!owned.is_repr_c_compatible());
     }
-}
 
-impl ParamNode {
-    fn display_tree_internal(&self, prefix: &str, _is_last: bool) {
-        println!(
-            "{}Param: {}: {}",
-            prefix,
-            self.name,
-            self.param_type.display()
+    #[test]
+    fn test_parse_multiline_where_clause() {
+        let input = "pub struct Pair<T, U>\nwhere\n  T: Clone,\n  U: Send\n    + Sync,\n  for<'a>\n    &'a T: Into<U>,\n{\n    a: T,\n    b: U,\n}";
+        let AstNode::Struct(node) = input.parse::<AstNode>().unwrap() else {
+            panic!("expected a struct");
+        };
+
+        assert_eq!(node.where_clause.len(), 3);
+        assert_eq!(
+            display_where_clause(&node.where_clause),
+            " where T: Clone, U: Send + Sync, for<'a> &'a T: Into<U>"
         );
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
     #[test]
-    fn test_parse_visualizer_trait() {
-        let inputs = vec![
-            r#"pub trait Visualizer {
-                fn visualize(&self, data: &[u8]);
-                fn process(&self, input: &str) -> String;
-            }"#,
-            r#"
-            pub trait Visualizer {
-                fn visualize(&self, data: &[u8]);
-                fn process(&self, input: &str) -> String;
+    fn test_walk_mut_uppercases_field_names() {
+        struct UppercaseFields;
+
+        impl VisitorMut for UppercaseFields {
+            fn visit_field_mut(&mut self, field: &mut FieldNode) {
+                field.name = field.name.to_uppercase();
             }
-            "#,
-            "pub trait Visualizer { fn visualize(&self, data: &[u8]); fn process(&self, input: &str) -> String; }",
-        ];
+        }
 
-        for input in inputs {
-            let expected = AstNode::Trait(TraitNode {
-                name: "Visualizer".to_string(),
-                methods: vec![
-                    MethodNode {
-                        name: "visualize".to_string(),
-                        params: vec![
-                            ParamNode {
-                                name: "&self".to_string(),
-                                param_type: Box::new(TypeNode::Reference(Box::new(TypeNode::Simple("self".to_string())))),
-                            },
-                            ParamNode {
-                                name: "data".to_string(),
-                                param_type: Box::new(TypeNode::Generic {
-                                    name: "[]".to_string(),
-                                    args: vec![TypeNode::Simple("u8".to_string())],
-                                }),
-                            },
-                        ],
-                        return_type: None,
-                    },
-                    MethodNode {
-                        name: "process".to_string(),
-                        params: vec![
-                            ParamNode {
-                                name: "&self".to_string(),
-                                param_type: Box::new(TypeNode::Reference(Box::new(TypeNode::Simple("self".to_string())))),
-                            },
-                            ParamNode {
-                                name: "input".to_string(),
-                                param_type: Box::new(TypeNode::Reference(Box::new(TypeNode::Simple("str".to_string())))),
-                            },
-                        ],
-                        return_type: Some(Box::new(TypeNode::Simple("String".to_string()))),
-                    },
-                ],
-            });
+        let mut ast = "pub struct Point { x: f64, y: f64, label: Option<String> }"
+            .parse::<AstNode>()
+            .unwrap();
+        ast.walk_mut(&mut UppercaseFields);
 
-            assert_eq!(input.parse::<AstNode>().unwrap(), expected);
-        }
+        let AstNode::Struct(node) = &ast else {
+            panic!("expected a struct");
+        };
+        let names: Vec<&str> = node.fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["X", "Y", "LABEL"]);
     }
 
     #[test]
-    fn test_parse_struct() {
-        let input = r#"
-            pub struct Point {
-                x: f64,
-                y: f64,
-                label: String,
+    fn test_walk_mut_substitutes_nested_types() {
+        struct NarrowFloats;
+
+        impl VisitorMut for NarrowFloats {
+            fn visit_type_mut(&mut self, ty: &mut TypeNode) {
+                if *ty == TypeNode::Simple("f64".to_string()) {
+                    *ty = TypeNode::Simple("f32".to_string());
+                }
             }
-        "#;
+        }
 
-        let expected = AstNode::Struct(StructNode {
-            name: "Point".to_string(),
-            fields: vec![
-                FieldNode {
-                    name: "x".to_string(),
-                    field_type: Box::new(TypeNode::Simple("f64".to_string())),
-                },
-                FieldNode {
-                    name: "y".to_string(),
-                    field_type: Box::new(TypeNode::Simple("f64".to_string())),
-                },
-                FieldNode {
-                    name: "label".to_string(),
-                    field_type: Box::new(TypeNode::Simple("String".to_string())),
-                },
-            ],
-        });
+        let mut ast =
+            "pub mod shapes { pub enum Shape { Circle(f64), Path { points: Vec<(f64, f64)> } } }"
+                .parse::<AstNode>()
+                .unwrap();
+        walk_mut(&mut ast, &mut NarrowFloats);
 
-        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+        let expected =
+            "pub mod shapes { pub enum Shape { Circle(f32), Path { points: Vec<(f32, f32)> } } }"
+                .parse::<AstNode>()
+                .unwrap();
+        assert_eq!(ast, expected);
     }
 
     #[test]
-    fn test_parse_enum() {
-        let input = r#"
-            pub enum Color {
-                Red,
-                Green,
-                Blue,
+    fn test_type_to_c_type() {
+        let c_type = |input: &str| {
+            Parser::parse_type(input, ParseOptions::default().max_depth)
+                .unwrap()
+                .to_c_type()
+        };
+
+        assert_eq!(c_type("i32").as_deref(), Some("int32_t"));
+        assert_eq!(c_type("u8").as_deref(), Some("uint8_t"));
+        assert_eq!(c_type("*const u8").as_deref(), Some("const uint8_t*"));
+        assert_eq!(c_type("*mut std::ffi::c_char").as_deref(), Some("char*"));
+        assert_eq!(c_type("*const *mut u8").as_deref(), Some("uint8_t* const*"));
+        assert_eq!(c_type("[[f32; 4]; 2]").as_deref(), Some("float[2][4]"));
+        assert_eq!(c_type("String"), None);
+        assert_eq!(c_type("Vec<u8>"), None);
+        assert_eq!(c_type("*const String"), None);
+    }
+
+    #[test]
+    fn test_parse_raw_pointer_types() {
+        let depth = ParseOptions::default().max_depth;
+        let u8 = || Box::new(TypeNode::Simple("u8".to_string()));
+
+        let ty = Parser::parse_type("*mut *const u8", depth).unwrap();
+        assert_eq!(
+            ty,
+            TypeNode::RawPointer {
+                mutable: true,
+                inner: Box::new(TypeNode::RawPointer {
+                    mutable: false,
+                    inner: u8(),
+                }),
             }
-        "#;
+        );
+        assert_eq!(ty.display(), "*mut *const u8");
+        assert_eq!(ty.depth(), 3);
 
-        let expected = AstNode::Enum(EnumNode {
-            name: "Color".to_string(),
-            variants: vec![
-                VariantNode {
-                    name: "Red".to_string(),
-                    associated_data: None,
-                },
-                VariantNode {
-                    name: "Green".to_string(),
-                    associated_data: None,
-                },
-                VariantNode {
-                    name: "Blue".to_string(),
-                    associated_data: None,
-                },
-            ],
-        });
+        let ty = Parser::parse_type("*const [u8; 4]", depth).unwrap();
+        assert_eq!(ty.display(), "*const [u8; 4]");
+        let ty = Parser::parse_type("*mut (dyn Read + Send)", depth).unwrap();
+        assert_eq!(ty.display(), "*mut (dyn Read + Send)");
+        assert!(Parser::parse_type("*u8", depth).is_err());
+        assert!(Parser::parse_type("*constant", depth).is_err());
 
-        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+        let mut ty = Parser::parse_type("*const Node", depth).unwrap();
+        assert_eq!(ty.rename("Node", "Leaf"), 1);
+        assert_eq!(ty.display(), "*const Leaf");
     }
 
     #[test]
-    fn test_parse_enum_with_associated_data() {
-        let input = r#"
-            pub enum Message {
-                Quit,
-                Move { x: i32, y: i32 },
-                Write(String),
-                ChangeColor(i32, i32, i32),
-            }
-        "#;
+    fn test_params_without_self() {
+        let method =
+            Parser::parse_method_signature("fn insert(&mut self, key: String, value: u32)")
+                .unwrap();
+        let names: Vec<&str> = method
+            .params_without_self()
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["key", "value"]);
 
-        let expected = AstNode::Enum(EnumNode {
-            name: "Message".to_string(),
-            variants: vec![
-                VariantNode {
-                    name: "Quit".to_string(),
-                    associated_data: None,
-                },
-                VariantNode {
-                    name: "Move".to_string(),
-                    associated_data: Some(Box::new(AstNode::Struct(StructNode {
-                        name: "".to_string(), // Anonymous struct
-                        fields: vec![
-                            FieldNode {
-                                name: "x".to_string(),
-                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
-                            },
-                            FieldNode {
-                                name: "y".to_string(),
-                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
-                            },
-                        ],
-                    }))),
-                },
-                VariantNode {
-                    name: "Write".to_string(),
-                    associated_data: Some(Box::new(AstNode::Struct(StructNode {
-                        name: "".to_string(), // Tuple struct equivalent
-                        fields: vec![FieldNode {
-                            name: "0".to_string(),
-                            field_type: Box::new(TypeNode::Simple("String".to_string())),
-                        }],
-                    }))),
-                },
-                VariantNode {
-                    name: "ChangeColor".to_string(),
-                    associated_data: Some(Box::new(AstNode::Struct(StructNode {
-                        name: "".to_string(), // Tuple struct equivalent
-                        fields: vec![
-                            FieldNode {
-                                name: "0".to_string(),
-                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
-                            },
-                            FieldNode {
-                                name: "1".to_string(),
-                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
-                            },
-                            FieldNode {
-                                name: "2".to_string(),
-                                field_type: Box::new(TypeNode::Simple("i32".to_string())),
-                            },
-                        ],
-                    }))),
-                },
-            ],
-        });
+        let constructor =
+            Parser::parse_method_signature("fn new(capacity: usize) -> Self").unwrap();
+        assert_eq!(
+            constructor.params_without_self(),
+            constructor.params.as_slice()
+        );
+    }
 
-        assert_eq!(input.parse::<AstNode>().unwrap(), expected);
+    #[test]
+    fn test_multiline_attributes() {
+        let input = "#[derive(\n    Debug,\n    serde::\n        Serialize,\n)]\n\n#[serde(\n    rename_all = \"camelCase\"\n)]\npub struct Point {\n    x: f64,\n}";
+        let ast = input.parse::<AstNode>().unwrap();
+
+        assert_eq!(ast.derives(), vec!["Debug", "serde::Serialize"]);
+        let AstNode::Struct(node) = &ast else {
+            panic!("expected a struct");
+        };
+        assert_eq!(node.attributes.len(), 2);
+        assert_eq!(
+            node.attributes[1].args.as_deref(),
+            Some("rename_all = \"camelCase\"")
+        );
     }
 
     #[test]
-    fn test_invalid_input() {
-        let input = "fn standalone_function() {}";
-        assert!(input.parse::<AstNode>().is_err());
+    fn test_method_return_type_name() {
+        let return_type_name = |signature: &str| {
+            let method = Parser::parse_method_signature(signature).unwrap();
+            method.return_type_name().map(str::to_string)
+        };
+
+        assert_eq!(
+            return_type_name("fn find(&self) -> Option<String>").as_deref(),
+            Some("Option")
+        );
+        assert_eq!(
+            return_type_name("fn name(&self) -> &str").as_deref(),
+            Some("str")
+        );
+        assert_eq!(return_type_name("fn pair(&self) -> (u8, u8)"), None);
+        assert_eq!(return_type_name("fn clear(&mut self)"), None);
     }
 
     #[test]
-    fn test_parse_struct_with_invalid_field() {
-        let input = r#"
-            pub struct InvalidStruct {
-                x f64, // Missing colon
-                y: f64,
-            }
-        "#;
+    fn test_resolve_type_alias() {
+        let uses: Vec<UseNode> = [
+            "use std::collections::HashMap as Map;",
+            "use crate::{models::User, net::{self, Socket as _}};",
+            "use super::shapes::*;",
+        ]
+        .iter()
+        .map(|input| match input.parse::<AstNode>().unwrap() {
+            AstNode::Use(node) => node,
+            other => panic!("expected a use declaration, got {:?}", other),
+        })
+        .collect();
 
-        assert!(input.parse::<AstNode>().is_err());
+        assert_eq!(
+            resolve_type_alias(&uses, "Map").as_deref(),
+            Some("std::collections::HashMap")
+        );
+        assert_eq!(
+            resolve_type_alias(&uses, "User").as_deref(),
+            Some("crate::models::User")
+        );
+        assert_eq!(
+            resolve_type_alias(&uses, "net::Error").as_deref(),
+            Some("crate::net::Error")
+        );
+        assert_eq!(
+            resolve_type_alias(&uses, "Circle").as_deref(),
+            Some("super::shapes::Circle")
+        );
+        assert_eq!(resolve_type_alias(&uses[..2], "Circle"), None);
     }
 
     #[test]
-    fn test_parse_trait_with_invalid_method() {
-        let input = r#"
-            pub trait InvalidTrait {
-                fn invalid_method(&self data: &[u8]); // Missing comma
+    fn test_parse_generic_arguments_without_spaces() {
+        let compact =
+            "pub struct Index<K:Hash+Eq,V>{entries:HashMap<String,Vec<u8>>,pairs:Vec<(K,V)>}";
+        let spaced =
+            "pub struct Index<K: Hash + Eq, V> { entries: HashMap<String, Vec<u8>>, pairs: Vec<(K, V)> }";
+        assert_eq!(
+            compact.parse::<AstNode>().unwrap(),
+            spaced.parse::<AstNode>().unwrap()
+        );
+
+        let depth = ParseOptions::default().max_depth;
+        assert_eq!(
+            Parser::parse_type("HashMap<String,Vec<u8>>", depth).unwrap(),
+            Parser::parse_type("HashMap<String, Vec<u8>>", depth).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_generic_param_defaults() {
+        let input =
+            "pub struct Cache<K, V: Clone = Vec<u8>, S = RandomState> { map: HashMap<K, V, S> }";
+        let ast = input.parse::<AstNode>().unwrap();
+        let AstNode::Struct(node) = &ast else {
+            panic!("expected a struct");
+        };
+
+        assert_eq!(
+            node.generics[1],
+            GenericParam::Type {
+                name: "V".to_string(),
+                bounds: vec![Bound::Trait(TypeNode::Simple("Clone".to_string()))],
+                default: Some(TypeNode::Generic {
+                    name: "Vec".to_string(),
+                    args: vec![TypeNode::Simple("u8".to_string())],
+                }),
             }
-        "#;
+        );
+        assert_eq!(
+            display_generics(&node.generics),
+            "<K, V: Clone = Vec<u8>, S = RandomState>"
+        );
+        assert_eq!(Parser::parse_borrowed(input).unwrap().to_owned(), ast);
 
-        assert!(input.parse::<AstNode>().is_err());
+        assert!("pub struct Ref<'a = 'static> { x: &'a u8 }"
+            .parse::<AstNode>()
+            .is_err());
     }
-}
\ No newline at end of file
+}